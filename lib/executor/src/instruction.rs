@@ -0,0 +1,350 @@
+use anyhow::{bail, Result};
+use solana_program::instruction::{AccountMeta, CompiledInstruction, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+use crate::constants::*;
+use crate::leg::{AccountReader, Leg};
+
+pub const SWAP_DISCRIMINATOR: u8 = 26;
+/// Discriminator, minimum profit, compute unit limit, no-failure flag, a reserved u16 and
+/// the flashloan flag
+pub const SWAP_DATA_LEN: usize = 1 + 8 + 8 + 1 + 2 + 1;
+
+/// Token account holding the executor's flashloaned SOL
+pub fn flashloan_vault_token_account() -> Pubkey {
+    Pubkey::find_program_address(&[b"vault_token_account", SOL_MINT.as_ref()], &crate::ID).0
+}
+
+/// The executor's arbitrage instruction. See
+/// https://docs.solanamevbot.com/home/onchain-bot/onchain-program for the program side.
+///
+/// Build one with `new` and the chained setters, then turn it into an `Instruction`. The
+/// decoders do the reverse for instructions found in existing transactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutorInstruction {
+    pub wallet: Pubkey,
+    pub wallet_sol_account: Pubkey,
+    pub mint: Pubkey,
+    /// SPL Token or Token 2022, whichever owns `mint`
+    pub token_program: Pubkey,
+    pub wallet_token_account: Pubkey,
+    /// Lamports the round trip must gain, otherwise the executor fails or, in no-failure
+    /// mode, skips the trade
+    pub minimum_profit: u64,
+    pub compute_unit_limit: u64,
+    /// Succeed without trading when no profitable route is found
+    pub no_failure_mode: bool,
+    pub use_flashloan: bool,
+    pub legs: Vec<Leg>,
+}
+
+impl ExecutorInstruction {
+    pub fn new(
+        wallet: Pubkey,
+        wallet_sol_account: Pubkey,
+        mint: Pubkey,
+        token_program: Pubkey,
+        wallet_token_account: Pubkey,
+    ) -> Self {
+        Self {
+            wallet,
+            wallet_sol_account,
+            mint,
+            token_program,
+            wallet_token_account,
+            minimum_profit: 0,
+            compute_unit_limit: 0,
+            no_failure_mode: false,
+            use_flashloan: false,
+            legs: Vec::new(),
+        }
+    }
+
+    pub fn minimum_profit(mut self, minimum_profit: u64) -> Self {
+        self.minimum_profit = minimum_profit;
+        self
+    }
+
+    pub fn compute_unit_limit(mut self, compute_unit_limit: u64) -> Self {
+        self.compute_unit_limit = compute_unit_limit;
+        self
+    }
+
+    pub fn no_failure_mode(mut self, no_failure_mode: bool) -> Self {
+        self.no_failure_mode = no_failure_mode;
+        self
+    }
+
+    pub fn flashloan(mut self, use_flashloan: bool) -> Self {
+        self.use_flashloan = use_flashloan;
+        self
+    }
+
+    pub fn leg(mut self, leg: Leg) -> Self {
+        self.legs.push(leg);
+        self
+    }
+
+    pub fn legs(mut self, legs: impl IntoIterator<Item = Leg>) -> Self {
+        self.legs.extend(legs);
+        self
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(SWAP_DATA_LEN);
+        data.push(SWAP_DISCRIMINATOR);
+        data.extend_from_slice(&self.minimum_profit.to_le_bytes());
+        data.extend_from_slice(&self.compute_unit_limit.to_le_bytes());
+        data.push(u8::from(self.no_failure_mode));
+        // Reserved, the program expects 0
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.push(u8::from(self.use_flashloan));
+        data
+    }
+
+    pub fn account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.wallet, true),
+            AccountMeta::new_readonly(SOL_MINT, false),
+            AccountMeta::new(FEE_COLLECTOR, false),
+            AccountMeta::new(self.wallet_sol_account, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ];
+        if self.use_flashloan {
+            accounts.push(AccountMeta::new_readonly(FLASHLOAN_MARKET, false));
+            accounts.push(AccountMeta::new(flashloan_vault_token_account(), false));
+        }
+        accounts.push(AccountMeta::new_readonly(self.mint, false));
+        accounts.push(AccountMeta::new_readonly(self.token_program, false));
+        accounts.push(AccountMeta::new(self.wallet_token_account, false));
+        for leg in &self.legs {
+            leg.append_account_metas(&mut accounts);
+        }
+        accounts
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: crate::ID,
+            accounts: self.account_metas(),
+            data: self.data(),
+        }
+    }
+
+    /// Decodes the instruction from its account keys, in instruction order, and data
+    pub fn decode(accounts: &[Pubkey], data: &[u8]) -> Result<Self> {
+        if data.len() < SWAP_DATA_LEN {
+            bail!("Invalid data length for executor instruction");
+        }
+        if data[0] != SWAP_DISCRIMINATOR {
+            bail!("Invalid discriminator {} for executor instruction", data[0]);
+        }
+        let minimum_profit = u64::from_le_bytes(data[1..9].try_into().unwrap());
+        let compute_unit_limit = u64::from_le_bytes(data[9..17].try_into().unwrap());
+        let no_failure_mode = data[17] != 0;
+        let use_flashloan = data[20] != 0;
+
+        let mut reader = AccountReader::new(accounts);
+        let wallet = reader.next("wallet")?;
+        reader.expect(SOL_MINT, "SOL mint")?;
+        reader.expect(FEE_COLLECTOR, "fee collector")?;
+        let wallet_sol_account = reader.next("wallet SOL account")?;
+        reader.expect(TOKEN_PROGRAM_ID, "token program")?;
+        reader.expect(system_program::ID, "system program")?;
+        reader.expect(ASSOCIATED_TOKEN_PROGRAM_ID, "associated token program")?;
+        if use_flashloan {
+            reader.expect(FLASHLOAN_MARKET, "flashloan market")?;
+            reader.expect(
+                flashloan_vault_token_account(),
+                "flashloan vault token account",
+            )?;
+        }
+        let mint = reader.next("mint")?;
+        let token_program = reader.next("mint token program")?;
+        let wallet_token_account = reader.next("wallet token account")?;
+
+        let mut legs = Vec::new();
+        while !reader.is_empty() {
+            let (leg, len) = Leg::decode(reader.remaining())?;
+            reader.offset += len;
+            legs.push(leg);
+        }
+
+        Ok(Self {
+            wallet,
+            wallet_sol_account,
+            mint,
+            token_program,
+            wallet_token_account,
+            minimum_profit,
+            compute_unit_limit,
+            no_failure_mode,
+            use_flashloan,
+            legs,
+        })
+    }
+
+    pub fn from_instruction(instruction: &Instruction) -> Result<Self> {
+        if instruction.program_id != crate::ID {
+            bail!("Not an executor instruction: {}", instruction.program_id);
+        }
+        let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|a| a.pubkey).collect();
+        Self::decode(&accounts, &instruction.data)
+    }
+
+    /// Decodes a compiled instruction against the message's account keys. For v0 messages
+    /// the keys must include the addresses loaded from lookup tables, writable ones first,
+    /// as listed in the transaction metadata.
+    pub fn from_compiled(
+        account_keys: &[Pubkey],
+        instruction: &CompiledInstruction,
+    ) -> Result<Self> {
+        let key = |index: u8| {
+            account_keys
+                .get(usize::from(index))
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Account index {} out of range", index))
+        };
+        if key(instruction.program_id_index)? != crate::ID {
+            bail!("Not an executor instruction");
+        }
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|index| key(*index))
+            .collect::<Result<Vec<_>>>()?;
+        Self::decode(&accounts, &instruction.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> Pubkey {
+        Pubkey::new_from_array([seed; 32])
+    }
+
+    fn executor_instruction() -> ExecutorInstruction {
+        ExecutorInstruction::new(key(1), key(2), key(3), TOKEN_PROGRAM_ID, key(4))
+            .minimum_profit(5_000)
+            .compute_unit_limit(600_000)
+            .no_failure_mode(true)
+            .flashloan(true)
+            .leg(Leg::Pump {
+                pool: key(10),
+                token_vault: key(11),
+                sol_vault: key(12),
+                fee_token_wallet: key(13),
+                coin_creator_vault_ata: key(14),
+                coin_creator_vault_authority: key(15),
+            })
+            .leg(Leg::Dlmm {
+                memo_program: Some(MEMO_PROGRAM_ID),
+                pair: key(20),
+                token_vault: key(21),
+                sol_vault: key(22),
+                oracle: key(23),
+                bin_arrays: vec![key(24), key(25), key(26)],
+            })
+            .leg(Leg::Whirlpool {
+                memo_program: None,
+                pool: key(30),
+                oracle: key(31),
+                x_vault: key(32),
+                y_vault: key(33),
+                tick_arrays: vec![key(34), key(35)],
+            })
+            .leg(Leg::Vertigo {
+                pool: key(40),
+                pool_owner: key(41),
+                token_x_vault: key(42),
+                token_sol_vault: key(43),
+            })
+    }
+
+    #[test]
+    fn test_data_layout() {
+        let data = executor_instruction().data();
+        assert_eq!(data.len(), SWAP_DATA_LEN);
+        assert_eq!(data[0], 26);
+        assert_eq!(data[1..9], 5_000u64.to_le_bytes());
+        assert_eq!(data[9..17], 600_000u64.to_le_bytes());
+        assert_eq!(data[17..], [1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_account_order() {
+        let metas = executor_instruction().account_metas();
+        let keys: Vec<Pubkey> = metas.iter().map(|m| m.pubkey).collect();
+
+        assert!(metas[0].is_signer && !metas[0].is_writable);
+        assert_eq!(keys[7], FLASHLOAN_MARKET);
+        assert_eq!(keys[8], flashloan_vault_token_account());
+        assert_eq!(keys[9..12], [key(3), TOKEN_PROGRAM_ID, key(4)]);
+
+        // Pump: program, three shared accounts, then the read-only pool
+        assert_eq!(keys[12], PUMP_PROGRAM_ID);
+        assert_eq!(keys[16], key(10));
+        assert!(!metas[16].is_writable);
+        assert!(metas[17].is_writable);
+        assert_eq!(keys[21], key(15));
+        assert!(!metas[21].is_writable);
+
+        // DLMM: the memo program sits between the event authority and the pair
+        assert_eq!(
+            keys[22..27],
+            [
+                DLMM_PROGRAM_ID,
+                DLMM_EVENT_AUTHORITY,
+                MEMO_PROGRAM_ID,
+                key(20),
+                key(21)
+            ]
+        );
+        assert_eq!(keys[29..32], [key(24), key(25), key(26)]);
+        assert_eq!(keys[32], WHIRLPOOL_PROGRAM_ID);
+        assert_eq!(keys.len(), 32 + 7 + 5);
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let original = executor_instruction();
+        let decoded = ExecutorInstruction::from_instruction(&original.instruction()).unwrap();
+        assert_eq!(decoded, original);
+
+        // Compiled against a shuffled key table, as in a real message
+        let instruction = original.instruction();
+        let mut account_keys: Vec<Pubkey> = instruction.accounts.iter().map(|a| a.pubkey).collect();
+        account_keys.push(crate::ID);
+        account_keys.reverse();
+        let index = |key: &Pubkey| account_keys.iter().position(|k| k == key).unwrap() as u8;
+        let compiled = CompiledInstruction {
+            program_id_index: index(&crate::ID),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|a| index(&a.pubkey))
+                .collect(),
+            data: instruction.data.clone(),
+        };
+        let decoded = ExecutorInstruction::from_compiled(&account_keys, &compiled).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_leg() {
+        let instruction = executor_instruction().flashloan(false).instruction();
+        let mut accounts: Vec<Pubkey> = instruction.accounts.iter().map(|a| a.pubkey).collect();
+        accounts.push(key(99));
+        assert!(ExecutorInstruction::decode(&accounts, &instruction.data).is_err());
+
+        let mut data = instruction.data.clone();
+        data[0] = 1;
+        accounts.pop();
+        assert!(ExecutorInstruction::decode(&accounts, &data).is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use anyhow::{anyhow, bail, Result};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+
+use crate::constants::*;
+
+/// One pool the executor may route through, with the accounts it needs for that pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Leg {
+    RaydiumAmm {
+        pool: Pubkey,
+        token_vault: Pubkey,
+        sol_vault: Pubkey,
+    },
+    RaydiumCp {
+        pool: Pubkey,
+        amm_config: Pubkey,
+        token_vault: Pubkey,
+        sol_vault: Pubkey,
+        observation: Pubkey,
+    },
+    Pump {
+        pool: Pubkey,
+        token_vault: Pubkey,
+        sol_vault: Pubkey,
+        fee_token_wallet: Pubkey,
+        coin_creator_vault_ata: Pubkey,
+        coin_creator_vault_authority: Pubkey,
+    },
+    Dlmm {
+        memo_program: Option<Pubkey>,
+        pair: Pubkey,
+        token_vault: Pubkey,
+        sol_vault: Pubkey,
+        oracle: Pubkey,
+        bin_arrays: Vec<Pubkey>,
+    },
+    Whirlpool {
+        memo_program: Option<Pubkey>,
+        pool: Pubkey,
+        oracle: Pubkey,
+        x_vault: Pubkey,
+        y_vault: Pubkey,
+        tick_arrays: Vec<Pubkey>,
+    },
+    RaydiumClmm {
+        memo_program: Option<Pubkey>,
+        pool: Pubkey,
+        amm_config: Pubkey,
+        observation_state: Pubkey,
+        bitmap_extension: Pubkey,
+        x_vault: Pubkey,
+        y_vault: Pubkey,
+        tick_arrays: Vec<Pubkey>,
+    },
+    MeteoraDamm {
+        pool: Pubkey,
+        token_x_vault: Pubkey,
+        token_sol_vault: Pubkey,
+        token_x_token_vault: Pubkey,
+        token_sol_token_vault: Pubkey,
+        token_x_lp_mint: Pubkey,
+        token_sol_lp_mint: Pubkey,
+        token_x_pool_lp: Pubkey,
+        token_sol_pool_lp: Pubkey,
+        admin_token_fee_x: Pubkey,
+        admin_token_fee_sol: Pubkey,
+    },
+    MeteoraDammV2 {
+        pool: Pubkey,
+        token_x_vault: Pubkey,
+        token_sol_vault: Pubkey,
+    },
+    Solfi {
+        pool: Pubkey,
+        token_x_vault: Pubkey,
+        token_sol_vault: Pubkey,
+    },
+    Vertigo {
+        pool: Pubkey,
+        pool_owner: Pubkey,
+        token_x_vault: Pubkey,
+        token_sol_vault: Pubkey,
+    },
+}
+
+/// Programs that open a leg in the account list
+pub const LEG_PROGRAM_IDS: [Pubkey; 10] = [
+    RAYDIUM_PROGRAM_ID,
+    RAYDIUM_CP_PROGRAM_ID,
+    PUMP_PROGRAM_ID,
+    DLMM_PROGRAM_ID,
+    WHIRLPOOL_PROGRAM_ID,
+    RAYDIUM_CLMM_PROGRAM_ID,
+    DAMM_PROGRAM_ID,
+    DAMM_V2_PROGRAM_ID,
+    SOLFI_PROGRAM_ID,
+    VERTIGO_PROGRAM_ID,
+];
+
+pub fn is_leg_program(key: &Pubkey) -> bool {
+    LEG_PROGRAM_IDS.contains(key)
+}
+
+impl Leg {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Leg::RaydiumAmm { .. } => RAYDIUM_PROGRAM_ID,
+            Leg::RaydiumCp { .. } => RAYDIUM_CP_PROGRAM_ID,
+            Leg::Pump { .. } => PUMP_PROGRAM_ID,
+            Leg::Dlmm { .. } => DLMM_PROGRAM_ID,
+            Leg::Whirlpool { .. } => WHIRLPOOL_PROGRAM_ID,
+            Leg::RaydiumClmm { .. } => RAYDIUM_CLMM_PROGRAM_ID,
+            Leg::MeteoraDamm { .. } => DAMM_PROGRAM_ID,
+            Leg::MeteoraDammV2 { .. } => DAMM_V2_PROGRAM_ID,
+            Leg::Solfi { .. } => SOLFI_PROGRAM_ID,
+            Leg::Vertigo { .. } => VERTIGO_PROGRAM_ID,
+        }
+    }
+
+    /// The pool (or DLMM pair) this leg trades against
+    pub fn pool(&self) -> Pubkey {
+        match self {
+            Leg::RaydiumAmm { pool, .. }
+            | Leg::RaydiumCp { pool, .. }
+            | Leg::Pump { pool, .. }
+            | Leg::Whirlpool { pool, .. }
+            | Leg::RaydiumClmm { pool, .. }
+            | Leg::MeteoraDamm { pool, .. }
+            | Leg::MeteoraDammV2 { pool, .. }
+            | Leg::Solfi { pool, .. }
+            | Leg::Vertigo { pool, .. } => *pool,
+            Leg::Dlmm { pair, .. } => *pair,
+        }
+    }
+
+    /// Appends the leg's accounts in the order the executor reads them, starting with the
+    /// DEX program
+    pub fn append_account_metas(&self, accounts: &mut Vec<AccountMeta>) {
+        let readonly = AccountMeta::new_readonly;
+        let writable = |key: &Pubkey| AccountMeta::new(*key, false);
+        accounts.push(readonly(self.program_id(), false));
+        match self {
+            Leg::RaydiumAmm {
+                pool,
+                token_vault,
+                sol_vault,
+            } => {
+                accounts.push(readonly(RAYDIUM_AUTHORITY, false));
+                accounts.extend([pool, token_vault, sol_vault].map(writable));
+            }
+            Leg::RaydiumCp {
+                pool,
+                amm_config,
+                token_vault,
+                sol_vault,
+                observation,
+            } => {
+                accounts.push(readonly(RAYDIUM_CP_AUTHORITY, false));
+                accounts.push(writable(pool));
+                accounts.push(readonly(*amm_config, false));
+                accounts.extend([token_vault, sol_vault, observation].map(writable));
+            }
+            Leg::Pump {
+                pool,
+                token_vault,
+                sol_vault,
+                fee_token_wallet,
+                coin_creator_vault_ata,
+                coin_creator_vault_authority,
+            } => {
+                accounts.push(readonly(PUMP_GLOBAL_CONFIG, false));
+                accounts.push(readonly(PUMP_EVENT_AUTHORITY, false));
+                accounts.push(readonly(PUMP_FEE_WALLET, false));
+                accounts.push(readonly(*pool, false));
+                accounts.extend(
+                    [
+                        token_vault,
+                        sol_vault,
+                        fee_token_wallet,
+                        coin_creator_vault_ata,
+                    ]
+                    .map(writable),
+                );
+                accounts.push(readonly(*coin_creator_vault_authority, false));
+            }
+            Leg::Dlmm {
+                memo_program,
+                pair,
+                token_vault,
+                sol_vault,
+                oracle,
+                bin_arrays,
+            } => {
+                accounts.push(writable(&DLMM_EVENT_AUTHORITY));
+                if let Some(memo_program) = memo_program {
+                    accounts.push(readonly(*memo_program, false));
+                }
+                accounts.extend([pair, token_vault, sol_vault, oracle].map(writable));
+                accounts.extend(bin_arrays.iter().map(writable));
+            }
+            Leg::Whirlpool {
+                memo_program,
+                pool,
+                oracle,
+                x_vault,
+                y_vault,
+                tick_arrays,
+            } => {
+                if let Some(memo_program) = memo_program {
+                    accounts.push(readonly(*memo_program, false));
+                }
+                accounts.extend([pool, oracle, x_vault, y_vault].map(writable));
+                accounts.extend(tick_arrays.iter().map(writable));
+            }
+            Leg::RaydiumClmm {
+                memo_program,
+                pool,
+                amm_config,
+                observation_state,
+                bitmap_extension,
+                x_vault,
+                y_vault,
+                tick_arrays,
+            } => {
+                if let Some(memo_program) = memo_program {
+                    accounts.push(readonly(*memo_program, false));
+                }
+                accounts.push(writable(pool));
+                accounts.push(readonly(*amm_config, false));
+                accounts
+                    .extend([observation_state, bitmap_extension, x_vault, y_vault].map(writable));
+                accounts.extend(tick_arrays.iter().map(writable));
+            }
+            Leg::MeteoraDamm {
+                pool,
+                token_x_vault,
+                token_sol_vault,
+                token_x_token_vault,
+                token_sol_token_vault,
+                token_x_lp_mint,
+                token_sol_lp_mint,
+                token_x_pool_lp,
+                token_sol_pool_lp,
+                admin_token_fee_x,
+                admin_token_fee_sol,
+            } => {
+                accounts.push(readonly(VAULT_PROGRAM_ID, false));
+                accounts.extend(
+                    [
+                        pool,
+                        token_x_vault,
+                        token_sol_vault,
+                        token_x_token_vault,
+                        token_sol_token_vault,
+                        token_x_lp_mint,
+                        token_sol_lp_mint,
+                        token_x_pool_lp,
+                        token_sol_pool_lp,
+                        admin_token_fee_x,
+                        admin_token_fee_sol,
+                    ]
+                    .map(writable),
+                );
+            }
+            Leg::MeteoraDammV2 {
+                pool,
+                token_x_vault,
+                token_sol_vault,
+            } => {
+                accounts.push(readonly(DAMM_V2_EVENT_AUTHORITY, false));
+                accounts.push(readonly(DAMM_V2_POOL_AUTHORITY, false));
+                accounts.extend([pool, token_x_vault, token_sol_vault].map(writable));
+            }
+            Leg::Solfi {
+                pool,
+                token_x_vault,
+                token_sol_vault,
+            } => {
+                accounts.push(readonly(SYSVAR_INSTRUCTIONS_ID, false));
+                accounts.extend([pool, token_x_vault, token_sol_vault].map(writable));
+            }
+            Leg::Vertigo {
+                pool,
+                pool_owner,
+                token_x_vault,
+                token_sol_vault,
+            } => {
+                accounts.push(writable(pool));
+                accounts.push(readonly(*pool_owner, false));
+                accounts.extend([token_x_vault, token_sol_vault].map(writable));
+            }
+        }
+    }
+
+    /// Decodes the leg opening at the front of `accounts`. Returns the leg and the number of
+    /// accounts it spans. Bin and tick arrays run until the next leg's program or the end.
+    pub fn decode(accounts: &[Pubkey]) -> Result<(Leg, usize)> {
+        let mut reader = AccountReader::new(accounts);
+        let program_id = reader.next("leg program")?;
+        let leg = match program_id {
+            RAYDIUM_PROGRAM_ID => {
+                reader.expect(RAYDIUM_AUTHORITY, "Raydium authority")?;
+                Leg::RaydiumAmm {
+                    pool: reader.next("pool")?,
+                    token_vault: reader.next("token vault")?,
+                    sol_vault: reader.next("SOL vault")?,
+                }
+            }
+            RAYDIUM_CP_PROGRAM_ID => {
+                reader.expect(RAYDIUM_CP_AUTHORITY, "Raydium CP authority")?;
+                Leg::RaydiumCp {
+                    pool: reader.next("pool")?,
+                    amm_config: reader.next("AMM config")?,
+                    token_vault: reader.next("token vault")?,
+                    sol_vault: reader.next("SOL vault")?,
+                    observation: reader.next("observation")?,
+                }
+            }
+            PUMP_PROGRAM_ID => {
+                reader.expect(PUMP_GLOBAL_CONFIG, "Pump global config")?;
+                reader.expect(PUMP_EVENT_AUTHORITY, "Pump event authority")?;
+                reader.expect(PUMP_FEE_WALLET, "Pump fee wallet")?;
+                Leg::Pump {
+                    pool: reader.next("pool")?,
+                    token_vault: reader.next("token vault")?,
+                    sol_vault: reader.next("SOL vault")?,
+                    fee_token_wallet: reader.next("fee token wallet")?,
+                    coin_creator_vault_ata: reader.next("coin creator vault ATA")?,
+                    coin_creator_vault_authority: reader.next("coin creator vault authority")?,
+                }
+            }
+            DLMM_PROGRAM_ID => {
+                reader.expect(DLMM_EVENT_AUTHORITY, "DLMM event authority")?;
+                Leg::Dlmm {
+                    memo_program: reader.memo_program(),
+                    pair: reader.next("pair")?,
+                    token_vault: reader.next("token vault")?,
+                    sol_vault: reader.next("SOL vault")?,
+                    oracle: reader.next("oracle")?,
+                    bin_arrays: reader.rest_of_leg(),
+                }
+            }
+            WHIRLPOOL_PROGRAM_ID => Leg::Whirlpool {
+                memo_program: reader.memo_program(),
+                pool: reader.next("pool")?,
+                oracle: reader.next("oracle")?,
+                x_vault: reader.next("X vault")?,
+                y_vault: reader.next("Y vault")?,
+                tick_arrays: reader.rest_of_leg(),
+            },
+            RAYDIUM_CLMM_PROGRAM_ID => Leg::RaydiumClmm {
+                memo_program: reader.memo_program(),
+                pool: reader.next("pool")?,
+                amm_config: reader.next("AMM config")?,
+                observation_state: reader.next("observation state")?,
+                bitmap_extension: reader.next("bitmap extension")?,
+                x_vault: reader.next("X vault")?,
+                y_vault: reader.next("Y vault")?,
+                tick_arrays: reader.rest_of_leg(),
+            },
+            DAMM_PROGRAM_ID => {
+                reader.expect(VAULT_PROGRAM_ID, "Meteora vault program")?;
+                Leg::MeteoraDamm {
+                    pool: reader.next("pool")?,
+                    token_x_vault: reader.next("token X vault")?,
+                    token_sol_vault: reader.next("SOL vault")?,
+                    token_x_token_vault: reader.next("token X token vault")?,
+                    token_sol_token_vault: reader.next("SOL token vault")?,
+                    token_x_lp_mint: reader.next("token X LP mint")?,
+                    token_sol_lp_mint: reader.next("SOL LP mint")?,
+                    token_x_pool_lp: reader.next("token X pool LP")?,
+                    token_sol_pool_lp: reader.next("SOL pool LP")?,
+                    admin_token_fee_x: reader.next("admin token X fee")?,
+                    admin_token_fee_sol: reader.next("admin SOL fee")?,
+                }
+            }
+            DAMM_V2_PROGRAM_ID => {
+                reader.expect(DAMM_V2_EVENT_AUTHORITY, "DAMM v2 event authority")?;
+                reader.expect(DAMM_V2_POOL_AUTHORITY, "DAMM v2 pool authority")?;
+                Leg::MeteoraDammV2 {
+                    pool: reader.next("pool")?,
+                    token_x_vault: reader.next("token X vault")?,
+                    token_sol_vault: reader.next("SOL vault")?,
+                }
+            }
+            SOLFI_PROGRAM_ID => {
+                reader.expect(SYSVAR_INSTRUCTIONS_ID, "instructions sysvar")?;
+                Leg::Solfi {
+                    pool: reader.next("pool")?,
+                    token_x_vault: reader.next("token X vault")?,
+                    token_sol_vault: reader.next("SOL vault")?,
+                }
+            }
+            VERTIGO_PROGRAM_ID => Leg::Vertigo {
+                pool: reader.next("pool")?,
+                pool_owner: reader.next("pool owner")?,
+                token_x_vault: reader.next("token X vault")?,
+                token_sol_vault: reader.next("SOL vault")?,
+            },
+            other => bail!("Unknown leg program {}", other),
+        };
+        Ok((leg, reader.offset))
+    }
+}
+
+/// Cursor over an instruction's account keys
+pub(crate) struct AccountReader<'a> {
+    accounts: &'a [Pubkey],
+    pub(crate) offset: usize,
+}
+
+impl<'a> AccountReader<'a> {
+    pub(crate) fn new(accounts: &'a [Pubkey]) -> Self {
+        Self {
+            accounts,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset >= self.accounts.len()
+    }
+
+    pub(crate) fn remaining(&self) -> &'a [Pubkey] {
+        &self.accounts[self.offset..]
+    }
+
+    pub(crate) fn next(&mut self, name: &str) -> Result<Pubkey> {
+        let key = *self
+            .accounts
+            .get(self.offset)
+            .ok_or_else(|| anyhow!("Missing {} at account {}", name, self.offset))?;
+        self.offset += 1;
+        Ok(key)
+    }
+
+    pub(crate) fn expect(&mut self, expected: Pubkey, name: &str) -> Result<()> {
+        let key = self.next(name)?;
+        if key != expected {
+            bail!("Expected {} {}, found {}", name, expected, key);
+        }
+        Ok(())
+    }
+
+    /// The Token 2022 memo program, when the leg includes it
+    fn memo_program(&mut self) -> Option<Pubkey> {
+        let key = self.accounts.get(self.offset).copied()?;
+        if key != MEMO_PROGRAM_ID {
+            return None;
+        }
+        self.offset += 1;
+        Some(key)
+    }
+
+    fn rest_of_leg(&mut self) -> Vec<Pubkey> {
+        let rest = &self.accounts[self.offset..];
+        let len = rest.iter().position(is_leg_program).unwrap_or(rest.len());
+        self.offset += len;
+        rest[..len].to_vec()
+    }
+}
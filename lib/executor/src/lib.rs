@@ -0,0 +1,9 @@
+mod constants;
+mod instruction;
+mod leg;
+
+pub use constants::*;
+pub use instruction::*;
+pub use leg::*;
+
+solana_program::declare_id!("MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz");
@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+
+use crate::math::{
+    mul_div_u64, pow, Rounding, BASIS_POINT_MAX, FEE_DENOMINATOR, MAX_FEE_NUMERATOR, ONE_Q64,
+};
+use crate::state::{BaseFeeStruct, DynamicFeeStruct, FeeSchedulerMode, PoolFeesStruct};
+
+impl BaseFeeStruct {
+    /// Base fee numerator at `current_point`. The fee starts at the cliff and decays once
+    /// per period after activation, stopping after `number_of_period` periods.
+    pub fn get_current_base_fee_numerator(
+        &self,
+        current_point: u64,
+        activation_point: u64,
+    ) -> Result<u64> {
+        if self.period_frequency == 0 {
+            return Ok(self.cliff_fee_numerator);
+        }
+
+        // Trading before activation is only open to the alpha vault, which pays the
+        // minimum fee
+        let period = if current_point < activation_point {
+            u64::from(self.number_of_period)
+        } else {
+            ((current_point - activation_point) / self.period_frequency)
+                .min(u64::from(self.number_of_period))
+        };
+
+        match FeeSchedulerMode::try_from(self.fee_scheduler_mode)? {
+            FeeSchedulerMode::Linear => self
+                .cliff_fee_numerator
+                .checked_sub(self.reduction_factor.saturating_mul(period))
+                .ok_or_else(|| anyhow!("Linear fee scheduler underflow")),
+            FeeSchedulerMode::Exponential => {
+                get_fee_in_period(self.cliff_fee_numerator, self.reduction_factor, period)
+            }
+        }
+    }
+}
+
+/// cliff_fee_numerator * (1 - reduction_factor / 10_000) ^ period
+pub fn get_fee_in_period(
+    cliff_fee_numerator: u64,
+    reduction_factor: u64,
+    period: u64,
+) -> Result<u64> {
+    let bps = (u128::from(reduction_factor) << 64) / u128::from(BASIS_POINT_MAX);
+    let base = ONE_Q64
+        .checked_sub(bps)
+        .ok_or_else(|| anyhow!("Reduction factor above 100%"))?;
+    let factor = pow(base, i128::from(period)).ok_or_else(|| anyhow!("Fee decay overflow"))?;
+    let fee = factor
+        .checked_mul(u128::from(cliff_fee_numerator))
+        .ok_or_else(|| anyhow!("Fee decay overflow"))?
+        >> 64;
+    Ok(fee as u64)
+}
+
+impl DynamicFeeStruct {
+    pub fn is_dynamic_fee_enable(&self) -> bool {
+        self.initialized != 0
+    }
+
+    /// Variable fee numerator from the volatility accumulator
+    pub fn get_variable_fee(&self) -> Result<u128> {
+        if !self.is_dynamic_fee_enable() {
+            return Ok(0);
+        }
+        let overflow = || anyhow!("Variable fee overflow");
+        let square_vfa_bin = self
+            .volatility_accumulator
+            .checked_mul(u128::from(self.bin_step))
+            .ok_or_else(overflow)?
+            .checked_pow(2)
+            .ok_or_else(overflow)?;
+        let v_fee = square_vfa_bin
+            .checked_mul(u128::from(self.variable_fee_control))
+            .ok_or_else(overflow)?;
+        Ok(v_fee.div_ceil(100_000_000_000))
+    }
+
+    /// Reference update the program applies before every swap
+    pub fn update_references(&mut self, sqrt_price_current: u128, current_timestamp: u64) {
+        let elapsed = current_timestamp.saturating_sub(self.last_update_timestamp);
+        if elapsed >= u64::from(self.filter_period) {
+            self.sqrt_price_reference = sqrt_price_current;
+            self.volatility_reference = if elapsed < u64::from(self.decay_period) {
+                self.volatility_accumulator * u128::from(self.reduction_factor)
+                    / u128::from(BASIS_POINT_MAX)
+            } else {
+                0
+            };
+        }
+    }
+}
+
+/// Split of a fee charged on a swap amount
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeOnAmountResult {
+    /// Amount left after all fees
+    pub amount: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub partner_fee: u64,
+}
+
+impl PoolFeesStruct {
+    /// Base plus variable fee numerator, before the cap
+    pub fn get_total_trading_fee(&self, current_point: u64, activation_point: u64) -> Result<u128> {
+        let base_fee_numerator = self
+            .base_fee
+            .get_current_base_fee_numerator(current_point, activation_point)?;
+        Ok(self.dynamic_fee.get_variable_fee()? + u128::from(base_fee_numerator))
+    }
+
+    pub fn get_fee_on_amount(
+        &self,
+        amount: u64,
+        trade_fee_numerator: u128,
+        has_partner: bool,
+    ) -> Result<FeeOnAmountResult> {
+        let trade_fee_numerator = trade_fee_numerator.min(u128::from(MAX_FEE_NUMERATOR)) as u64;
+        let trading_fee = mul_div_u64(amount, trade_fee_numerator, FEE_DENOMINATOR, Rounding::Up)?;
+        let amount = amount
+            .checked_sub(trading_fee)
+            .ok_or_else(|| anyhow!("Fee exceeds amount"))?;
+
+        let protocol_fee = mul_div_u64(
+            trading_fee,
+            u64::from(self.protocol_fee_percent),
+            100,
+            Rounding::Down,
+        )?;
+        let lp_fee = trading_fee - protocol_fee;
+        let partner_fee = if has_partner {
+            mul_div_u64(
+                protocol_fee,
+                u64::from(self.partner_fee_percent),
+                100,
+                Rounding::Down,
+            )?
+        } else {
+            0
+        };
+
+        Ok(FeeOnAmountResult {
+            amount,
+            lp_fee,
+            protocol_fee: protocol_fee - partner_fee,
+            partner_fee,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_fee_decay() {
+        let linear = BaseFeeStruct {
+            cliff_fee_numerator: 500_000_000,
+            fee_scheduler_mode: 0,
+            number_of_period: 10,
+            period_frequency: 60,
+            reduction_factor: 40_000_000,
+        };
+        assert_eq!(
+            linear.get_current_base_fee_numerator(1_000, 1_000).unwrap(),
+            500_000_000
+        );
+        assert_eq!(
+            linear.get_current_base_fee_numerator(1_119, 1_000).unwrap(),
+            460_000_000
+        );
+        // Decay stops after the last period
+        assert_eq!(
+            linear
+                .get_current_base_fee_numerator(99_999, 1_000)
+                .unwrap(),
+            100_000_000
+        );
+        // The alpha vault trades at the final fee before activation
+        assert_eq!(
+            linear.get_current_base_fee_numerator(0, 1_000).unwrap(),
+            100_000_000
+        );
+
+        let exponential = BaseFeeStruct {
+            fee_scheduler_mode: 1,
+            reduction_factor: 5_000,
+            ..linear
+        };
+        assert_eq!(
+            exponential
+                .get_current_base_fee_numerator(1_000, 1_000)
+                .unwrap(),
+            500_000_000
+        );
+        let halved = exponential
+            .get_current_base_fee_numerator(1_060, 1_000)
+            .unwrap();
+        assert!((249_999_990..=250_000_000).contains(&halved));
+    }
+}
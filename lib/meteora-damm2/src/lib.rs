@@ -1,4 +1,10 @@
-use anchor_gen::generate_cpi_crate;
+mod fee;
+mod math;
+mod state;
+mod swap;
+pub use fee::*;
+pub use math::*;
+pub use state::*;
+pub use swap::*;
 
-generate_cpi_crate!("./idl.json");
-declare_id!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
\ No newline at end of file
+solana_program::declare_id!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
@@ -0,0 +1,177 @@
+use anyhow::{anyhow, bail, Result};
+
+// The arithmetic the macro generates trips lints that can't be fixed from here
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod uint_types {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+pub use uint_types::U256;
+
+pub const SCALE_OFFSET: u32 = 64;
+pub const ONE_Q64: u128 = 1u128 << SCALE_OFFSET;
+pub const BASIS_POINT_MAX: u64 = 10_000;
+
+/// Fee numerators are over 1e9
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+/// Trading fees are capped at 50%
+pub const MAX_FEE_NUMERATOR: u64 = 500_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+fn mul_div_u256(x: U256, y: U256, denominator: U256, rounding: Rounding) -> Result<U256> {
+    if denominator.is_zero() {
+        bail!("Division by zero");
+    }
+    let product = x
+        .checked_mul(y)
+        .ok_or_else(|| anyhow!("Multiplication overflow"))?;
+    let (quotient, remainder) = product.div_mod(denominator);
+    Ok(match rounding {
+        Rounding::Up if !remainder.is_zero() => quotient + 1,
+        _ => quotient,
+    })
+}
+
+pub fn mul_div_u64(x: u64, y: u64, denominator: u64, rounding: Rounding) -> Result<u64> {
+    let result = mul_div_u256(
+        U256::from(x),
+        U256::from(y),
+        U256::from(denominator),
+        rounding,
+    )?;
+    to_u64(result)
+}
+
+fn to_u64(value: U256) -> Result<u64> {
+    if value > U256::from(u64::MAX) {
+        bail!("Amount exceeds u64");
+    }
+    Ok(value.as_u64())
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    if value > U256::from(u128::MAX) {
+        bail!("Sqrt price exceeds u128");
+    }
+    Ok(value.as_u128())
+}
+
+/// Q64.64 `base ^ exp` by square-and-multiply, inverting the base when it is above one so
+/// that squaring stays within 128 bits
+pub fn pow(base: u128, exp: i128) -> Option<u128> {
+    if exp == 0 {
+        return Some(ONE_Q64);
+    }
+
+    let mut invert = exp.is_negative();
+    let exp = exp.unsigned_abs();
+    if exp >= 0x80000 {
+        return None;
+    }
+
+    let mut squared_base = base;
+    let mut result = ONE_Q64;
+    if squared_base >= result {
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        invert = !invert;
+    }
+
+    for bit in 0..19 {
+        if bit > 0 {
+            squared_base = squared_base.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+        if exp & (1 << bit) > 0 {
+            result = result.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+    }
+
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+
+/// Token A between two sqrt prices: L * (upper - lower) / (upper * lower)
+pub fn get_delta_amount_a_unsigned(
+    lower_sqrt_price: u128,
+    upper_sqrt_price: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u64> {
+    let denominator = U256::from(lower_sqrt_price) * U256::from(upper_sqrt_price);
+    let result = mul_div_u256(
+        U256::from(liquidity),
+        U256::from(upper_sqrt_price - lower_sqrt_price),
+        denominator,
+        rounding,
+    )?;
+    to_u64(result)
+}
+
+/// Token B between two sqrt prices: L * (upper - lower) >> 128
+pub fn get_delta_amount_b_unsigned(
+    lower_sqrt_price: u128,
+    upper_sqrt_price: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u64> {
+    let product = U256::from(liquidity) * U256::from(upper_sqrt_price - lower_sqrt_price);
+    let shift = SCALE_OFFSET * 2;
+    let result = match rounding {
+        Rounding::Up => {
+            let denominator = U256::one() << shift;
+            let (quotient, remainder) = product.div_mod(denominator);
+            if remainder.is_zero() {
+                quotient
+            } else {
+                quotient + 1
+            }
+        }
+        Rounding::Down => product >> shift,
+    };
+    to_u64(result)
+}
+
+/// Sqrt price after swapping `amount_in` into the pool
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u64,
+    a_for_b: bool,
+) -> Result<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        bail!("Pool has no liquidity");
+    }
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+
+    if a_for_b {
+        // L * P / (L + amount * P), rounded up
+        let product = U256::from(amount_in) * U256::from(sqrt_price);
+        let denominator = U256::from(liquidity) + product;
+        let result = mul_div_u256(
+            U256::from(liquidity),
+            U256::from(sqrt_price),
+            denominator,
+            Rounding::Up,
+        )?;
+        to_u128(result)
+    } else {
+        // P + (amount << 128) / L, rounded down
+        let quotient = (U256::from(amount_in) << (SCALE_OFFSET * 2)) / U256::from(liquidity);
+        to_u128(U256::from(sqrt_price) + quotient)
+    }
+}
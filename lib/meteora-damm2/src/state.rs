@@ -0,0 +1,321 @@
+use anyhow::{anyhow, bail, Result};
+use solana_program::pubkey::Pubkey;
+
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+/// Little-endian cursor over account data
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let bytes = self.data[self.offset..self.offset + N].try_into().unwrap();
+        self.offset += N;
+        bytes
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.offset += len;
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+
+    fn u128(&mut self) -> u128 {
+        u128::from_le_bytes(self.take())
+    }
+
+    fn pubkey(&mut self) -> Pubkey {
+        Pubkey::new_from_array(self.take())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeSchedulerMode {
+    /// cliff_fee_numerator - period * reduction_factor
+    Linear,
+    /// cliff_fee_numerator * (1 - reduction_factor / 10_000) ^ period
+    Exponential,
+}
+
+impl TryFrom<u8> for FeeSchedulerMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Linear),
+            1 => Ok(Self::Exponential),
+            _ => Err(anyhow!("Invalid fee scheduler mode {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectFeeMode {
+    /// Fees are taken from the output token
+    BothToken,
+    /// Fees are always taken in token B
+    OnlyB,
+}
+
+impl TryFrom<u8> for CollectFeeMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::BothToken),
+            1 => Ok(Self::OnlyB),
+            _ => Err(anyhow!("Invalid collect fee mode {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationType {
+    Slot,
+    Timestamp,
+}
+
+impl TryFrom<u8> for ActivationType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Slot),
+            1 => Ok(Self::Timestamp),
+            _ => Err(anyhow!("Invalid activation type {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaseFeeStruct {
+    pub cliff_fee_numerator: u64,
+    pub fee_scheduler_mode: u8,
+    pub number_of_period: u16,
+    pub period_frequency: u64,
+    pub reduction_factor: u64,
+}
+
+impl BaseFeeStruct {
+    pub const LEN: usize = 40;
+
+    fn read(reader: &mut Reader) -> Self {
+        let cliff_fee_numerator = reader.u64();
+        let fee_scheduler_mode = reader.u8();
+        reader.skip(5);
+        let number_of_period = reader.u16();
+        let period_frequency = reader.u64();
+        let reduction_factor = reader.u64();
+        reader.skip(8);
+        Self {
+            cliff_fee_numerator,
+            fee_scheduler_mode,
+            number_of_period,
+            period_frequency,
+            reduction_factor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DynamicFeeStruct {
+    pub initialized: u8,
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+    pub bin_step: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub last_update_timestamp: u64,
+    pub bin_step_u128: u128,
+    pub sqrt_price_reference: u128,
+    pub volatility_accumulator: u128,
+    pub volatility_reference: u128,
+}
+
+impl DynamicFeeStruct {
+    pub const LEN: usize = 96;
+
+    fn read(reader: &mut Reader) -> Self {
+        let initialized = reader.u8();
+        reader.skip(7);
+        Self {
+            initialized,
+            max_volatility_accumulator: reader.u32(),
+            variable_fee_control: reader.u32(),
+            bin_step: reader.u16(),
+            filter_period: reader.u16(),
+            decay_period: reader.u16(),
+            reduction_factor: reader.u16(),
+            last_update_timestamp: reader.u64(),
+            bin_step_u128: reader.u128(),
+            sqrt_price_reference: reader.u128(),
+            volatility_accumulator: reader.u128(),
+            volatility_reference: reader.u128(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolFeesStruct {
+    pub base_fee: BaseFeeStruct,
+    pub protocol_fee_percent: u8,
+    pub partner_fee_percent: u8,
+    pub referral_fee_percent: u8,
+    pub dynamic_fee: DynamicFeeStruct,
+}
+
+impl PoolFeesStruct {
+    pub const LEN: usize = BaseFeeStruct::LEN + 8 + DynamicFeeStruct::LEN + 16;
+
+    fn read(reader: &mut Reader) -> Self {
+        let base_fee = BaseFeeStruct::read(reader);
+        let protocol_fee_percent = reader.u8();
+        let partner_fee_percent = reader.u8();
+        let referral_fee_percent = reader.u8();
+        reader.skip(5);
+        let dynamic_fee = DynamicFeeStruct::read(reader);
+        reader.skip(16);
+        Self {
+            base_fee,
+            protocol_fee_percent,
+            partner_fee_percent,
+            referral_fee_percent,
+            dynamic_fee,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pool {
+    pub pool_fees: PoolFeesStruct,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub whitelisted_vault: Pubkey,
+    pub partner: Pubkey,
+    pub liquidity: u128,
+    pub protocol_a_fee: u64,
+    pub protocol_b_fee: u64,
+    pub partner_a_fee: u64,
+    pub partner_b_fee: u64,
+    pub sqrt_min_price: u128,
+    pub sqrt_max_price: u128,
+    pub sqrt_price: u128,
+    pub activation_point: u64,
+    pub activation_type: u8,
+    pub pool_status: u8,
+    pub token_a_flag: u8,
+    pub token_b_flag: u8,
+    pub collect_fee_mode: u8,
+    pub pool_type: u8,
+    pub permanent_lock_liquidity: u128,
+}
+
+impl Pool {
+    /// Size up to and including `permanent_lock_liquidity`; metrics and rewards follow
+    pub const MIN_LEN: usize =
+        8 + PoolFeesStruct::LEN + 32 * 6 + 16 * 2 + 8 * 4 + 16 * 3 + 16 + 32 * 2 + 16;
+    pub const LEN: usize = 1112;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::MIN_LEN {
+            bail!("Invalid data length for MeteoraDAmmV2Pool");
+        }
+        if data[..8] != POOL_DISCRIMINATOR {
+            bail!("Invalid discriminator for MeteoraDAmmV2Pool");
+        }
+
+        let mut reader = Reader::new(&data[8..]);
+        let pool_fees = PoolFeesStruct::read(&mut reader);
+        let token_a_mint = reader.pubkey();
+        let token_b_mint = reader.pubkey();
+        let token_a_vault = reader.pubkey();
+        let token_b_vault = reader.pubkey();
+        let whitelisted_vault = reader.pubkey();
+        let partner = reader.pubkey();
+        let liquidity = reader.u128();
+        reader.skip(16);
+        let protocol_a_fee = reader.u64();
+        let protocol_b_fee = reader.u64();
+        let partner_a_fee = reader.u64();
+        let partner_b_fee = reader.u64();
+        let sqrt_min_price = reader.u128();
+        let sqrt_max_price = reader.u128();
+        let sqrt_price = reader.u128();
+        let activation_point = reader.u64();
+        let activation_type = reader.u8();
+        let pool_status = reader.u8();
+        let token_a_flag = reader.u8();
+        let token_b_flag = reader.u8();
+        let collect_fee_mode = reader.u8();
+        let pool_type = reader.u8();
+        reader.skip(2);
+        // fee_a_per_liquidity and fee_b_per_liquidity
+        reader.skip(64);
+        let permanent_lock_liquidity = reader.u128();
+
+        Ok(Self {
+            pool_fees,
+            token_a_mint,
+            token_b_mint,
+            token_a_vault,
+            token_b_vault,
+            whitelisted_vault,
+            partner,
+            liquidity,
+            protocol_a_fee,
+            protocol_b_fee,
+            partner_a_fee,
+            partner_b_fee,
+            sqrt_min_price,
+            sqrt_max_price,
+            sqrt_price,
+            activation_point,
+            activation_type,
+            pool_status,
+            token_a_flag,
+            token_b_flag,
+            collect_fee_mode,
+            pool_type,
+            permanent_lock_liquidity,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.pool_status == 0
+    }
+
+    pub fn has_partner(&self) -> bool {
+        self.partner != Pubkey::default()
+    }
+
+    /// Slot or timestamp, depending on the pool's activation type
+    pub fn current_point(&self, slot: u64, timestamp: u64) -> Result<u64> {
+        Ok(match ActivationType::try_from(self.activation_type)? {
+            ActivationType::Slot => slot,
+            ActivationType::Timestamp => timestamp,
+        })
+    }
+}
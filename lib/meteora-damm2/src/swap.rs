@@ -0,0 +1,178 @@
+use anyhow::{bail, Result};
+
+use crate::fee::FeeOnAmountResult;
+use crate::math::{
+    get_delta_amount_a_unsigned, get_delta_amount_b_unsigned, get_next_sqrt_price_from_input,
+    Rounding,
+};
+use crate::state::{CollectFeeMode, Pool};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    pub amount_out: u64,
+    pub next_sqrt_price: u128,
+    /// Total fee charged, in the input token when `fees_on_input` and the output token
+    /// otherwise
+    pub trading_fee: u64,
+    pub fees_on_input: bool,
+    /// Base plus variable fee numerator applied, over 1e9
+    pub trade_fee_numerator: u128,
+}
+
+impl Pool {
+    /// Whether fees are charged on the input token for a swap in this direction
+    pub fn fees_on_input(&self, a_to_b: bool) -> Result<bool> {
+        Ok(match CollectFeeMode::try_from(self.collect_fee_mode)? {
+            CollectFeeMode::BothToken => false,
+            CollectFeeMode::OnlyB => !a_to_b,
+        })
+    }
+
+    /// Exact-in swap at cluster `slot` and `timestamp`. The base fee follows the pool's
+    /// scheduler from its activation point, so freshly launched pools quote with their
+    /// starting fee. Fails before activation since only the alpha vault can trade then.
+    pub fn get_swap_result(
+        &self,
+        amount_in: u64,
+        a_to_b: bool,
+        slot: u64,
+        timestamp: u64,
+    ) -> Result<SwapResult> {
+        if !self.is_enabled() {
+            bail!("Pool is disabled");
+        }
+        let current_point = self.current_point(slot, timestamp)?;
+        if current_point < self.activation_point {
+            bail!(
+                "Pool activates at {}, current point is {}",
+                self.activation_point,
+                current_point
+            );
+        }
+
+        let mut pool_fees = self.pool_fees.clone();
+        if pool_fees.dynamic_fee.is_dynamic_fee_enable() {
+            pool_fees
+                .dynamic_fee
+                .update_references(self.sqrt_price, timestamp);
+        }
+        let trade_fee_numerator =
+            pool_fees.get_total_trading_fee(current_point, self.activation_point)?;
+        let fees_on_input = self.fees_on_input(a_to_b)?;
+        let charge = |amount: u64| -> Result<FeeOnAmountResult> {
+            pool_fees.get_fee_on_amount(amount, trade_fee_numerator, self.has_partner())
+        };
+
+        let (actual_amount_in, input_fee) = if fees_on_input {
+            let fee = charge(amount_in)?;
+            (fee.amount, amount_in - fee.amount)
+        } else {
+            (amount_in, 0)
+        };
+
+        let (output_amount, next_sqrt_price) = if a_to_b {
+            let next_sqrt_price = get_next_sqrt_price_from_input(
+                self.sqrt_price,
+                self.liquidity,
+                actual_amount_in,
+                true,
+            )?;
+            if next_sqrt_price < self.sqrt_min_price {
+                bail!("Swap moves the price below the pool's range");
+            }
+            let amount_out = get_delta_amount_b_unsigned(
+                next_sqrt_price,
+                self.sqrt_price,
+                self.liquidity,
+                Rounding::Down,
+            )?;
+            (amount_out, next_sqrt_price)
+        } else {
+            let next_sqrt_price = get_next_sqrt_price_from_input(
+                self.sqrt_price,
+                self.liquidity,
+                actual_amount_in,
+                false,
+            )?;
+            if next_sqrt_price > self.sqrt_max_price {
+                bail!("Swap moves the price above the pool's range");
+            }
+            let amount_out = get_delta_amount_a_unsigned(
+                self.sqrt_price,
+                next_sqrt_price,
+                self.liquidity,
+                Rounding::Down,
+            )?;
+            (amount_out, next_sqrt_price)
+        };
+
+        let (amount_out, trading_fee) = if fees_on_input {
+            (output_amount, input_fee)
+        } else {
+            let fee = charge(output_amount)?;
+            (fee.amount, output_amount - fee.amount)
+        };
+
+        Ok(SwapResult {
+            amount_out,
+            next_sqrt_price,
+            trading_fee,
+            fees_on_input,
+            trade_fee_numerator,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::ONE_Q64;
+    use crate::state::BaseFeeStruct;
+
+    fn pool(cliff_fee_numerator: u64) -> Pool {
+        let mut pool = Pool {
+            // Price 1, about 1e9 of each token in range
+            liquidity: 1_000_000_000u128 << 64,
+            sqrt_price: ONE_Q64,
+            sqrt_min_price: ONE_Q64 / 2,
+            sqrt_max_price: ONE_Q64 * 2,
+            activation_type: 1,
+            activation_point: 1_000,
+            ..Pool::default()
+        };
+        pool.pool_fees.base_fee = BaseFeeStruct {
+            cliff_fee_numerator,
+            fee_scheduler_mode: 0,
+            number_of_period: 9,
+            period_frequency: 10,
+            reduction_factor: 50_000_000,
+        };
+        pool
+    }
+
+    #[test]
+    fn test_launch_fee_decays() {
+        let pool = pool(500_000_000);
+        assert!(pool.get_swap_result(1_000_000, true, 0, 999).is_err());
+
+        let at_launch = pool.get_swap_result(1_000_000, true, 0, 1_000).unwrap();
+        assert_eq!(at_launch.trade_fee_numerator, 500_000_000);
+        assert!(!at_launch.fees_on_input);
+        assert!(at_launch.amount_out < 500_000);
+
+        let settled = pool.get_swap_result(1_000_000, true, 0, 2_000).unwrap();
+        assert_eq!(settled.trade_fee_numerator, 50_000_000);
+        assert!(settled.amount_out > 940_000 && settled.amount_out < 950_000);
+        assert!(settled.next_sqrt_price < ONE_Q64);
+    }
+
+    #[test]
+    fn test_only_b_charges_input_on_b_to_a() {
+        let mut pool = pool(500_000_000);
+        pool.collect_fee_mode = 1;
+        let result = pool.get_swap_result(1_000_000, false, 0, 2_000).unwrap();
+        assert!(result.fees_on_input);
+        assert_eq!(result.trading_fee, 50_000);
+        assert!(result.next_sqrt_price > ONE_Q64);
+    }
+}
@@ -1,2 +1,6 @@
+mod math;
 mod state;
-pub use state::*;
\ No newline at end of file
+mod swap;
+pub use math::*;
+pub use state::*;
+pub use swap::*;
@@ -1,14 +1,219 @@
+use libm::{floor, pow, sqrt};
+
+// The arithmetic the macro generates trips lints that can't be fixed from here
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod uint_types {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+
+    construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+pub use uint_types::{U256, U512};
+
+pub type U128 = u128;
+
+pub const MIN_TICK_INDEX: i32 = -443636;
+pub const MAX_TICK_INDEX: i32 = 443636;
+pub const MIN_SQRT_PRICE: u128 = 4295048016;
+pub const MAX_SQRT_PRICE: u128 = 79226673515401279992447579055;
+
+const BIT_PRECISION: u32 = 14;
+const LOG_B_2_X32: i128 = 59543866431248i128;
+const LOG_B_P_ERR_MARGIN_LOWER_X64: i128 = 184467440737095516i128;
+const LOG_B_P_ERR_MARGIN_UPPER_X64: i128 = 15793534762490258745i128;
 const Q64_RESOLUTION: f64 = 18446744073709551616.0;
 
 /// # Returns
 /// * `f64` - The decimal price
 pub fn sqrt_price_to_price(sqrt_price: U128, decimals_a: u8, decimals_b: u8) -> f64 {
     let power = pow(10f64, decimals_a as f64 - decimals_b as f64);
-    let sqrt_price: u128 = sqrt_price.into();
     let sqrt_price_u128 = sqrt_price as f64;
     pow(sqrt_price_u128 / Q64_RESOLUTION, 2.0) * power
 }
 
+/// Convert a decimal price to a Q64.64 sqrt price
+///
+/// # Parameters
+/// * `price` - The decimal price
+/// * `decimals_a` - The number of decimals of the base token
+/// * `decimals_b` - The number of decimals of the quote token
+///
+/// # Returns
+/// * `u128` - The sqrt price in Q64.64
+pub fn price_to_sqrt_price(price: f64, decimals_a: u8, decimals_b: u8) -> U128 {
+    let power = pow(10f64, decimals_a as f64 - decimals_b as f64);
+    floor(sqrt(price / power) * Q64_RESOLUTION) as u128
+}
+
+/// Convert a decimal price to the tick index it falls in
+pub fn price_to_tick_index(price: f64, decimals_a: u8, decimals_b: u8) -> i32 {
+    sqrt_price_to_tick_index(price_to_sqrt_price(price, decimals_a, decimals_b))
+}
+
+/// Convert a tick index to its decimal price
+pub fn tick_index_to_price(tick_index: i32, decimals_a: u8, decimals_b: u8) -> f64 {
+    sqrt_price_to_price(tick_index_to_sqrt_price(tick_index), decimals_a, decimals_b)
+}
+
+/// Get the Q64.64 sqrt price for a tick index, i.e. sqrt(1.0001 ^ tick_index) * 2^64
+///
+/// # Parameters
+/// - `tick_index` - A i32 integer in [MIN_TICK_INDEX, MAX_TICK_INDEX]
+///
+/// # Returns
+/// - The sqrt price as a Q64.64 u128, rounded down
+pub fn tick_index_to_sqrt_price(tick_index: i32) -> U128 {
+    if tick_index >= 0 {
+        sqrt_price_from_tick_index_positive(tick_index)
+    } else {
+        sqrt_price_from_tick_index_negative(tick_index)
+    }
+}
+
+fn mul_shift_96(n0: u128, n1: u128) -> u128 {
+    let product = U256::from(n0) * U256::from(n1);
+    (product >> 96).as_u128()
+}
+
+// Performs the exponential conversion with Q64.64 precision
+fn sqrt_price_from_tick_index_positive(tick: i32) -> u128 {
+    let mut ratio: u128 = if tick & 1 != 0 {
+        79232123823359799118286999567
+    } else {
+        79228162514264337593543950336
+    };
+
+    if tick & 2 != 0 {
+        ratio = mul_shift_96(ratio, 79236085330515764027303304731);
+    }
+    if tick & 4 != 0 {
+        ratio = mul_shift_96(ratio, 79244008939048815603706035061);
+    }
+    if tick & 8 != 0 {
+        ratio = mul_shift_96(ratio, 79259858533276714757314932305);
+    }
+    if tick & 16 != 0 {
+        ratio = mul_shift_96(ratio, 79291567232598584799939703904);
+    }
+    if tick & 32 != 0 {
+        ratio = mul_shift_96(ratio, 79355022692464371645785046466);
+    }
+    if tick & 64 != 0 {
+        ratio = mul_shift_96(ratio, 79482085999252804386437311141);
+    }
+    if tick & 128 != 0 {
+        ratio = mul_shift_96(ratio, 79736823300114093921829183326);
+    }
+    if tick & 256 != 0 {
+        ratio = mul_shift_96(ratio, 80248749790819932309965073892);
+    }
+    if tick & 512 != 0 {
+        ratio = mul_shift_96(ratio, 81282483887344747381513967011);
+    }
+    if tick & 1024 != 0 {
+        ratio = mul_shift_96(ratio, 83390072131320151908154831281);
+    }
+    if tick & 2048 != 0 {
+        ratio = mul_shift_96(ratio, 87770609709833776024991924138);
+    }
+    if tick & 4096 != 0 {
+        ratio = mul_shift_96(ratio, 97234110755111693312479820773);
+    }
+    if tick & 8192 != 0 {
+        ratio = mul_shift_96(ratio, 119332217159966728226237229890);
+    }
+    if tick & 16384 != 0 {
+        ratio = mul_shift_96(ratio, 179736315981702064433883588727);
+    }
+    if tick & 32768 != 0 {
+        ratio = mul_shift_96(ratio, 407748233172238350107850275304);
+    }
+    if tick & 65536 != 0 {
+        ratio = mul_shift_96(ratio, 2098478828474011932436660412517);
+    }
+    if tick & 131072 != 0 {
+        ratio = mul_shift_96(ratio, 55581415166113811149459800483533);
+    }
+    if tick & 262144 != 0 {
+        ratio = mul_shift_96(ratio, 38992368544603139932233054999993551);
+    }
+
+    ratio >> 32
+}
+
+fn sqrt_price_from_tick_index_negative(tick: i32) -> u128 {
+    let abs_tick = tick.abs();
+
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        18445821805675392311
+    } else {
+        18446744073709551616
+    };
+
+    if abs_tick & 2 != 0 {
+        ratio = (ratio * 18444899583751176498) >> 64
+    }
+    if abs_tick & 4 != 0 {
+        ratio = (ratio * 18443055278223354162) >> 64
+    }
+    if abs_tick & 8 != 0 {
+        ratio = (ratio * 18439367220385604838) >> 64
+    }
+    if abs_tick & 16 != 0 {
+        ratio = (ratio * 18431993317065449817) >> 64
+    }
+    if abs_tick & 32 != 0 {
+        ratio = (ratio * 18417254355718160513) >> 64
+    }
+    if abs_tick & 64 != 0 {
+        ratio = (ratio * 18387811781193591352) >> 64
+    }
+    if abs_tick & 128 != 0 {
+        ratio = (ratio * 18329067761203520168) >> 64
+    }
+    if abs_tick & 256 != 0 {
+        ratio = (ratio * 18212142134806087854) >> 64
+    }
+    if abs_tick & 512 != 0 {
+        ratio = (ratio * 17980523815641551639) >> 64
+    }
+    if abs_tick & 1024 != 0 {
+        ratio = (ratio * 17526086738831147013) >> 64
+    }
+    if abs_tick & 2048 != 0 {
+        ratio = (ratio * 16651378430235024244) >> 64
+    }
+    if abs_tick & 4096 != 0 {
+        ratio = (ratio * 15030750278693429944) >> 64
+    }
+    if abs_tick & 8192 != 0 {
+        ratio = (ratio * 12247334978882834399) >> 64
+    }
+    if abs_tick & 16384 != 0 {
+        ratio = (ratio * 8131365268884726200) >> 64
+    }
+    if abs_tick & 32768 != 0 {
+        ratio = (ratio * 3584323654723342297) >> 64
+    }
+    if abs_tick & 65536 != 0 {
+        ratio = (ratio * 696457651847595233) >> 64
+    }
+    if abs_tick & 131072 != 0 {
+        ratio = (ratio * 26294789957452057) >> 64
+    }
+    if abs_tick & 262144 != 0 {
+        ratio = (ratio * 37481735321082) >> 64
+    }
+
+    ratio
+}
+
 /// Invert a price
 /// IMPORTANT: floating point operations can reduce the precision of the result.
 /// Make sure to do these operations last and not to use the result for further calculations.
@@ -20,7 +225,6 @@ pub fn sqrt_price_to_price(sqrt_price: U128, decimals_a: u8, decimals_b: u8) ->
 ///
 /// # Returns
 /// * `f64` - The inverted price
-#[cfg_attr(feature = "wasm", wasm_expose)]
 pub fn invert_price(price: f64, decimals_a: u8, decimals_b: u8) -> f64 {
     let tick_index = price_to_tick_index(price, decimals_a, decimals_b);
     let inverted_tick_index = invert_tick_index(tick_index);
@@ -37,15 +241,13 @@ pub fn invert_price(price: f64, decimals_a: u8, decimals_b: u8) -> f64 {
 ///
 /// # Returns
 /// - A i32 integer representing the tick index for the inverse of the price
-#[cfg_attr(feature = "wasm", wasm_expose)]
 pub fn invert_tick_index(tick_index: i32) -> i32 {
     -tick_index
 }
 
 
-#[cfg_attr(feature = "wasm", wasm_expose)]
 pub fn sqrt_price_to_tick_index(sqrt_price: U128) -> i32 {
-    let sqrt_price_x64: u128 = sqrt_price.into();
+    let sqrt_price_x64: u128 = sqrt_price;
     // Determine log_b(sqrt_ratio). First by calculating integer portion (msb)
     let msb: u32 = 128 - sqrt_price_x64.leading_zeros() - 1;
     let log2p_integer_x32 = (msb as i128 - 64) << 32;
@@ -91,7 +293,7 @@ pub fn sqrt_price_to_tick_index(sqrt_price: U128) -> i32 {
         // then the actual tick_high has to be higher than than tick_high.
         // Otherwise, the actual value is between tick_low & tick_high, so a floor value
         // (tick_low) is returned
-        let actual_tick_high_sqrt_price_x64: u128 = tick_index_to_sqrt_price(tick_high).into();
+        let actual_tick_high_sqrt_price_x64: u128 = tick_index_to_sqrt_price(tick_high);
         if actual_tick_high_sqrt_price_x64 <= sqrt_price_x64 {
             tick_high
         } else {
@@ -1,3 +1,7 @@
+use crate::math::{MAX_TICK_INDEX, MIN_TICK_INDEX};
+use anyhow::{bail, Result};
+use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
 
 pub const NUM_REWARDS: usize = 3;
 
@@ -59,4 +63,142 @@ pub struct WhirlpoolState {
     pub reward_last_updated_timestamp: u64, // 8
 
     pub reward_infos: [WhirlpoolRewardInfo; NUM_REWARDS], // 384
-}
\ No newline at end of file
+}
+
+pub const TICK_ARRAY_SIZE: usize = 88;
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Tick {
+    pub initialized: bool,
+    /// Amount of net liquidity added when the tick is crossed left to right
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_a: u128,
+    pub fee_growth_outside_b: u128,
+    pub reward_growths_outside: [u128; NUM_REWARDS],
+}
+
+impl Tick {
+    pub const LEN: usize = 1 + 16 + 16 + 16 + 16 + 16 * NUM_REWARDS;
+
+    pub fn check_is_valid_start_tick(tick_index: i32, tick_spacing: u16) -> bool {
+        tick_index % (tick_spacing as i32 * TICK_ARRAY_SIZE as i32) == 0
+    }
+
+    fn unpack(data: &[u8]) -> Self {
+        let read_u128 =
+            |offset: usize| u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+        let mut reward_growths_outside = [0u128; NUM_REWARDS];
+        for (i, growth) in reward_growths_outside.iter_mut().enumerate() {
+            *growth = read_u128(65 + 16 * i);
+        }
+        Tick {
+            initialized: data[0] != 0,
+            liquidity_net: read_u128(1) as i128,
+            liquidity_gross: read_u128(17),
+            fee_growth_outside_a: read_u128(33),
+            fee_growth_outside_b: read_u128(49),
+            reward_growths_outside,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TickArray {
+    pub start_tick_index: i32,
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+    pub whirlpool: Pubkey,
+}
+
+impl TickArray {
+    pub const LEN: usize = 8 + 4 + Tick::LEN * TICK_ARRAY_SIZE + 32;
+
+    pub fn try_deserialize(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::LEN {
+            bail!("Invalid data length for TickArray");
+        }
+
+        let start_tick_index = i32::from_le_bytes(data[8..12].try_into()?);
+        let mut ticks = [Tick::default(); TICK_ARRAY_SIZE];
+        for (i, tick) in ticks.iter_mut().enumerate() {
+            let offset = 12 + i * Tick::LEN;
+            *tick = Tick::unpack(&data[offset..offset + Tick::LEN]);
+        }
+        let whirlpool_offset = 12 + Tick::LEN * TICK_ARRAY_SIZE;
+        let whirlpool = Pubkey::try_from(&data[whirlpool_offset..whirlpool_offset + 32])?;
+
+        Ok(TickArray {
+            start_tick_index,
+            ticks,
+            whirlpool,
+        })
+    }
+
+    /// Number of ticks covered by one array for the given spacing
+    pub fn ticks_in_array(tick_spacing: u16) -> i32 {
+        TICK_ARRAY_SIZE as i32 * tick_spacing as i32
+    }
+
+    pub fn is_min_tick_array(&self) -> bool {
+        self.start_tick_index <= MIN_TICK_INDEX
+    }
+
+    pub fn is_max_tick_array(&self, tick_spacing: u16) -> bool {
+        self.start_tick_index + Self::ticks_in_array(tick_spacing) > MAX_TICK_INDEX
+    }
+
+    /// Whether `tick_index` can start a search in this array. Searches to the right
+    /// (b to a) are shifted one spacing left so that a price sitting exactly on the
+    /// lower bound of the next array still resolves here.
+    pub fn in_search_range(&self, tick_index: i32, tick_spacing: u16, shifted: bool) -> bool {
+        let mut lower = self.start_tick_index;
+        let mut upper = self.start_tick_index + Self::ticks_in_array(tick_spacing);
+        if shifted {
+            lower -= tick_spacing as i32;
+            upper -= tick_spacing as i32;
+        }
+        tick_index >= lower && tick_index < upper
+    }
+
+    pub fn get_tick(&self, tick_index: i32, tick_spacing: u16) -> Option<&Tick> {
+        if tick_index % tick_spacing as i32 != 0 {
+            return None;
+        }
+        let offset = (tick_index - self.start_tick_index).div_euclid(tick_spacing as i32);
+        if offset < 0 {
+            return None;
+        }
+        self.ticks.get(offset as usize)
+    }
+
+    /// Next initialized tick index in the swap direction, if any remain in this array
+    pub fn get_next_init_tick_index(
+        &self,
+        tick_index: i32,
+        tick_spacing: u16,
+        a_to_b: bool,
+    ) -> Result<Option<i32>> {
+        if !self.in_search_range(tick_index, tick_spacing, !a_to_b) {
+            bail!(
+                "Tick {} is outside of tick array {}",
+                tick_index,
+                self.start_tick_index
+            );
+        }
+
+        let mut offset = (tick_index - self.start_tick_index).div_euclid(tick_spacing as i32);
+        // a to b can land on the current tick, b to a always moves past it
+        if !a_to_b {
+            offset += 1;
+        }
+
+        while offset >= 0 && offset < TICK_ARRAY_SIZE as i32 {
+            if self.ticks[offset as usize].initialized {
+                return Ok(Some(self.start_tick_index + offset * tick_spacing as i32));
+            }
+            offset = if a_to_b { offset - 1 } else { offset + 1 };
+        }
+
+        Ok(None)
+    }
+}
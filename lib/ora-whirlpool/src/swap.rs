@@ -0,0 +1,469 @@
+use crate::math::{
+    sqrt_price_to_tick_index, tick_index_to_sqrt_price, MAX_SQRT_PRICE, MAX_TICK_INDEX,
+    MIN_SQRT_PRICE, MIN_TICK_INDEX, U256, U512,
+};
+use crate::state::{TickArray, WhirlpoolState};
+use anyhow::{anyhow, bail, Result};
+
+/// fee_rate is stored in hundredths of a basis point
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// The subset of whirlpool state a swap reads and moves
+#[derive(Debug, Clone, Copy)]
+pub struct WhirlpoolSwapState {
+    pub liquidity: u128,
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+}
+
+impl From<&WhirlpoolState> for WhirlpoolSwapState {
+    fn from(whirlpool: &WhirlpoolState) -> Self {
+        WhirlpoolSwapState {
+            liquidity: whirlpool.liquidity,
+            sqrt_price: whirlpool.sqrt_price,
+            tick_current_index: whirlpool.tick_current_index,
+            tick_spacing: whirlpool.tick_spacing,
+            fee_rate: whirlpool.fee_rate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStepComputation {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub next_price: u128,
+    pub fee_amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    /// Input consumed, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub end_sqrt_price: u128,
+    pub end_tick_index: i32,
+    pub end_liquidity: u128,
+    /// How many of the leading tick arrays the swap read. Arrays past this are not needed.
+    pub tick_arrays_touched: usize,
+}
+
+/// Simulate an exact-in swap across `tick_arrays`, which must be ordered in the swap
+/// direction with the first one holding the current tick.
+///
+/// Fails when the arrays run out before `amount_in` is fully consumed.
+pub fn swap_exact_in(
+    pool: &WhirlpoolSwapState,
+    tick_arrays: &[TickArray],
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<SwapResult> {
+    if tick_arrays.is_empty() {
+        bail!("At least one tick array is required");
+    }
+
+    let sqrt_price_limit = if a_to_b {
+        MIN_SQRT_PRICE
+    } else {
+        MAX_SQRT_PRICE
+    };
+
+    let mut amount_remaining = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut curr_sqrt_price = pool.sqrt_price;
+    let mut curr_tick_index = pool.tick_current_index;
+    let mut curr_liquidity = pool.liquidity;
+    let mut curr_array_index = 0;
+
+    while amount_remaining > 0 && curr_sqrt_price != sqrt_price_limit {
+        let (next_array_index, next_tick_index) = next_initialized_tick_index(
+            tick_arrays,
+            curr_tick_index,
+            pool.tick_spacing,
+            a_to_b,
+            curr_array_index,
+        )?;
+
+        let next_tick_sqrt_price = tick_index_to_sqrt_price(next_tick_index);
+        let sqrt_price_target = if (a_to_b && next_tick_sqrt_price < sqrt_price_limit)
+            || (!a_to_b && next_tick_sqrt_price > sqrt_price_limit)
+        {
+            sqrt_price_limit
+        } else {
+            next_tick_sqrt_price
+        };
+
+        let step = compute_swap_step(
+            amount_remaining,
+            pool.fee_rate,
+            curr_liquidity,
+            curr_sqrt_price,
+            sqrt_price_target,
+            a_to_b,
+        )?;
+
+        amount_remaining = amount_remaining
+            .checked_sub(step.amount_in)
+            .and_then(|v| v.checked_sub(step.fee_amount))
+            .ok_or_else(|| anyhow!("Swap step consumed more than the remaining input"))?;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or_else(|| anyhow!("Amount out overflow"))?;
+        fee_amount += step.fee_amount;
+
+        if step.next_price == next_tick_sqrt_price {
+            let tick = tick_arrays[next_array_index].get_tick(next_tick_index, pool.tick_spacing);
+            if let Some(tick) = tick.filter(|t| t.initialized) {
+                let liquidity_net = if a_to_b {
+                    -tick.liquidity_net
+                } else {
+                    tick.liquidity_net
+                };
+                curr_liquidity = add_liquidity_delta(curr_liquidity, liquidity_net)?;
+            }
+            curr_tick_index = if a_to_b {
+                next_tick_index - 1
+            } else {
+                next_tick_index
+            };
+        } else if step.next_price != curr_sqrt_price {
+            curr_tick_index = sqrt_price_to_tick_index(step.next_price);
+        }
+
+        curr_sqrt_price = step.next_price;
+        curr_array_index = next_array_index;
+    }
+
+    if amount_remaining > 0 {
+        bail!(
+            "Not enough liquidity in the provided tick arrays, {} of {} unfilled",
+            amount_remaining,
+            amount_in
+        );
+    }
+
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        fee_amount,
+        end_sqrt_price: curr_sqrt_price,
+        end_tick_index: curr_tick_index,
+        end_liquidity: curr_liquidity,
+        tick_arrays_touched: curr_array_index + 1,
+    })
+}
+
+/// Find the next initialized tick starting from `start_array_index`. When an array has
+/// none left, the search continues in the next one; the last array (or the price range
+/// boundary) stops the search at its edge so the swap can step up to it.
+fn next_initialized_tick_index(
+    tick_arrays: &[TickArray],
+    tick_index: i32,
+    tick_spacing: u16,
+    a_to_b: bool,
+    start_array_index: usize,
+) -> Result<(usize, i32)> {
+    let ticks_in_array = TickArray::ticks_in_array(tick_spacing);
+    let mut search_index = tick_index;
+    let mut array_index = start_array_index;
+
+    loop {
+        let tick_array = tick_arrays.get(array_index).ok_or_else(|| {
+            anyhow!(
+                "Swap crossed past the last of {} tick arrays",
+                tick_arrays.len()
+            )
+        })?;
+
+        if let Some(next_index) =
+            tick_array.get_next_init_tick_index(search_index, tick_spacing, a_to_b)?
+        {
+            return Ok((array_index, next_index));
+        }
+
+        if a_to_b && tick_array.is_min_tick_array() {
+            return Ok((array_index, MIN_TICK_INDEX));
+        }
+        if !a_to_b && tick_array.is_max_tick_array(tick_spacing) {
+            return Ok((array_index, MAX_TICK_INDEX));
+        }
+
+        if array_index + 1 == tick_arrays.len() {
+            let edge = if a_to_b {
+                tick_array.start_tick_index
+            } else {
+                tick_array.start_tick_index + ticks_in_array - tick_spacing as i32
+            };
+            return Ok((array_index, edge));
+        }
+
+        search_index = if a_to_b {
+            tick_array.start_tick_index - 1
+        } else {
+            tick_array.start_tick_index + ticks_in_array - 1
+        };
+        array_index += 1;
+    }
+}
+
+/// One exact-in step from `sqrt_price_current` towards `sqrt_price_target` within a
+/// single liquidity range
+pub fn compute_swap_step(
+    amount_remaining: u64,
+    fee_rate: u16,
+    liquidity: u128,
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    a_to_b: bool,
+) -> Result<SwapStepComputation> {
+    let fee_rate = u32::from(fee_rate);
+    let amount_after_fee = (u128::from(amount_remaining)
+        * u128::from(FEE_RATE_DENOMINATOR - fee_rate)
+        / u128::from(FEE_RATE_DENOMINATOR)) as u64;
+
+    let max_amount_in = amount_in_delta(sqrt_price_current, sqrt_price_target, liquidity, a_to_b);
+
+    let next_price = match max_amount_in {
+        Some(max_in) if max_in <= amount_after_fee => sqrt_price_target,
+        _ => next_sqrt_price(sqrt_price_current, liquidity, amount_after_fee, a_to_b)?,
+    };
+    let is_max_swap = next_price == sqrt_price_target;
+
+    let amount_in = match max_amount_in {
+        Some(max_in) if is_max_swap => max_in,
+        _ => amount_in_delta(sqrt_price_current, next_price, liquidity, a_to_b)
+            .ok_or_else(|| anyhow!("Amount in exceeds u64"))?,
+    };
+    let amount_out = amount_out_delta(sqrt_price_current, next_price, liquidity, a_to_b)
+        .ok_or_else(|| anyhow!("Amount out exceeds u64"))?;
+
+    let fee_amount = if is_max_swap {
+        let numerator = u128::from(amount_in) * u128::from(fee_rate);
+        let denominator = u128::from(FEE_RATE_DENOMINATOR - fee_rate);
+        numerator.div_ceil(denominator) as u64
+    } else {
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or_else(|| anyhow!("Amount in exceeds the remaining input"))?
+    };
+
+    Ok(SwapStepComputation {
+        amount_in,
+        amount_out,
+        next_price,
+        fee_amount,
+    })
+}
+
+fn amount_in_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    a_to_b: bool,
+) -> Option<u64> {
+    if a_to_b {
+        get_amount_delta_a(sqrt_price_0, sqrt_price_1, liquidity, true)
+    } else {
+        get_amount_delta_b(sqrt_price_0, sqrt_price_1, liquidity, true)
+    }
+}
+
+fn amount_out_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    a_to_b: bool,
+) -> Option<u64> {
+    if a_to_b {
+        get_amount_delta_b(sqrt_price_0, sqrt_price_1, liquidity, false)
+    } else {
+        get_amount_delta_a(sqrt_price_0, sqrt_price_1, liquidity, false)
+    }
+}
+
+/// Token A between two sqrt prices: L * (upper - lower) / (upper * lower).
+/// `None` when the amount does not fit in a u64.
+pub fn get_amount_delta_a(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u64> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    if lower == 0 {
+        return None;
+    }
+    let numerator = (U512::from(liquidity) * U512::from(upper - lower)) << 64;
+    let denominator = U512::from(upper) * U512::from(lower);
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    let amount = if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    };
+    (amount <= U512::from(u64::MAX)).then(|| amount.as_u64())
+}
+
+/// Token B between two sqrt prices: L * (upper - lower).
+/// `None` when the amount does not fit in a u64.
+pub fn get_amount_delta_b(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u64> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    let product = U256::from(liquidity) * U256::from(upper - lower);
+    let quotient = product >> 64;
+    let amount = if round_up && product.low_u64() != 0 {
+        quotient + 1
+    } else {
+        quotient
+    };
+    (amount <= U256::from(u64::MAX)).then(|| amount.as_u64())
+}
+
+fn next_sqrt_price(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<u128> {
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+    if liquidity == 0 {
+        bail!("Cannot move the price without liquidity");
+    }
+
+    let next = if a_to_b {
+        // L * P / (L + amount * P), rounded up so the pool never gives away extra B
+        let numerator = (U512::from(liquidity) * U512::from(sqrt_price)) << 64;
+        let denominator =
+            (U512::from(liquidity) << 64) + U512::from(sqrt_price) * U512::from(amount_in);
+        let (quotient, remainder) = numerator.div_mod(denominator);
+        let next = if remainder.is_zero() {
+            quotient
+        } else {
+            quotient + 1
+        };
+        if next > U512::from(u128::MAX) {
+            bail!("Sqrt price overflow");
+        }
+        next.as_u128()
+    } else {
+        // P + amount / L, rounded down
+        let delta = (U256::from(amount_in) << 64) / U256::from(liquidity);
+        if delta > U256::from(u128::MAX) {
+            bail!("Sqrt price overflow");
+        }
+        sqrt_price
+            .checked_add(delta.as_u128())
+            .ok_or_else(|| anyhow!("Sqrt price overflow"))?
+    };
+
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&next) {
+        bail!("Sqrt price {} out of bounds", next);
+    }
+    Ok(next)
+}
+
+fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let next = if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    };
+    next.ok_or_else(|| anyhow!("Liquidity delta {} overflows {}", delta, liquidity))
+}
+
+fn sorted(a: u128, b: u128) -> (u128, u128) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Tick, TICK_ARRAY_SIZE};
+    use solana_program::pubkey::Pubkey;
+
+    const TICK_SPACING: u16 = 64;
+
+    fn tick_array(start_tick_index: i32) -> TickArray {
+        TickArray {
+            start_tick_index,
+            ticks: [Tick::default(); TICK_ARRAY_SIZE],
+            whirlpool: Pubkey::default(),
+        }
+    }
+
+    fn pool(liquidity: u128) -> WhirlpoolSwapState {
+        WhirlpoolSwapState {
+            liquidity,
+            sqrt_price: tick_index_to_sqrt_price(0),
+            tick_current_index: 0,
+            tick_spacing: TICK_SPACING,
+            fee_rate: 3000,
+        }
+    }
+
+    #[test]
+    fn test_tick_index_round_trip() {
+        for tick in [-443636, -100_000, -1, 0, 1, 64, 100_000, 443636] {
+            let sqrt_price = tick_index_to_sqrt_price(tick);
+            assert_eq!(sqrt_price_to_tick_index(sqrt_price), tick);
+        }
+        assert_eq!(tick_index_to_sqrt_price(0), 1u128 << 64);
+        assert_eq!(tick_index_to_sqrt_price(MIN_TICK_INDEX), MIN_SQRT_PRICE);
+        assert_eq!(tick_index_to_sqrt_price(MAX_TICK_INDEX), MAX_SQRT_PRICE);
+    }
+
+    #[test]
+    fn test_swap_within_one_range() {
+        let liquidity = 1_000_000_000_000u128;
+        let result = swap_exact_in(&pool(liquidity), &[tick_array(0)], 1_000_000, false).unwrap();
+
+        assert_eq!(result.amount_in, 1_000_000);
+        assert!((2_999..=3_000).contains(&result.fee_amount));
+        // Price barely moves, so output is close to the fee-adjusted input
+        assert!(result.amount_out < 997_000 && result.amount_out > 996_000);
+        assert!(result.end_sqrt_price > 1u128 << 64);
+        assert_eq!(result.end_liquidity, liquidity);
+        assert_eq!(result.tick_arrays_touched, 1);
+    }
+
+    #[test]
+    fn test_swap_crosses_initialized_tick() {
+        let liquidity = 1_000_000u128;
+        let mut first = tick_array(-TickArray::ticks_in_array(TICK_SPACING));
+        let second_start = first.start_tick_index - TickArray::ticks_in_array(TICK_SPACING);
+        let second = tick_array(second_start);
+        // A position whose lower bound is at -64 ends when the price moves below it
+        first.ticks[TICK_ARRAY_SIZE - 1] = Tick {
+            initialized: true,
+            liquidity_net: 400_000,
+            ..Tick::default()
+        };
+
+        let mut state = pool(liquidity);
+        state.tick_current_index = -1;
+        state.sqrt_price = tick_index_to_sqrt_price(0) - 1;
+        let result = swap_exact_in(&state, &[first, second], 10_000, true).unwrap();
+
+        assert_eq!(result.end_liquidity, 600_000);
+        assert!(result.end_tick_index < -64);
+        // The first array has nothing left below -64, so the search reads the second
+        assert_eq!(result.tick_arrays_touched, 2);
+
+        let exhausted = swap_exact_in(&state, &[tick_array(-5632)], u64::MAX / 2, true);
+        assert!(exhausted.is_err());
+    }
+}
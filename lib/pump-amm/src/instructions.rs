@@ -0,0 +1,183 @@
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+use crate::state::{event_authority, global_config_address, Pool};
+
+pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Accounts shared by `buy` and `sell`, in the order the program expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapAccounts {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub global_config: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub user_base_token_account: Pubkey,
+    pub user_quote_token_account: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub protocol_fee_recipient: Pubkey,
+    pub protocol_fee_recipient_token_account: Pubkey,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub event_authority: Pubkey,
+    pub coin_creator_vault_ata: Pubkey,
+    pub coin_creator_vault_authority: Pubkey,
+}
+
+impl SwapAccounts {
+    /// Fills the pool-derived accounts from decoded state. Token accounts owned by the
+    /// user, the fee recipient and the creator vault are left to the caller since they
+    /// depend on the token programs in use.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_pool(
+        pool_address: Pubkey,
+        pool: &Pool,
+        user: Pubkey,
+        user_base_token_account: Pubkey,
+        user_quote_token_account: Pubkey,
+        protocol_fee_recipient: Pubkey,
+        protocol_fee_recipient_token_account: Pubkey,
+        base_token_program: Pubkey,
+        quote_token_program: Pubkey,
+        coin_creator_vault_ata: Pubkey,
+    ) -> Self {
+        Self {
+            pool: pool_address,
+            user,
+            global_config: global_config_address(),
+            base_mint: pool.base_mint,
+            quote_mint: pool.quote_mint,
+            user_base_token_account,
+            user_quote_token_account,
+            pool_base_token_account: pool.pool_base_token_account,
+            pool_quote_token_account: pool.pool_quote_token_account,
+            protocol_fee_recipient,
+            protocol_fee_recipient_token_account,
+            base_token_program,
+            quote_token_program,
+            event_authority: event_authority(),
+            coin_creator_vault_ata,
+            coin_creator_vault_authority: pool.coin_creator_vault_authority(),
+        }
+    }
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.pool, false),
+            AccountMeta::new(self.user, true),
+            AccountMeta::new_readonly(self.global_config, false),
+            AccountMeta::new_readonly(self.base_mint, false),
+            AccountMeta::new_readonly(self.quote_mint, false),
+            AccountMeta::new(self.user_base_token_account, false),
+            AccountMeta::new(self.user_quote_token_account, false),
+            AccountMeta::new(self.pool_base_token_account, false),
+            AccountMeta::new(self.pool_quote_token_account, false),
+            AccountMeta::new_readonly(self.protocol_fee_recipient, false),
+            AccountMeta::new(self.protocol_fee_recipient_token_account, false),
+            AccountMeta::new_readonly(self.base_token_program, false),
+            AccountMeta::new_readonly(self.quote_token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(self.event_authority, false),
+            AccountMeta::new_readonly(crate::ID, false),
+            AccountMeta::new(self.coin_creator_vault_ata, false),
+            AccountMeta::new_readonly(self.coin_creator_vault_authority, false),
+        ]
+    }
+}
+
+fn swap_data(discriminator: [u8; 8], amount: u64, limit: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(&discriminator);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&limit.to_le_bytes());
+    data
+}
+
+/// Buy exactly `base_amount_out`, paying at most `max_quote_amount_in`
+pub fn buy(accounts: &SwapAccounts, base_amount_out: u64, max_quote_amount_in: u64) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(),
+        data: swap_data(BUY_DISCRIMINATOR, base_amount_out, max_quote_amount_in),
+    }
+}
+
+/// Sell exactly `base_amount_in`, receiving at least `min_quote_amount_out`
+pub fn sell(
+    accounts: &SwapAccounts,
+    base_amount_in: u64,
+    min_quote_amount_out: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(),
+        data: swap_data(SELL_DISCRIMINATOR, base_amount_in, min_quote_amount_out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_addresses() {
+        assert_eq!(
+            global_config_address().to_string(),
+            "ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw"
+        );
+        assert_eq!(
+            event_authority().to_string(),
+            "GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR"
+        );
+    }
+
+    #[test]
+    fn test_sell_encoding() {
+        let pool = Pool {
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            pool_base_token_account: Pubkey::new_unique(),
+            pool_quote_token_account: Pubkey::new_unique(),
+            coin_creator: Pubkey::new_unique(),
+            ..Pool::default()
+        };
+        let pool_address = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let accounts = SwapAccounts::from_pool(
+            pool_address,
+            &pool,
+            user,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let ix = sell(&accounts, 1_000, 990);
+        assert_eq!(ix.program_id, crate::ID);
+        assert_eq!(&ix.data[..8], &SELL_DISCRIMINATOR);
+        assert_eq!(
+            u64::from_le_bytes(ix.data[8..16].try_into().unwrap()),
+            1_000
+        );
+        assert_eq!(u64::from_le_bytes(ix.data[16..24].try_into().unwrap()), 990);
+
+        assert_eq!(ix.accounts.len(), 19);
+        assert_eq!(ix.accounts[0].pubkey, pool_address);
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].pubkey == user);
+        assert_eq!(ix.accounts[7].pubkey, pool.pool_base_token_account);
+        assert_eq!(ix.accounts[16].pubkey, crate::ID);
+        assert_eq!(ix.accounts[18].pubkey, pool.coin_creator_vault_authority());
+    }
+}
@@ -4,4 +4,6 @@ mod instructions;
 
 pub use state::*;
 pub use instructions::*;
-pub use math::*;
\ No newline at end of file
+pub use math::*;
+
+solana_program::declare_id!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
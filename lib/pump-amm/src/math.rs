@@ -1,6 +1,7 @@
 use uint::construct_uint;
 use solana_program::pubkey::Pubkey;
 use solana_program::program_error::ProgramError;
+use crate::state::{GlobalConfig, Pool};
 const PRECISION: u64 = 1_000_000_000; // 用于滑点计算
 
 construct_uint! {
@@ -216,6 +217,104 @@ pub fn sell_base_input_internal(
     })
 }
 
+/// Live inputs of a swap: pool and global config state plus the vault balances
+pub struct SwapContext<'a> {
+    pub pool: &'a Pool,
+    pub global_config: &'a GlobalConfig,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+}
+
+impl SwapContext<'_> {
+    fn reserves(&self) -> (U256, U256) {
+        (U256::from(self.base_reserve), U256::from(self.quote_reserve))
+    }
+
+    fn fee_bps(&self) -> (U256, U256, U256) {
+        (
+            U256::from(self.global_config.lp_fee_basis_points),
+            U256::from(self.global_config.protocol_fee_basis_points),
+            U256::from(self.global_config.coin_creator_fee_basis_points),
+        )
+    }
+
+    pub fn buy_base_input(
+        &self,
+        base: u64,
+        slippage: f64,
+    ) -> Result<BuyBaseInputResult, ProgramError> {
+        let (base_reserve, quote_reserve) = self.reserves();
+        let (lp_fee_bps, protocol_fee_bps, coin_creator_fee_bps) = self.fee_bps();
+        buy_base_input_internal(
+            &U256::from(base),
+            slippage,
+            &base_reserve,
+            &quote_reserve,
+            &lp_fee_bps,
+            &protocol_fee_bps,
+            &coin_creator_fee_bps,
+            &self.pool.coin_creator,
+        )
+    }
+
+    pub fn buy_quote_input(
+        &self,
+        quote: u64,
+        slippage: f64,
+    ) -> Result<BuyQuoteInputResult, ProgramError> {
+        let (base_reserve, quote_reserve) = self.reserves();
+        let (lp_fee_bps, protocol_fee_bps, coin_creator_fee_bps) = self.fee_bps();
+        buy_quote_input_internal(
+            &U256::from(quote),
+            slippage,
+            &base_reserve,
+            &quote_reserve,
+            &lp_fee_bps,
+            &protocol_fee_bps,
+            &coin_creator_fee_bps,
+            &self.pool.coin_creator,
+        )
+    }
+
+    pub fn sell_base_input(
+        &self,
+        base: u64,
+        slippage: f64,
+    ) -> Result<SellBaseInputResult, ProgramError> {
+        let (base_reserve, quote_reserve) = self.reserves();
+        let (lp_fee_bps, protocol_fee_bps, coin_creator_fee_bps) = self.fee_bps();
+        sell_base_input_internal(
+            &U256::from(base),
+            slippage,
+            &base_reserve,
+            &quote_reserve,
+            &lp_fee_bps,
+            &protocol_fee_bps,
+            &coin_creator_fee_bps,
+            &self.pool.coin_creator,
+        )
+    }
+
+    pub fn sell_quote_input(
+        &self,
+        quote: u64,
+        slippage: f64,
+    ) -> Result<SellQuoteInputResult, ProgramError> {
+        let (base_reserve, quote_reserve) = self.reserves();
+        let (lp_fee_bps, protocol_fee_bps, coin_creator_fee_bps) = self.fee_bps();
+        sell_quote_input_internal(
+            &U256::from(quote),
+            slippage,
+            &base_reserve,
+            &quote_reserve,
+            &lp_fee_bps,
+            &protocol_fee_bps,
+            &coin_creator_fee_bps,
+            &self.pool.coin_creator,
+        )
+    }
+}
+
 // 辅助函数：计算向上取整的除法
 fn ceil_div(numerator: &U256, denominator: &U256) -> U256 {
     let result = numerator / denominator;
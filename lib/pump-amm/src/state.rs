@@ -0,0 +1,126 @@
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub const POOL_SEED: &[u8] = b"pool";
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+pub const CREATOR_VAULT_SEED: &[u8] = b"creator_vault";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+pub const PROTOCOL_FEE_RECIPIENTS: usize = 8;
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pool {
+    pub pool_bump: u8,
+    pub index: u16,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+    pub lp_supply: u64,
+    /// Default for pools created before creator fees existed
+    pub coin_creator: Pubkey,
+}
+
+impl Pool {
+    /// Size up to `lp_supply`, which every pool has
+    pub const MIN_LEN: usize = 8 + 1 + 2 + 32 * 6 + 8;
+    pub const LEN: usize = Self::MIN_LEN + 32;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < Self::MIN_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let coin_creator = if data.len() >= Self::LEN {
+            read_pubkey(data, 211)
+        } else {
+            Pubkey::default()
+        };
+
+        Ok(Self {
+            pool_bump: data[8],
+            index: u16::from_le_bytes([data[9], data[10]]),
+            creator: read_pubkey(data, 11),
+            base_mint: read_pubkey(data, 43),
+            quote_mint: read_pubkey(data, 75),
+            lp_mint: read_pubkey(data, 107),
+            pool_base_token_account: read_pubkey(data, 139),
+            pool_quote_token_account: read_pubkey(data, 171),
+            lp_supply: read_u64(data, 203),
+            coin_creator,
+        })
+    }
+
+    pub fn has_coin_creator(&self) -> bool {
+        self.coin_creator != Pubkey::default()
+    }
+
+    /// Authority of the vault collecting this pool's creator fees
+    pub fn coin_creator_vault_authority(&self) -> Pubkey {
+        coin_creator_vault_authority(&self.coin_creator)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+    pub disable_flags: u8,
+    pub protocol_fee_recipients: [Pubkey; PROTOCOL_FEE_RECIPIENTS],
+    /// Zero on configs written before creator fees existed
+    pub coin_creator_fee_basis_points: u64,
+}
+
+impl GlobalConfig {
+    /// Size up to `protocol_fee_recipients`
+    pub const MIN_LEN: usize = 8 + 32 + 8 + 8 + 1 + 32 * PROTOCOL_FEE_RECIPIENTS;
+    pub const LEN: usize = Self::MIN_LEN + 8;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < Self::MIN_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut protocol_fee_recipients = [Pubkey::default(); PROTOCOL_FEE_RECIPIENTS];
+        for (i, recipient) in protocol_fee_recipients.iter_mut().enumerate() {
+            *recipient = read_pubkey(data, 57 + i * 32);
+        }
+        let coin_creator_fee_basis_points = if data.len() >= Self::LEN {
+            read_u64(data, Self::MIN_LEN)
+        } else {
+            0
+        };
+
+        Ok(Self {
+            admin: read_pubkey(data, 8),
+            lp_fee_basis_points: read_u64(data, 40),
+            protocol_fee_basis_points: read_u64(data, 48),
+            disable_flags: data[56],
+            protocol_fee_recipients,
+            coin_creator_fee_basis_points,
+        })
+    }
+}
+
+pub fn global_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &crate::ID).0
+}
+
+pub fn event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &crate::ID).0
+}
+
+pub fn coin_creator_vault_authority(coin_creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CREATOR_VAULT_SEED, coin_creator.as_ref()], &crate::ID).0
+}
@@ -1,5 +1,8 @@
-use anchor_gen::generate_cpi_crate;
-
-generate_cpi_crate!("./idl.json");
-declare_id!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+mod math;
+mod state;
+mod swap;
+pub use math::*;
+pub use state::*;
+pub use swap::*;
 
+solana_program::declare_id!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
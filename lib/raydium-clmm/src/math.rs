@@ -0,0 +1,242 @@
+use anyhow::{anyhow, bail, Result};
+
+// The arithmetic the macro generates trips lints that can't be fixed from here
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod uint_types {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+
+    construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+pub use uint_types::{U256, U512};
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = -MIN_TICK;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+/// Trade fee rates are stored in hundredths of a bip
+pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+
+const Q64: u128 = 1u128 << 64;
+const BIT_PRECISION: u32 = 16;
+
+/// Q64.64 sqrt price at a tick: sqrt(1.0001 ^ tick) * 2^64
+pub fn get_sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        bail!("Tick {} out of range", tick);
+    }
+
+    // i = 0
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from(0xfffcb933bd6fb800u128)
+    } else {
+        U256::from(Q64)
+    };
+    // Each constant is 2^64 / sqrt(1.0001) ^ (2^i)
+    let factors: [(u32, u128); 18] = [
+        (0x2, 0xfff97272373d4000),
+        (0x4, 0xfff2e50f5f657000),
+        (0x8, 0xffe5caca7e10f000),
+        (0x10, 0xffcb9843d60f7000),
+        (0x20, 0xff973b41fa98e800),
+        (0x40, 0xff2ea16466c9b000),
+        (0x80, 0xfe5dee046a9a3800),
+        (0x100, 0xfcbe86c7900bb000),
+        (0x200, 0xf987a7253ac65800),
+        (0x400, 0xf3392b0822bb6000),
+        (0x800, 0xe7159475a2caf000),
+        (0x1000, 0xd097f3bdfd2f2000),
+        (0x2000, 0xa9f746462d9f8000),
+        (0x4000, 0x70d869a156f31c00),
+        (0x8000, 0x31be135f97ed3200),
+        (0x10000, 0x9aa508b5b85a500),
+        (0x20000, 0x5d6af8dedc582c),
+        (0x40000, 0x2216e584f5fa),
+    ];
+    for (bit, factor) in factors {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * U256::from(factor)) >> 64;
+        }
+    }
+
+    // Divide to obtain 1 / ratio for positive ticks
+    if tick > 0 {
+        ratio = U256::from(u128::MAX) / ratio;
+    }
+
+    Ok(ratio.as_u128())
+}
+
+/// Greatest tick whose sqrt price is at most `sqrt_price_x64`
+pub fn get_tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        bail!("Sqrt price {} out of range", sqrt_price_x64);
+    }
+
+    // Integer part of log2 from the most significant bit
+    let msb: u32 = 128 - sqrt_price_x64.leading_zeros() - 1;
+    let log2p_integer_x32 = (msb as i128 - 64) << 32;
+
+    // Fractional part by repeated squaring
+    let mut bit: i128 = 0x8000_0000_0000_0000i128;
+    let mut precision = 0;
+    let mut log2p_fraction_x64 = 0;
+    let mut r = if msb >= 64 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+
+    while bit > 0 && precision < BIT_PRECISION {
+        r *= r;
+        let is_r_more_than_two = r >> 127_u32;
+        r >>= 63 + is_r_more_than_two;
+        log2p_fraction_x64 += bit * is_r_more_than_two as i128;
+        bit >>= 1;
+        precision += 1;
+    }
+
+    let log2p_fraction_x32 = log2p_fraction_x64 >> 32;
+    let log2p_x32 = log2p_integer_x32 + log2p_fraction_x32;
+
+    // Change of base to sqrt(1.0001), then bracket the error of the approximation
+    let log_sqrt_10001_x64 = log2p_x32 * 59543866431248i128;
+    let tick_low = ((log_sqrt_10001_x64 - 184467440737095516i128) >> 64) as i32;
+    let tick_high = ((log_sqrt_10001_x64 + 15793534762490258745i128) >> 64) as i32;
+
+    Ok(if tick_low == tick_high {
+        tick_low
+    } else if get_sqrt_price_at_tick(tick_high)? <= sqrt_price_x64 {
+        tick_high
+    } else {
+        tick_low
+    })
+}
+
+fn sorted(a: u128, b: u128) -> (u128, u128) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+/// a * b / denominator with a 512-bit intermediate. Saturates at U256::MAX, which
+/// callers reject as an out of range amount anyway.
+fn mul_div(a: U256, b: U256, denominator: U256, round_up: bool) -> U256 {
+    let widen = |v: U256| {
+        let mut bytes = [0u8; 64];
+        v.to_little_endian(&mut bytes[..32]);
+        U512::from_little_endian(&bytes)
+    };
+    let (quotient, remainder) = (widen(a) * widen(b)).div_mod(widen(denominator));
+    let quotient = if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    };
+    let mut bytes = [0u8; 64];
+    quotient.to_little_endian(&mut bytes);
+    if bytes[32..].iter().any(|b| *b != 0) {
+        return U256::MAX;
+    }
+    U256::from_little_endian(&bytes[..32])
+}
+
+fn to_u64(amount: U256) -> Result<u64> {
+    if amount > U256::from(u64::MAX) {
+        bail!("Token amount exceeds u64");
+    }
+    Ok(amount.as_u64())
+}
+
+/// Token 0 between two sqrt prices: L * (upper - lower) / (upper * lower)
+pub fn get_delta_amount_0_unsigned(
+    sqrt_ratio_a_x64: u128,
+    sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x64, sqrt_ratio_b_x64);
+    if lower == 0 {
+        bail!("Sqrt price must be positive");
+    }
+    let numerator_1 = U256::from(liquidity) << 64;
+    let numerator_2 = U256::from(upper - lower);
+    // Divide by upper then lower, rounding each step, as the program does
+    let amount = mul_div(numerator_1, numerator_2, U256::from(upper), round_up);
+    let (quotient, remainder) = amount.div_mod(U256::from(lower));
+    let amount = if round_up && !remainder.is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    };
+    to_u64(amount)
+}
+
+/// Token 1 between two sqrt prices: L * (upper - lower)
+pub fn get_delta_amount_1_unsigned(
+    sqrt_ratio_a_x64: u128,
+    sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = sorted(sqrt_ratio_a_x64, sqrt_ratio_b_x64);
+    to_u64(mul_div(
+        U256::from(liquidity),
+        U256::from(upper - lower),
+        U256::from(Q64),
+        round_up,
+    ))
+}
+
+/// Sqrt price after adding `amount_in` of the input token to the current range
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        bail!("Cannot move the price without liquidity");
+    }
+    if amount_in == 0 {
+        return Ok(sqrt_price_x64);
+    }
+
+    if zero_for_one {
+        // L * P / (L + amount * P), rounded up
+        let numerator_1 = U256::from(liquidity) << 64;
+        let product = U256::from(amount_in) * U256::from(sqrt_price_x64);
+        let denominator = numerator_1 + product;
+        let next = mul_div(numerator_1, U256::from(sqrt_price_x64), denominator, true);
+        if next > U256::from(u128::MAX) {
+            bail!("Sqrt price overflow");
+        }
+        Ok(next.as_u128())
+    } else {
+        // P + amount / L, rounded down
+        let quotient = (U256::from(amount_in) << 64) / U256::from(liquidity);
+        sqrt_price_x64
+            .checked_add(quotient.low_u128())
+            .filter(|_| quotient <= U256::from(u128::MAX))
+            .ok_or_else(|| anyhow!("Sqrt price overflow"))
+    }
+}
+
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let next = if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    };
+    next.ok_or_else(|| anyhow!("Liquidity delta {} overflows {}", delta, liquidity))
+}
@@ -0,0 +1,397 @@
+use anyhow::{anyhow, Result};
+use solana_program::pubkey::Pubkey;
+
+pub const TICK_ARRAY_SEED: &str = "tick_array";
+pub const TICK_ARRAY_SIZE: i32 = 60;
+pub const TICK_ARRAY_SIZE_USIZE: usize = 60;
+pub const REWARD_NUM: usize = 3;
+
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &str = "pool_tick_array_bitmap_extension";
+
+pub enum RewardState {
+    Uninitialized,
+    Initialized,
+    Opening,
+    Ended,
+}
+
+/// Little-endian cursor over account data, after the discriminator
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let bytes = self.data[self.offset..self.offset + N].try_into().unwrap();
+        self.offset += N;
+        bytes
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.offset += len;
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn i32(&mut self) -> i32 {
+        i32::from_le_bytes(self.take())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+
+    fn u128(&mut self) -> u128 {
+        u128::from_le_bytes(self.take())
+    }
+
+    fn i128(&mut self) -> i128 {
+        i128::from_le_bytes(self.take())
+    }
+
+    fn pubkey(&mut self) -> Pubkey {
+        Pubkey::new_from_array(self.take())
+    }
+}
+
+fn account_body<'a>(data: &'a [u8], len: usize, name: &str) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(anyhow!("Invalid data length for {}", name));
+    }
+    Ok(&data[8..len])
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+impl RewardInfo {
+    pub const LEN: usize = 1 + 8 * 3 + 16 + 8 * 2 + 32 * 3 + 16;
+
+    pub fn new(authority: Pubkey) -> Self {
+        Self {
+            authority,
+            ..Default::default()
+        }
+    }
+
+    pub fn initialized(&self) -> bool {
+        self.token_mint.ne(&Pubkey::default())
+    }
+
+    fn read(reader: &mut Reader) -> Self {
+        Self {
+            reward_state: reader.u8(),
+            open_time: reader.u64(),
+            end_time: reader.u64(),
+            last_update_time: reader.u64(),
+            emissions_per_second_x64: reader.u128(),
+            reward_total_emissioned: reader.u64(),
+            reward_claimed: reader.u64(),
+            token_mint: reader.pubkey(),
+            token_vault: reader.pubkey(),
+            authority: reader.pubkey(),
+            reward_growth_global_x64: reader.u128(),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct PoolState {
+    pub bump: [u8; 1],
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+
+    pub observation_key: Pubkey,
+
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+
+    pub padding3: u16,
+    pub padding4: u16,
+
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+
+    pub status: u8,
+    pub padding: [u8; 7],
+
+    pub reward_infos: [RewardInfo; REWARD_NUM],
+
+    pub tick_array_bitmap: [u64; 16],
+
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+
+    pub open_time: u64,
+    pub recent_epoch: u64,
+
+    pub padding1: [u64; 24],
+    pub padding2: [u64; 32],
+}
+
+impl PoolState {
+    pub const LEN: usize = 8 + 1536;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(account_body(data, Self::LEN, "RaydiumClmmPoolState")?);
+
+        let mut pool = Self {
+            bump: [reader.u8()],
+            amm_config: reader.pubkey(),
+            owner: reader.pubkey(),
+            token_mint_0: reader.pubkey(),
+            token_mint_1: reader.pubkey(),
+            token_vault_0: reader.pubkey(),
+            token_vault_1: reader.pubkey(),
+            observation_key: reader.pubkey(),
+            mint_decimals_0: reader.u8(),
+            mint_decimals_1: reader.u8(),
+            tick_spacing: reader.u16(),
+            liquidity: reader.u128(),
+            sqrt_price_x64: reader.u128(),
+            tick_current: reader.i32(),
+            padding3: reader.u16(),
+            padding4: reader.u16(),
+            fee_growth_global_0_x64: reader.u128(),
+            fee_growth_global_1_x64: reader.u128(),
+            protocol_fees_token_0: reader.u64(),
+            protocol_fees_token_1: reader.u64(),
+            swap_in_amount_token_0: reader.u128(),
+            swap_out_amount_token_1: reader.u128(),
+            swap_in_amount_token_1: reader.u128(),
+            swap_out_amount_token_0: reader.u128(),
+            status: reader.u8(),
+            padding: reader.take(),
+            ..Default::default()
+        };
+        for reward_info in pool.reward_infos.iter_mut() {
+            *reward_info = RewardInfo::read(&mut reader);
+        }
+        for word in pool.tick_array_bitmap.iter_mut() {
+            *word = reader.u64();
+        }
+        pool.total_fees_token_0 = reader.u64();
+        pool.total_fees_claimed_token_0 = reader.u64();
+        pool.total_fees_token_1 = reader.u64();
+        pool.total_fees_claimed_token_1 = reader.u64();
+        pool.fund_fees_token_0 = reader.u64();
+        pool.fund_fees_token_1 = reader.u64();
+        pool.open_time = reader.u64();
+        pool.recent_epoch = reader.u64();
+
+        Ok(pool)
+    }
+
+    /// Swaps are disabled when bit 4 of `status` is set
+    pub fn swap_enabled(&self) -> bool {
+        self.status & (1 << 4) == 0
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    pub protocol_fee_rate: u32,
+    /// Trade fee, denominated in hundredths of a bip (10^-6)
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+    pub fund_owner: Pubkey,
+}
+
+impl AmmConfig {
+    pub const LEN: usize = 8 + 1 + 2 + 32 + 4 + 4 + 2 + 4 + 4 + 32 + 8 * 3;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        // Only the fields up to tick_spacing are required to quote
+        let mut reader = Reader::new(account_body(
+            data,
+            data.len().max(8 + 1 + 2 + 32 + 4 + 4 + 2),
+            "RaydiumClmmAmmConfig",
+        )?);
+
+        let mut config = Self {
+            bump: reader.u8(),
+            index: reader.u16(),
+            owner: reader.pubkey(),
+            protocol_fee_rate: reader.u32(),
+            trade_fee_rate: reader.u32(),
+            tick_spacing: reader.u16(),
+            ..Default::default()
+        };
+        if data.len() >= Self::LEN {
+            config.fund_fee_rate = reader.u32();
+            reader.skip(4);
+            config.fund_owner = reader.pubkey();
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct TickState {
+    pub tick: i32,
+    /// Amount of net liquidity added when the tick is crossed left to right
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_0_x64: u128,
+    pub fee_growth_outside_1_x64: u128,
+    pub reward_growths_outside_x64: [u128; REWARD_NUM],
+}
+
+impl TickState {
+    pub const LEN: usize = 4 + 16 + 16 + 16 + 16 + 16 * REWARD_NUM + 4 * 13;
+
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+
+    fn read(reader: &mut Reader) -> Self {
+        let mut tick = Self {
+            tick: reader.i32(),
+            liquidity_net: reader.i128(),
+            liquidity_gross: reader.u128(),
+            fee_growth_outside_0_x64: reader.u128(),
+            fee_growth_outside_1_x64: reader.u128(),
+            ..Default::default()
+        };
+        for growth in tick.reward_growths_outside_x64.iter_mut() {
+            *growth = reader.u128();
+        }
+        reader.skip(4 * 13);
+        tick
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TickArrayState {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [TickState; TICK_ARRAY_SIZE_USIZE],
+    pub initialized_tick_count: u8,
+    pub recent_epoch: u64,
+}
+
+impl TickArrayState {
+    pub const LEN: usize = 8 + 32 + 4 + TickState::LEN * TICK_ARRAY_SIZE_USIZE + 1 + 8 + 107;
+
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(account_body(data, Self::LEN, "RaydiumClmmTickArrayState")?);
+
+        let pool_id = reader.pubkey();
+        let start_tick_index = reader.i32();
+        let mut ticks = [TickState::default(); TICK_ARRAY_SIZE_USIZE];
+        for tick in ticks.iter_mut() {
+            *tick = TickState::read(&mut reader);
+        }
+
+        Ok(Self {
+            pool_id,
+            start_tick_index,
+            ticks,
+            initialized_tick_count: reader.u8(),
+            recent_epoch: reader.u64(),
+        })
+    }
+
+    /// Start index of the array holding `tick_index`
+    pub fn get_array_start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = TICK_ARRAY_SIZE * i32::from(tick_spacing);
+        tick_index.div_euclid(ticks_in_array) * ticks_in_array
+    }
+
+    /// Next initialized tick in the swap direction from `current_tick_index`, which
+    /// must fall inside this array. Zero for one can land on the current tick.
+    pub fn next_initialized_tick(
+        &self,
+        current_tick_index: i32,
+        tick_spacing: u16,
+        zero_for_one: bool,
+    ) -> Option<&TickState> {
+        if Self::get_array_start_index(current_tick_index, tick_spacing) != self.start_tick_index {
+            return None;
+        }
+        let mut offset = (current_tick_index - self.start_tick_index) / i32::from(tick_spacing);
+
+        if zero_for_one {
+            while offset >= 0 {
+                if self.ticks[offset as usize].is_initialized() {
+                    return Some(&self.ticks[offset as usize]);
+                }
+                offset -= 1;
+            }
+        } else {
+            offset += 1;
+            while offset < TICK_ARRAY_SIZE {
+                if self.ticks[offset as usize].is_initialized() {
+                    return Some(&self.ticks[offset as usize]);
+                }
+                offset += 1;
+            }
+        }
+        None
+    }
+
+    /// First initialized tick met when entering this array in the swap direction
+    pub fn first_initialized_tick(&self, zero_for_one: bool) -> Option<&TickState> {
+        if zero_for_one {
+            self.ticks.iter().rev().find(|tick| tick.is_initialized())
+        } else {
+            self.ticks.iter().find(|tick| tick.is_initialized())
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use crate::math::{
+    add_delta, get_delta_amount_0_unsigned, get_delta_amount_1_unsigned,
+    get_next_sqrt_price_from_input, get_sqrt_price_at_tick, get_tick_at_sqrt_price,
+    FEE_RATE_DENOMINATOR_VALUE, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK,
+};
+use crate::state::{PoolState, TickArrayState, TickState};
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    /// Input consumed, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub end_sqrt_price_x64: u128,
+    pub end_tick: i32,
+    pub end_liquidity: u128,
+    /// How many of the leading tick arrays the swap read
+    pub tick_arrays_touched: usize,
+}
+
+/// One exact-in step from `sqrt_price_current_x64` towards `sqrt_price_target_x64`
+/// within a single liquidity range
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    zero_for_one: bool,
+) -> Result<SwapStep> {
+    let amount_in_range = |sqrt_price_next_x64: u128| {
+        if zero_for_one {
+            get_delta_amount_0_unsigned(
+                sqrt_price_next_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                true,
+            )
+        } else {
+            get_delta_amount_1_unsigned(
+                sqrt_price_current_x64,
+                sqrt_price_next_x64,
+                liquidity,
+                true,
+            )
+        }
+    };
+
+    let amount_remaining_less_fee = (u128::from(amount_remaining)
+        * u128::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate)
+        / u128::from(FEE_RATE_DENOMINATOR_VALUE)) as u64;
+    // None when draining the range needs more than a u64 of input
+    let max_amount_in = amount_in_range(sqrt_price_target_x64).ok();
+
+    let sqrt_price_next_x64 = match max_amount_in {
+        Some(max_in) if amount_remaining_less_fee >= max_in => sqrt_price_target_x64,
+        _ => get_next_sqrt_price_from_input(
+            sqrt_price_current_x64,
+            liquidity,
+            amount_remaining_less_fee,
+            zero_for_one,
+        )?,
+    };
+    let max = sqrt_price_next_x64 == sqrt_price_target_x64;
+
+    let amount_in = match max_amount_in {
+        Some(max_in) if max => max_in,
+        _ => amount_in_range(sqrt_price_next_x64)?,
+    };
+    let amount_out = if zero_for_one {
+        get_delta_amount_1_unsigned(
+            sqrt_price_next_x64,
+            sqrt_price_current_x64,
+            liquidity,
+            false,
+        )?
+    } else {
+        get_delta_amount_0_unsigned(
+            sqrt_price_current_x64,
+            sqrt_price_next_x64,
+            liquidity,
+            false,
+        )?
+    };
+
+    let fee_amount = if max {
+        (u128::from(amount_in) * u128::from(fee_rate))
+            .div_ceil(u128::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate)) as u64
+    } else {
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or_else(|| anyhow!("Amount in exceeds the remaining input"))?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next_x64,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Simulate an exact-in swap. `tick_arrays` are the initialized tick arrays in swap
+/// order, the way the program expects them: the first one holds the current tick
+/// unless that array is uninitialized, and empty arrays are skipped.
+///
+/// Fails when the arrays run out before `amount_in` is fully consumed.
+pub fn swap_exact_in(
+    pool: &PoolState,
+    trade_fee_rate: u32,
+    tick_arrays: &[TickArrayState],
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<SwapResult> {
+    if !pool.swap_enabled() {
+        bail!("Swaps are disabled on this pool");
+    }
+    if tick_arrays.is_empty() {
+        bail!("At least one tick array is required");
+    }
+    let sqrt_price_limit_x64 = if zero_for_one {
+        MIN_SQRT_PRICE_X64 + 1
+    } else {
+        MAX_SQRT_PRICE_X64 - 1
+    };
+
+    let mut amount_remaining = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut sqrt_price_x64 = pool.sqrt_price_x64;
+    let mut tick = pool.tick_current;
+    let mut liquidity = pool.liquidity;
+
+    let mut array_index = 0;
+    let mut is_match_pool_current_tick_array = tick_arrays[0].start_tick_index
+        == TickArrayState::get_array_start_index(tick, pool.tick_spacing);
+
+    while amount_remaining != 0
+        && sqrt_price_x64 != sqrt_price_limit_x64
+        && tick < MAX_TICK
+        && tick > MIN_TICK
+    {
+        let sqrt_price_start_x64 = sqrt_price_x64;
+
+        let mut next_tick = tick_arrays[array_index]
+            .next_initialized_tick(tick, pool.tick_spacing, zero_for_one)
+            .copied();
+        if next_tick.is_none() && !is_match_pool_current_tick_array {
+            is_match_pool_current_tick_array = true;
+            next_tick = tick_arrays[array_index]
+                .first_initialized_tick(zero_for_one)
+                .copied();
+        }
+        let next_tick = match next_tick {
+            Some(next_tick) => next_tick,
+            None => {
+                // Move on to the next array that still has initialized ticks
+                array_index = tick_arrays
+                    .iter()
+                    .enumerate()
+                    .skip(array_index + 1)
+                    .find(|(_, array)| array.initialized_tick_count > 0)
+                    .map(|(index, _)| index)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Swap needs more than the {} tick arrays provided",
+                            tick_arrays.len()
+                        )
+                    })?;
+                tick_arrays[array_index]
+                    .first_initialized_tick(zero_for_one)
+                    .copied()
+                    .unwrap_or_default()
+            }
+        };
+
+        let initialized = next_tick.is_initialized();
+        let tick_next = next_tick.tick.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_x64 = get_sqrt_price_at_tick(tick_next)?;
+        let target_price = if (zero_for_one && sqrt_price_next_x64 < sqrt_price_limit_x64)
+            || (!zero_for_one && sqrt_price_next_x64 > sqrt_price_limit_x64)
+        {
+            sqrt_price_limit_x64
+        } else {
+            sqrt_price_next_x64
+        };
+
+        let step = compute_swap_step(
+            sqrt_price_x64,
+            target_price,
+            liquidity,
+            amount_remaining,
+            trade_fee_rate,
+            zero_for_one,
+        )?;
+        sqrt_price_x64 = step.sqrt_price_next_x64;
+        amount_remaining = amount_remaining
+            .checked_sub(step.amount_in)
+            .and_then(|v| v.checked_sub(step.fee_amount))
+            .ok_or_else(|| anyhow!("Swap step consumed more than the remaining input"))?;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or_else(|| anyhow!("Amount out overflow"))?;
+        fee_amount += step.fee_amount;
+
+        if sqrt_price_x64 == sqrt_price_next_x64 {
+            if initialized {
+                liquidity = cross(liquidity, &next_tick, zero_for_one)?;
+            }
+            tick = if zero_for_one {
+                tick_next - 1
+            } else {
+                tick_next
+            };
+        } else if sqrt_price_x64 != sqrt_price_start_x64 {
+            tick = get_tick_at_sqrt_price(sqrt_price_x64)?;
+        }
+    }
+
+    if amount_remaining > 0 {
+        bail!(
+            "Not enough liquidity in the provided tick arrays, {} of {} unfilled",
+            amount_remaining,
+            amount_in
+        );
+    }
+
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        fee_amount,
+        end_sqrt_price_x64: sqrt_price_x64,
+        end_tick: tick,
+        end_liquidity: liquidity,
+        tick_arrays_touched: array_index + 1,
+    })
+}
+
+fn cross(liquidity: u128, tick: &TickState, zero_for_one: bool) -> Result<u128> {
+    let liquidity_net = if zero_for_one {
+        -tick.liquidity_net
+    } else {
+        tick.liquidity_net
+    };
+    add_delta(liquidity, liquidity_net)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{TICK_ARRAY_SIZE, TICK_ARRAY_SIZE_USIZE};
+    use solana_program::pubkey::Pubkey;
+
+    const TICK_SPACING: u16 = 10;
+
+    fn tick_array(start_tick_index: i32) -> TickArrayState {
+        let mut ticks = [TickState::default(); TICK_ARRAY_SIZE_USIZE];
+        for (i, tick) in ticks.iter_mut().enumerate() {
+            tick.tick = start_tick_index + i as i32 * TICK_SPACING as i32;
+        }
+        TickArrayState {
+            pool_id: Pubkey::default(),
+            start_tick_index,
+            ticks,
+            initialized_tick_count: 0,
+            recent_epoch: 0,
+        }
+    }
+
+    fn pool(liquidity: u128, tick_current: i32) -> PoolState {
+        PoolState {
+            tick_spacing: TICK_SPACING,
+            liquidity,
+            sqrt_price_x64: get_sqrt_price_at_tick(tick_current).unwrap(),
+            tick_current,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tick_math_bounds() {
+        assert_eq!(
+            get_sqrt_price_at_tick(MIN_TICK).unwrap(),
+            MIN_SQRT_PRICE_X64
+        );
+        assert_eq!(
+            get_sqrt_price_at_tick(MAX_TICK).unwrap(),
+            MAX_SQRT_PRICE_X64
+        );
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), 1u128 << 64);
+        for tick in [MIN_TICK, -50_000, -1, 0, 1, 50_000, MAX_TICK - 1] {
+            let sqrt_price = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn test_swap_crosses_into_next_array() {
+        let ticks_in_array = TICK_ARRAY_SIZE * TICK_SPACING as i32;
+        let mut current = tick_array(0);
+        let mut next = tick_array(ticks_in_array);
+        // One position from tick 0 to the start of the next array, a second from 650
+        // to 1100, and an empty crossing at 300 so small swaps stay in the first array
+        current.ticks[0].liquidity_net = 1_000_000_000;
+        current.ticks[0].liquidity_gross = 1_000_000_000;
+        current.ticks[30].liquidity_gross = 1;
+        current.initialized_tick_count = 2;
+        next.ticks[0].liquidity_net = -1_000_000_000;
+        next.ticks[0].liquidity_gross = 1_000_000_000;
+        next.ticks[5].liquidity_net = 2_000_000_000;
+        next.ticks[5].liquidity_gross = 2_000_000_000;
+        next.ticks[50].liquidity_net = -2_000_000_000;
+        next.ticks[50].liquidity_gross = 2_000_000_000;
+        next.initialized_tick_count = 3;
+
+        let pool = pool(1_000_000_000, 5);
+        let in_range =
+            swap_exact_in(&pool, 3000, &[current.clone(), next.clone()], 1_000, false).unwrap();
+        assert_eq!(in_range.tick_arrays_touched, 1);
+        assert_eq!(in_range.fee_amount, 3);
+        assert!(in_range.amount_out > 0 && in_range.amount_out < 997);
+
+        let large = 40_000_000;
+        let crossed = swap_exact_in(&pool, 3000, &[current.clone(), next], large, false).unwrap();
+        assert_eq!(crossed.tick_arrays_touched, 2);
+        assert_eq!(crossed.end_liquidity, 2_000_000_000);
+        assert!(crossed.end_tick >= ticks_in_array + 5 * TICK_SPACING as i32);
+
+        assert!(swap_exact_in(&pool, 3000, &[current], large, false).is_err());
+    }
+}
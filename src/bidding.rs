@@ -0,0 +1,318 @@
+use crate::config::BiddingConfig;
+use anyhow::{bail, Result};
+use rand::Rng;
+use std::sync::Arc;
+
+/// What one transaction offers to land
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bid {
+    /// In micro-lamports
+    pub compute_unit_price: u64,
+    /// Sender tip in lamports, 0 when no sender takes tips
+    pub tip_lamports: u64,
+}
+
+/// What a strategy knows when bidding for one transaction
+#[derive(Debug, Clone, Copy)]
+pub struct BidContext {
+    /// Quoted profit in lamports, None on the blind spam path
+    pub expected_profit: Option<i64>,
+    pub compute_unit_limit: u32,
+    /// Fixed or estimated compute unit price
+    pub base_compute_unit_price: u64,
+    /// Whether any sender takes tips
+    pub tipped: bool,
+    /// Smallest tip a tipping sender asks for
+    pub base_tip_lamports: u64,
+}
+
+impl BidContext {
+    pub fn base_bid(&self) -> Bid {
+        Bid {
+            compute_unit_price: self.base_compute_unit_price,
+            tip_lamports: if self.tipped {
+                self.base_tip_lamports
+            } else {
+                0
+            },
+        }
+    }
+}
+
+pub trait BiddingStrategy: Send + Sync {
+    fn bid(&self, context: &BidContext) -> Bid;
+
+    /// Whether bids depend on the expected profit, in which case it is quoted before
+    /// every send
+    fn uses_expected_profit(&self) -> bool {
+        false
+    }
+}
+
+/// Bids the base price and tip whatever the profit. The default for both quoted and
+/// blind sends.
+pub struct FixedBidding;
+
+impl BiddingStrategy for FixedBidding {
+    fn bid(&self, context: &BidContext) -> Bid {
+        context.base_bid()
+    }
+}
+
+/// Spends `profit_fraction` of the expected profit on landing the transaction, split
+/// between the sender tip and the priority fee
+pub struct ProfitShareBidding {
+    pub profit_fraction: f64,
+    pub tip_fraction: f64,
+    pub min_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+    pub min_tip_lamports: u64,
+    pub max_tip_lamports: u64,
+}
+
+impl BiddingStrategy for ProfitShareBidding {
+    fn bid(&self, context: &BidContext) -> Bid {
+        let Some(expected_profit) = context.expected_profit else {
+            return FixedBidding.bid(context);
+        };
+        let spend = (expected_profit.max(0) as f64 * self.profit_fraction) as u64;
+        let tip_lamports = if context.tipped {
+            ((spend as f64 * self.tip_fraction) as u64)
+                .clamp(self.min_tip_lamports, self.max_tip_lamports)
+        } else {
+            0
+        };
+        // The compute unit price is in micro-lamports
+        let priority_fee = spend.saturating_sub(tip_lamports);
+        let compute_unit_price =
+            u128::from(priority_fee) * 1_000_000 / u128::from(context.compute_unit_limit.max(1));
+        Bid {
+            compute_unit_price: (compute_unit_price.min(u128::from(u64::MAX)) as u64)
+                .clamp(self.min_compute_unit_price, self.max_compute_unit_price),
+            tip_lamports,
+        }
+    }
+
+    fn uses_expected_profit(&self) -> bool {
+        true
+    }
+}
+
+/// Bids a uniformly random compute unit price and tip within bounds, ignoring the profit.
+/// Tips without bounds are the smallest one a sender asks for.
+pub struct RandomBidding {
+    pub min_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+    pub tip_lamports: Option<(u64, u64)>,
+}
+
+impl BiddingStrategy for RandomBidding {
+    fn bid(&self, context: &BidContext) -> Bid {
+        let mut rng = rand::thread_rng();
+        let base = context.base_bid();
+        Bid {
+            compute_unit_price: rng
+                .gen_range(self.min_compute_unit_price..=self.max_compute_unit_price),
+            tip_lamports: match self.tip_lamports {
+                Some((min, max)) if context.tipped => rng.gen_range(min..=max),
+                _ => base.tip_lamports,
+            },
+        }
+    }
+}
+
+/// Strategy for sends made without a quote. Strategies that bid from the expected profit
+/// have nothing to work with there, so they are rejected.
+pub fn spam_from_config(config: Option<&BiddingConfig>) -> Result<Arc<dyn BiddingStrategy>> {
+    let strategy = from_config(config)?;
+    if strategy.uses_expected_profit() {
+        bail!("Sends without a quote can't bid from the expected profit");
+    }
+    Ok(strategy)
+}
+
+pub fn from_config(config: Option<&BiddingConfig>) -> Result<Arc<dyn BiddingStrategy>> {
+    match config {
+        None | Some(BiddingConfig::Fixed) => Ok(Arc::new(FixedBidding)),
+        Some(BiddingConfig::ProfitShare {
+            profit_fraction,
+            tip_fraction,
+            min_compute_unit_price,
+            max_compute_unit_price,
+            min_tip_lamports,
+            max_tip_lamports,
+        }) => {
+            let tip_fraction = tip_fraction.unwrap_or(0.5);
+            let min_tip_lamports = min_tip_lamports.unwrap_or(0);
+            let max_tip_lamports = max_tip_lamports.unwrap_or(u64::MAX);
+            if !(0.0..=1.0).contains(profit_fraction) || !(0.0..=1.0).contains(&tip_fraction) {
+                bail!("Bidding fractions must be between 0 and 1");
+            }
+            if min_compute_unit_price > max_compute_unit_price
+                || min_tip_lamports > max_tip_lamports
+            {
+                bail!("Bidding minimums must not exceed their maximums");
+            }
+            Ok(Arc::new(ProfitShareBidding {
+                profit_fraction: *profit_fraction,
+                tip_fraction,
+                min_compute_unit_price: *min_compute_unit_price,
+                max_compute_unit_price: *max_compute_unit_price,
+                min_tip_lamports,
+                max_tip_lamports,
+            }))
+        }
+        Some(BiddingConfig::Random {
+            min_compute_unit_price,
+            max_compute_unit_price,
+            min_tip_lamports,
+            max_tip_lamports,
+        }) => {
+            let tip_lamports = match (min_tip_lamports, max_tip_lamports) {
+                (None, None) => None,
+                (min, max) => Some((min.unwrap_or(0), max.unwrap_or(u64::MAX))),
+            };
+            if min_compute_unit_price > max_compute_unit_price
+                || tip_lamports.is_some_and(|(min, max)| min > max)
+            {
+                bail!("Bidding minimums must not exceed their maximums");
+            }
+            Ok(Arc::new(RandomBidding {
+                min_compute_unit_price: *min_compute_unit_price,
+                max_compute_unit_price: *max_compute_unit_price,
+                tip_lamports,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profit_share_bids() {
+        let strategy = from_config(Some(&BiddingConfig::ProfitShare {
+            profit_fraction: 0.5,
+            tip_fraction: Some(0.8),
+            min_compute_unit_price: 1_000,
+            max_compute_unit_price: 100_000,
+            min_tip_lamports: Some(1_000),
+            max_tip_lamports: None,
+        }))
+        .unwrap();
+        assert!(strategy.uses_expected_profit());
+        let context = BidContext {
+            expected_profit: Some(100_000),
+            compute_unit_limit: 200_000,
+            base_compute_unit_price: 5_000,
+            tipped: true,
+            base_tip_lamports: 10_000,
+        };
+
+        // Half the profit is spent, 40_000 lamports of it on the tip
+        assert_eq!(
+            strategy.bid(&context),
+            Bid {
+                compute_unit_price: 50_000,
+                tip_lamports: 40_000,
+            }
+        );
+        // Without a tipping sender the whole spend would go to the priority fee, up to the ceiling
+        let untipped = BidContext {
+            tipped: false,
+            ..context
+        };
+        assert_eq!(
+            strategy.bid(&untipped),
+            Bid {
+                compute_unit_price: 100_000,
+                tip_lamports: 0,
+            }
+        );
+        // Losses bid the floor
+        let losing = BidContext {
+            expected_profit: Some(-5_000),
+            ..context
+        };
+        assert_eq!(
+            strategy.bid(&losing),
+            Bid {
+                compute_unit_price: 1_000,
+                tip_lamports: 1_000,
+            }
+        );
+        // Blind spam falls back to the base bid
+        let blind = BidContext {
+            expected_profit: None,
+            ..context
+        };
+        assert_eq!(strategy.bid(&blind), context.base_bid());
+
+        assert!(from_config(Some(&BiddingConfig::ProfitShare {
+            profit_fraction: 1.5,
+            tip_fraction: None,
+            min_compute_unit_price: 0,
+            max_compute_unit_price: 0,
+            min_tip_lamports: None,
+            max_tip_lamports: None,
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_spam_bids() {
+        let context = BidContext {
+            expected_profit: None,
+            compute_unit_limit: 200_000,
+            base_compute_unit_price: 5_000,
+            tipped: true,
+            base_tip_lamports: 10_000,
+        };
+        let fixed = spam_from_config(None).unwrap();
+        assert_eq!(fixed.bid(&context), context.base_bid());
+
+        let random = spam_from_config(Some(&BiddingConfig::Random {
+            min_compute_unit_price: 1_000,
+            max_compute_unit_price: 2_000,
+            min_tip_lamports: Some(20_000),
+            max_tip_lamports: Some(30_000),
+        }))
+        .unwrap();
+        for _ in 0..100 {
+            let bid = random.bid(&context);
+            assert!((1_000..=2_000).contains(&bid.compute_unit_price));
+            assert!((20_000..=30_000).contains(&bid.tip_lamports));
+        }
+        let untipped = BidContext {
+            tipped: false,
+            ..context
+        };
+        assert_eq!(random.bid(&untipped).tip_lamports, 0);
+        // Without tip bounds it tips what the senders ask for
+        let random = spam_from_config(Some(&BiddingConfig::Random {
+            min_compute_unit_price: 1_000,
+            max_compute_unit_price: 1_000,
+            min_tip_lamports: None,
+            max_tip_lamports: None,
+        }))
+        .unwrap();
+        assert_eq!(
+            random.bid(&context),
+            Bid {
+                compute_unit_price: 1_000,
+                tip_lamports: 10_000,
+            }
+        );
+
+        assert!(spam_from_config(Some(&BiddingConfig::ProfitShare {
+            profit_fraction: 0.5,
+            tip_fraction: None,
+            min_compute_unit_price: 0,
+            max_compute_unit_price: 0,
+            min_tip_lamports: None,
+            max_tip_lamports: None,
+        }))
+        .is_err());
+    }
+}
@@ -1,18 +1,25 @@
-use crate::config::Config;
-use crate::dex::meteora::{constants::dlmm_program_id, dlmm_info::DlmmInfo};
-use crate::dex::raydium::{
-    get_tick_array_pubkeys, raydium_clmm_program_id,
-    PoolState
+use crate::bidding::{self, BidContext};
+use crate::clock::{
+    blockhash_refresher, slot_tracker, websocket_url, ChainClock, DEFAULT_MIN_REMAINING_BLOCKS,
+    DEFAULT_REFRESH_EVERY_SLOTS,
 };
-use crate::dex::whirlpool::{
-    constants::whirlpool_program_id, state::Whirlpool, update_tick_array_accounts_for_onchain,
+use crate::config::{Config, StateSourceConfig};
+use crate::confirmation::{confirmation_tracker, ConfirmationTracker, DEFAULT_POLL_INTERVAL};
+use crate::nonce::{self, nonce_refresher, NoncePool, DEFAULT_REUSE_AFTER};
+use crate::priority_fee::{priority_fee_refresher, PriorityFeeOracle, DEFAULT_REFRESH_INTERVAL};
+use crate::refresh::{fetch_pool_accounts, initialize_pool_data, pool_state_refresher};
+use crate::sender::{SenderRouter, DEFAULT_SEND_TIMEOUT};
+use crate::simulation::{Simulator, DEFAULT_COMPUTE_UNIT_MARGIN};
+use crate::sizing::SizingParams;
+use crate::snapshot::SnapshotCell;
+use crate::state_cache::{
+    self, account_subscriber, AccountSource, PollingSource, StateCache, WebsocketSource,
 };
-use crate::refresh::initialize_pool_data;
 use crate::transaction::build_and_send_transaction;
+use crate::trigger::{MintTrigger, Trigger};
 use anyhow::Context;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
-use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
@@ -26,48 +33,150 @@ use spl_associated_token_account::{
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
+
+/// Compute unit price for setup transactions when priority fees aren't estimated
+const SETUP_COMPUTE_UNIT_PRICE: u64 = 1_000_000;
+
 pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
     let config = Config::load(config_path)?;
     info!("Configuration loaded successfully");
 
     let rpc_client = Arc::new(RpcClient::new(config.rpc.url.clone()));
 
-    let sending_rpc_clients = if let Some(spam_config) = &config.spam {
-        if spam_config.enabled {
-            spam_config
-                .sending_rpc_urls
-                .iter()
-                .map(|url| Arc::new(RpcClient::new(url.clone())))
-                .collect::<Vec<_>>()
-        } else {
-            vec![rpc_client.clone()]
-        }
-    } else {
-        vec![rpc_client.clone()]
+    let router = Arc::new(
+        SenderRouter::from_config(&config)
+            .await
+            .context("Failed to set up transaction senders")?,
+    );
+    router.warm_up().await;
+    let send_timeout = config
+        .sending
+        .as_ref()
+        .and_then(|s| s.timeout_ms)
+        .or_else(|| config.spam.as_ref().and_then(|s| s.send_timeout_ms))
+        .map_or(DEFAULT_SEND_TIMEOUT, Duration::from_millis);
+    let ws_url = config
+        .rpc
+        .ws_url
+        .clone()
+        .unwrap_or_else(|| websocket_url(&config.rpc.url));
+
+    let simulator = match &config.simulation {
+        Some(simulation_config) if simulation_config.enabled => Some(Arc::new(Simulator::new(
+            config.rpc.url.clone(),
+            send_timeout,
+            simulation_config
+                .compute_unit_margin
+                .unwrap_or(DEFAULT_COMPUTE_UNIT_MARGIN),
+        ))),
+        _ => None,
     };
 
     let wallet_kp =
         load_keypair(&config.wallet.private_key).context("Failed to load wallet keypair")?;
     info!("Wallet loaded: {}", wallet_kp.pubkey());
 
-    let initial_blockhash = rpc_client.get_latest_blockhash()?;
-    let cached_blockhash = Arc::new(Mutex::new(initial_blockhash));
+    let clock = Arc::new(ChainClock::new(
+        config
+            .clock
+            .as_ref()
+            .and_then(|c| c.min_remaining_blocks)
+            .unwrap_or(DEFAULT_MIN_REMAINING_BLOCKS),
+    ));
+    clock.refresh_slot(&rpc_client)?;
+    clock.refresh_blockhash(&rpc_client)?;
+    clock.refresh_sysvar(&rpc_client)?;
+
+    let slot_ws_url = ws_url.clone();
+    let slot_client = rpc_client.clone();
+    let slot_clock = clock.clone();
+    tokio::spawn(async move {
+        slot_tracker(slot_ws_url, slot_client, slot_clock).await;
+    });
 
-    let refresh_interval = Duration::from_secs(10);
+    let state_source: Arc<dyn AccountSource> = match &config.state_source {
+        None | Some(StateSourceConfig::Websocket) => {
+            Arc::new(WebsocketSource::new(ws_url, rpc_client.clone()))
+        }
+        Some(StateSourceConfig::Polling { interval_ms }) => Arc::new(PollingSource::new(
+            rpc_client.clone(),
+            interval_ms.map_or(state_cache::DEFAULT_POLL_INTERVAL, Duration::from_millis),
+        )),
+        #[cfg(feature = "geyser")]
+        Some(StateSourceConfig::Geyser { url, x_token }) => Arc::new(
+            crate::geyser::GeyserSource::new(url.clone(), x_token.clone(), rpc_client.clone()),
+        ),
+        #[cfg(not(feature = "geyser"))]
+        Some(StateSourceConfig::Geyser { .. }) => {
+            anyhow::bail!("The geyser state source needs a build with `--features geyser`")
+        }
+    };
+    info!(
+        "Following pool state through the {} source",
+        state_source.name()
+    );
+
+    let state_cache = Arc::new(StateCache::new());
+    let fallback = PollingSource::new(rpc_client.clone(), state_cache::DEFAULT_POLL_INTERVAL);
+    let subscriber_cache = state_cache.clone();
+    tokio::spawn(async move {
+        account_subscriber(state_source, fallback, subscriber_cache).await;
+    });
+
+    let refresh_every_slots = config
+        .clock
+        .as_ref()
+        .and_then(|c| c.refresh_every_slots)
+        .unwrap_or(DEFAULT_REFRESH_EVERY_SLOTS);
     let blockhash_client = rpc_client.clone();
-    let blockhash_cache = cached_blockhash.clone();
+    let blockhash_clock = clock.clone();
     tokio::spawn(async move {
-        blockhash_refresher(blockhash_client, blockhash_cache, refresh_interval).await;
+        blockhash_refresher(blockhash_client, blockhash_clock, refresh_every_slots).await;
     });
 
+    let nonce_pool = match &config.nonce {
+        Some(nonce_config) if nonce_config.enabled => {
+            let accounts = nonce_config
+                .accounts
+                .iter()
+                .map(|account| Pubkey::from_str(account))
+                .collect::<Result<Vec<_>, _>>()
+                .context("Invalid nonce account")?;
+            let pool = Arc::new(NoncePool::new(
+                wallet_kp.pubkey(),
+                accounts,
+                DEFAULT_REUSE_AFTER,
+            ));
+            pool.refresh(&rpc_client)
+                .context("Failed to load nonce accounts")?;
+            let nonce_client = rpc_client.clone();
+            let nonce_pool = pool.clone();
+            tokio::spawn(async move {
+                nonce_refresher(nonce_client, nonce_pool, nonce::DEFAULT_REFRESH_INTERVAL).await;
+            });
+            Some(pool)
+        }
+        _ => None,
+    };
+
+    let tracker = Arc::new(ConfirmationTracker::default());
+    let tracker_client = rpc_client.clone();
+    let tracker_clone = tracker.clone();
+    tokio::spawn(async move {
+        confirmation_tracker(tracker_client, tracker_clone, DEFAULT_POLL_INTERVAL).await;
+    });
+
+    let pool_accounts = fetch_pool_accounts(&rpc_client, &config.routing.mint_config_list).await;
     for mint_config in &config.routing.mint_config_list {
-        // Get the mint account info to check owner
-        let mint_owner = rpc_client
-            .get_account(&Pubkey::from_str(&mint_config.mint).unwrap())
-            .unwrap()
-            .owner;
+        // The mint account is fetched with the pools, its owner is the token program
+        let Some(mint_owner) = pool_accounts
+            .get(&Pubkey::from_str(&mint_config.mint).unwrap())
+            .map(|account| account.owner)
+        else {
+            error!("Mint account {} not found, skipping", mint_config.mint);
+            continue;
+        };
         let wallet_token_account = get_associated_token_address_with_program_id(
             &wallet_kp.pubkey(),
             &Pubkey::from_str(&mint_config.mint).unwrap(),
@@ -99,8 +208,16 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
                     // Get a recent blockhash
                     let blockhash = rpc_client.get_latest_blockhash()?;
 
+                    let compute_unit_price = setup_compute_unit_price(
+                        &config,
+                        &rpc_client,
+                        vec![
+                            wallet_token_account,
+                            Pubkey::from_str(&mint_config.mint).unwrap(),
+                        ],
+                    );
                     let compute_unit_price_ix =
-                        ComputeBudgetInstruction::set_compute_unit_price(1_000_000);
+                        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price);
                     let compute_unit_limit_ix =
                         ComputeBudgetInstruction::set_compute_unit_limit(60_000);
 
@@ -130,7 +247,7 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
     for mint_config in &config.routing.mint_config_list {
         info!("Processing mint: {}", mint_config.mint);
 
-        let pool_data = initialize_pool_data(
+        let pool_data = match initialize_pool_data(
             &mint_config.mint,
             &wallet_kp.pubkey().to_string(),
             mint_config.raydium_pool_list.as_ref(),
@@ -143,142 +260,70 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
             mint_config.solfi_pool_list.as_ref(),
             mint_config.meteora_damm_v2_pool_list.as_ref(),
             mint_config.vertigo_pool_list.as_ref(),
-            rpc_client.clone(), // Clone the Arc<RpcClient> to avoid moving it
-        )
-        .await?;
-
-        let mint_pool_data = Arc::new(Mutex::new(pool_data));
-        // TODO: Add logic to periodically refresh pool data
-        let mint_pool_data_clone = mint_pool_data.clone();
-        let rpc_client_clone= rpc_client.clone();
-        tokio::spawn(async move {
-            let refresh_interval = Duration::from_secs(5); // 每 5 秒刷新一次
-            loop {
-                let mut guard = mint_pool_data_clone.lock().await;
-
-                // 更新 Raydium CLMM 缓存
-
-                for clmm_pool in guard.raydium_clmm_pools.iter_mut() {
-                    match rpc_client_clone.get_account(&clmm_pool.pool) {
-                        Ok(account) => {
-                            if account.owner == raydium_clmm_program_id() {
-                                match PoolState::load_checked(&account.data) {
-                                    Ok(raydium_clmm) => {
-                                        let tick_array_pubkeys = get_tick_array_pubkeys(
-                                            &clmm_pool.pool,
-                                            raydium_clmm.tick_current,
-                                            raydium_clmm.tick_spacing,
-                                            &[-1, 0, 1],
-                                            &raydium_clmm_program_id(),
-                                        )
-                                        .unwrap();
-                                      
-                                        clmm_pool.tick_arrays = tick_array_pubkeys;
-                                        info!(
-                                            "freshing Raydium CLMM pool {:?} with tick arrays",
-                                            clmm_pool.pool
-                                        );
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to load Raydium CLMM pool {}: {:?}",
-                                            clmm_pool.pool, e
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to fetch Raydium CLMM pool {}: {:?}",
-                                clmm_pool.pool, e
-                            );
-                        }
-                    }
-                }
-
-                // 更新 Meteora DLMM 缓存
-                for dlmm_pool in guard.dlmm_pairs.iter_mut() {
-                    match rpc_client_clone.get_account(&dlmm_pool.pair) {
-                        Ok(account) => {
-                            if account.owner == dlmm_program_id() {
-                                match DlmmInfo::load_checked(&account.data) {
-                                    Ok(dlmm_info) => {
-                                        let bin_arrays = dlmm_info
-                                            .calculate_bin_arrays(&dlmm_pool.pair)
-                                            .unwrap_or_default();
-                                        dlmm_pool.bin_arrays = bin_arrays;
-                                        info!(
-                                            "freshing Meteora DLMM pool {:?} with bin arrays",
-                                            dlmm_pool.pair
-                                        );
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to load DLMM pool {:?}: {:?}",
-                                            dlmm_pool.pair, e
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to fetch DLMM pool {}: {:?}", dlmm_pool.pair, e);
-                        }
-                    }
-                }
+            &pool_accounts,
+        ) {
+            Ok(pool_data) => pool_data,
+            Err(e) => {
+                error!("Skipping mint {}: {}", mint_config.mint, e);
+                continue;
+            }
+        };
 
-                // 更新 Whirlpool 缓存
+        let mint_pool_data = Arc::new(SnapshotCell::new(pool_data));
+        let refresher_cache = state_cache.clone();
+        let refresher_pool_data = mint_pool_data.clone();
+        tokio::spawn(async move {
+            pool_state_refresher(refresher_cache, refresher_pool_data).await;
+        });
 
-                for whirlpool_pool in guard.whirlpool_pools.iter_mut() {
-                    match rpc_client_clone.get_account(&whirlpool_pool.pool) {
-                        Ok(account) => {
-                            if account.owner == whirlpool_program_id() {
-                                match Whirlpool::try_deserialize(&account.data) {
-                                    Ok(whirlpool) => {
-                                        let tick_array_pubkeys_account =
-                                            update_tick_array_accounts_for_onchain(
-                                                &whirlpool,
-                                                &whirlpool_pool.pool,
-                                                &whirlpool_program_id(),
-                                            );
-                                        let tick_array_pubkeys: Vec<Pubkey> = tick_array_pubkeys_account
-                                            .iter()
-                                            .map(|meta| meta.pubkey)
-                                            .collect();
-                                        whirlpool_pool.tick_arrays = tick_array_pubkeys;
-                                        info!(
-                                            "freshing whirlpool_pool {:?} with  tick arrays",
-                                            whirlpool_pool.pool
-                                        );
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to load Whirlpool pool {:?}: {:?}",
-                                            whirlpool_pool.pool, e
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!(
-                                "Failed to fetch Whirlpool pool {:?}: {:?}",
-                                whirlpool_pool.pool, e
-                            );
-                        }
-                    }
+        let priority_fee_oracle = match &config.priority_fee {
+            Some(priority_fee_config) if priority_fee_config.enabled => {
+                let oracle = Arc::new(PriorityFeeOracle::new(
+                    priority_fee_config,
+                    mint_pool_data.load().writable_pool_accounts(),
+                ));
+                if let Err(e) = oracle.refresh(&rpc_client) {
+                    warn!(
+                        "Failed to estimate priority fees for mint {}: {}",
+                        mint_config.mint, e
+                    );
                 }
-
-                drop(guard); // 释放锁
-                tokio::time::sleep(refresh_interval).await;
+                let refresh_interval = priority_fee_config
+                    .refresh_interval_ms
+                    .map_or(DEFAULT_REFRESH_INTERVAL, Duration::from_millis);
+                let priority_fee_client = rpc_client.clone();
+                let priority_fee_oracle = oracle.clone();
+                tokio::spawn(async move {
+                    priority_fee_refresher(
+                        priority_fee_client,
+                        priority_fee_oracle,
+                        refresh_interval,
+                    )
+                    .await;
+                });
+                Some(oracle)
             }
-        });
+            _ => None,
+        };
+        let fixed_compute_unit_price = config.spam.as_ref().map_or(1000, |s| s.compute_unit_price);
+
+        let bidding_strategy = bidding::from_config(mint_config.bidding.as_ref())
+            .with_context(|| format!("Invalid bidding for mint {}", mint_config.mint))?;
+        let spam_bidding_strategy = bidding::spam_from_config(mint_config.spam_bidding.as_ref())
+            .with_context(|| format!("Invalid spam bidding for mint {}", mint_config.mint))?;
+        let compute_unit_limit = config.bot.compute_unit_limit;
+        let min_tip_lamports = router.min_tip_lamports();
 
         let config_clone = config.clone();
+        let profit_config = config.profit_config(mint_config);
+        let quote_rpc_client = rpc_client.clone();
+        let quote_cache = state_cache.clone();
         let mint_config_clone = mint_config.clone();
-        let sending_rpc_clients_clone = sending_rpc_clients.clone();
-        let cached_blockhash_clone = cached_blockhash.clone();
+        let router_clone = router.clone();
+        let tracker_clone = tracker.clone();
+        let simulator_clone = simulator.clone();
+        let nonce_pool_clone = nonce_pool.clone();
+        let clock_clone = clock.clone();
         let wallet_bytes = wallet_kp.to_bytes();
         let wallet_kp_clone = Keypair::from_bytes(&wallet_bytes).unwrap();
         let mut lookup_table_accounts = mint_config_clone.lookup_table_accounts.unwrap_or_default();
@@ -333,22 +378,99 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
             );
         }
 
-        tokio::spawn(async move {
-            let process_delay = Duration::from_millis(mint_config_clone.process_delay);
+        let mut trigger = MintTrigger::new(
+            state_cache.clone(),
+            mint_pool_data.load().quote_accounts(),
+            mint_config.heartbeat_ms.map(Duration::from_millis),
+            Duration::from_millis(mint_config.process_delay),
+        );
 
+        tokio::spawn(async move {
             loop {
-                let latest_blockhash = {
-                    let guard = cached_blockhash_clone.lock().await;
-                    *guard
+                let reason = trigger.next().await;
+                let pool_snapshot = mint_pool_data.load();
+                trigger.set_accounts(pool_snapshot.quote_accounts());
+
+                let expected_profit = if reason == Trigger::StateChanged
+                    || profit_config.dynamic_minimum_profit
+                    || bidding_strategy.uses_expected_profit()
+                {
+                    match pool_snapshot
+                        .expected_profit(
+                            &quote_cache,
+                            &quote_rpc_client,
+                            &clock_clone,
+                            &SizingParams::default(),
+                        )
+                        .await
+                    {
+                        Ok(expected_profit) => expected_profit,
+                        Err(e) => {
+                            error!("Failed to quote mint {}: {}", mint_config_clone.mint, e);
+                            None
+                        }
+                    }
+                } else {
+                    None
                 };
+                // Heartbeats send regardless, state changes only when the quote pays
+                if reason == Trigger::StateChanged
+                    && !expected_profit.is_some_and(|p| profit_config.clears_minimum_profit(p))
+                {
+                    debug!(
+                        "No profitable route for mint {} after a state change",
+                        mint_config_clone.mint
+                    );
+                    continue;
+                }
 
-                let guard = mint_pool_data.lock().await;
+                let nonce = nonce_pool_clone.as_ref().and_then(|pool| pool.acquire());
+                if nonce_pool_clone.is_some() && nonce.is_none() {
+                    debug!(
+                        "No nonce available for mint {}, using the cached blockhash",
+                        mint_config_clone.mint
+                    );
+                }
+                let Some(latest_blockhash) = nonce
+                    .as_ref()
+                    .map(|nonce| nonce.value)
+                    .or_else(|| clock_clone.blockhash())
+                else {
+                    warn!(
+                        "Blockhash is close to expiry, not sending for mint {}",
+                        mint_config_clone.mint
+                    );
+                    continue;
+                };
+
+                let strategy = if expected_profit.is_some() {
+                    &bidding_strategy
+                } else {
+                    &spam_bidding_strategy
+                };
+                let bid = strategy.bid(&BidContext {
+                    expected_profit,
+                    compute_unit_limit,
+                    base_compute_unit_price: priority_fee_oracle
+                        .as_ref()
+                        .map_or(fixed_compute_unit_price, |oracle| {
+                            oracle.compute_unit_price()
+                        }),
+                    tipped: min_tip_lamports.is_some(),
+                    base_tip_lamports: min_tip_lamports.unwrap_or(0),
+                });
 
                 match build_and_send_transaction(
                     &wallet_kp_clone,
                     &config_clone,
-                    &*guard, // Dereference the guard here
-                    &sending_rpc_clients_clone,
+                    &profit_config,
+                    expected_profit,
+                    &pool_snapshot,
+                    bid,
+                    &router_clone,
+                    &tracker_clone,
+                    simulator_clone.as_deref(),
+                    nonce.as_ref(),
                     latest_blockhash,
                     &lookup_table_accounts_list,
                 )
@@ -356,8 +478,8 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
                 {
                     Ok(signatures) => {
                         info!(
-                            "Transactions sent successfully for mint {}",
-                            mint_config_clone.mint
+                            "Transactions sent successfully for mint {} from pool snapshot {}",
+                            mint_config_clone.mint, pool_snapshot.version
                         );
                         for signature in signatures {
                             info!("  Signature: {}", signature);
@@ -370,8 +492,6 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
                         );
                     }
                 }
-
-                tokio::time::sleep(process_delay).await;
             }
         });
     }
@@ -381,24 +501,40 @@ pub async fn run_bot(config_path: &str) -> anyhow::Result<()> {
     }
 }
 
-async fn blockhash_refresher(
-    rpc_client: Arc<RpcClient>,
-    cached_blockhash: Arc<Mutex<Hash>>,
-    refresh_interval: Duration,
-) {
-    loop {
-        match rpc_client.get_latest_blockhash() {
-            Ok(blockhash) => {
-                let mut guard = cached_blockhash.lock().await;
-                *guard = blockhash;
-                info!("Blockhash refreshed: {}", blockhash);
-            }
-            Err(e) => {
-                error!("Failed to refresh blockhash: {:?}", e);
+/// Estimates the price from fees recently paid to write `accounts`, falling back to
+/// `SETUP_COMPUTE_UNIT_PRICE`
+fn setup_compute_unit_price(config: &Config, rpc_client: &RpcClient, accounts: Vec<Pubkey>) -> u64 {
+    match &config.priority_fee {
+        Some(priority_fee_config) if priority_fee_config.enabled => {
+            let oracle = PriorityFeeOracle::new(priority_fee_config, accounts);
+            match oracle.refresh(rpc_client) {
+                Ok(compute_unit_price) => compute_unit_price,
+                Err(e) => {
+                    warn!("Failed to estimate priority fees: {}", e);
+                    oracle.clamp(SETUP_COMPUTE_UNIT_PRICE)
+                }
             }
         }
-        tokio::time::sleep(refresh_interval).await;
+        _ => SETUP_COMPUTE_UNIT_PRICE,
+    }
+}
+
+/// Creates `count` durable nonce accounts owned by the configured wallet and prints them
+/// for the `[nonce]` section of the config
+pub fn create_nonce_accounts(config_path: &str, count: usize) -> anyhow::Result<()> {
+    let config = Config::load(config_path)?;
+    let rpc_client = RpcClient::new(config.rpc.url.clone());
+    let wallet_kp =
+        load_keypair(&config.wallet.private_key).context("Failed to load wallet keypair")?;
+
+    let accounts = nonce::create_nonce_accounts(&rpc_client, &wallet_kp, count)?;
+    println!("   Created {} nonce accounts:", accounts.len());
+    println!("accounts = [");
+    for account in accounts {
+        println!("  \"{}\",", account);
     }
+    println!("]");
+    Ok(())
 }
 
 fn load_keypair(private_key: &str) -> anyhow::Result<Keypair> {
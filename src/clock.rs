@@ -0,0 +1,317 @@
+use crate::reconnect::subscribe_or_poll;
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::from_account;
+use solana_sdk::clock::{Clock, DEFAULT_MS_PER_SLOT};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::sysvar;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info};
+
+pub const DEFAULT_REFRESH_EVERY_SLOTS: u64 = 10;
+
+/// Blockhashes with fewer blocks than this left before they expire are not signed with
+pub const DEFAULT_MIN_REMAINING_BLOCKS: u64 = 30;
+
+/// Slot polling interval while the websocket is down, about one slot
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// A blockhash and where the chain was when it was fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockhashInfo {
+    pub hash: Hash,
+    pub last_valid_block_height: u64,
+    pub block_height: u64,
+    pub slot: u64,
+}
+
+impl BlockhashInfo {
+    /// Blocks left before the hash expires at `slot`. Every slot since the fetch is
+    /// counted as a block, so skipped slots only make this err on the early side.
+    pub fn remaining_blocks(&self, slot: u64) -> u64 {
+        let block_height = self.block_height + slot.saturating_sub(self.slot);
+        self.last_valid_block_height.saturating_sub(block_height)
+    }
+}
+
+/// Current slot, cluster time and a blockhash that is safe to sign with, shared by every
+/// task. Tasks that need to act on new slots subscribe to its ticks.
+pub struct ChainClock {
+    slot: AtomicU64,
+    blockhash: RwLock<Option<BlockhashInfo>>,
+    /// Clock sysvar as of its last refresh
+    sysvar: RwLock<Option<Clock>>,
+    ticks: broadcast::Sender<u64>,
+    min_remaining_blocks: u64,
+}
+
+impl ChainClock {
+    pub fn new(min_remaining_blocks: u64) -> Self {
+        Self {
+            slot: AtomicU64::new(0),
+            blockhash: RwLock::new(None),
+            sysvar: RwLock::new(None),
+            ticks: broadcast::channel(64).0,
+            min_remaining_blocks,
+        }
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.slot.load(Ordering::Relaxed)
+    }
+
+    /// Receives every new slot. Slow receivers skip ahead rather than block the clock.
+    pub fn subscribe(&self) -> broadcast::Receiver<u64> {
+        self.ticks.subscribe()
+    }
+
+    /// Advances the clock, ignoring slots it has already seen
+    pub fn set_slot(&self, slot: u64) {
+        if self.slot.fetch_max(slot, Ordering::Relaxed) < slot {
+            // Nobody listening is fine
+            let _ = self.ticks.send(slot);
+        }
+    }
+
+    pub fn blockhash_info(&self) -> Option<BlockhashInfo> {
+        *self.blockhash.read().unwrap()
+    }
+
+    pub fn set_blockhash(&self, info: BlockhashInfo) {
+        *self.blockhash.write().unwrap() = Some(info);
+    }
+
+    /// Blockhash to sign with, None when there is none yet or it is close to expiry
+    pub fn blockhash(&self) -> Option<Hash> {
+        self.blockhash_info()
+            .filter(|info| info.remaining_blocks(self.slot()) >= self.min_remaining_blocks)
+            .map(|info| info.hash)
+    }
+
+    pub fn refresh_blockhash(&self, rpc_client: &RpcClient) -> Result<BlockhashInfo> {
+        let commitment = CommitmentConfig::confirmed();
+        let (hash, last_valid_block_height) =
+            rpc_client.get_latest_blockhash_with_commitment(commitment)?;
+        let block_height = rpc_client.get_block_height_with_commitment(commitment)?;
+        let info = BlockhashInfo {
+            hash,
+            last_valid_block_height,
+            block_height,
+            slot: self.slot(),
+        };
+        self.set_blockhash(info);
+        Ok(info)
+    }
+
+    /// Clock sysvar moved forward to the current slot, at the default slot duration. It is
+    /// refreshed along with the blockhash rather than followed, since it changes every
+    /// slot.
+    pub fn sysvar(&self) -> Option<Clock> {
+        let mut clock = self.sysvar.read().unwrap().clone()?;
+        let slot = self.slot();
+        if slot > clock.slot {
+            clock.unix_timestamp += ((slot - clock.slot) * DEFAULT_MS_PER_SLOT / 1_000) as i64;
+            clock.slot = slot;
+        }
+        Some(clock)
+    }
+
+    pub fn set_sysvar(&self, clock: Clock) {
+        *self.sysvar.write().unwrap() = Some(clock);
+    }
+
+    pub fn refresh_sysvar(&self, rpc_client: &RpcClient) -> Result<Clock> {
+        let account = rpc_client
+            .get_account_with_commitment(&sysvar::clock::id(), CommitmentConfig::processed())?
+            .value
+            .ok_or_else(|| anyhow!("Clock sysvar not found"))?;
+        let clock: Clock =
+            from_account(&account).ok_or_else(|| anyhow!("Invalid Clock sysvar data"))?;
+        self.set_sysvar(clock.clone());
+        Ok(clock)
+    }
+
+    pub fn refresh_slot(&self, rpc_client: &RpcClient) -> Result<u64> {
+        let slot = rpc_client.get_slot_with_commitment(CommitmentConfig::processed())?;
+        self.set_slot(slot);
+        Ok(slot)
+    }
+}
+
+/// Websocket URL of the node at `rpc_url`. As with the Solana CLI, an explicit port is
+/// replaced by the next one up, where nodes serve their websocket.
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        ("wss", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        ("ws", rest)
+    } else {
+        return rpc_url.to_string();
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let port = authority
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?.checked_add(1)?)));
+    match port {
+        Some((host, port)) => format!("{}://{}:{}{}", scheme, host, port, path),
+        None => format!("{}://{}", scheme, rest),
+    }
+}
+
+/// Follows slots over `slotSubscribe`, polling `getSlot` while the websocket is down
+pub async fn slot_tracker(ws_url: String, rpc_client: Arc<RpcClient>, clock: Arc<ChainClock>) {
+    subscribe_or_poll(
+        "Slot subscription",
+        || follow_slots(&ws_url, &clock),
+        || async {
+            loop {
+                let refresh = {
+                    let rpc_client = rpc_client.clone();
+                    let clock = clock.clone();
+                    tokio::task::spawn_blocking(move || clock.refresh_slot(&rpc_client))
+                };
+                if let Err(e) = refresh.await.unwrap_or_else(|e| Err(e.into())) {
+                    error!("Failed to poll slot: {:?}", e);
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        },
+    )
+    .await;
+}
+
+async fn follow_slots(ws_url: &str, clock: &ChainClock) -> Result<()> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut slots, unsubscribe) = client.slot_subscribe().await?;
+    info!("Subscribed to slots at {}", ws_url);
+    while let Some(slot_info) = slots.next().await {
+        clock.set_slot(slot_info.slot);
+    }
+    unsubscribe().await;
+    Err(anyhow!("Websocket closed"))
+}
+
+/// Fetches a new blockhash and Clock sysvar every `refresh_every_slots` slots
+pub async fn blockhash_refresher(
+    rpc_client: Arc<RpcClient>,
+    clock: Arc<ChainClock>,
+    refresh_every_slots: u64,
+) {
+    let mut ticks = clock.subscribe();
+    let mut refreshed_at = clock.blockhash_info().map_or(0, |info| info.slot);
+    loop {
+        let slot = match ticks.recv().await {
+            Ok(slot) => slot,
+            Err(broadcast::error::RecvError::Lagged(_)) => clock.slot(),
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if slot < refreshed_at + refresh_every_slots {
+            continue;
+        }
+        // The blocking client runs off the async workers
+        let refresh = {
+            let rpc_client = rpc_client.clone();
+            let clock = clock.clone();
+            tokio::task::spawn_blocking(move || clock.refresh_blockhash(&rpc_client))
+        };
+        match refresh.await.unwrap_or_else(|e| Err(e.into())) {
+            Ok(info) => {
+                refreshed_at = slot;
+                debug!(
+                    "Blockhash refreshed at slot {}: {}, valid until block height {}",
+                    slot, info.hash, info.last_valid_block_height
+                );
+            }
+            Err(e) => {
+                error!("Failed to refresh blockhash: {:?}", e);
+            }
+        }
+        let refresh = {
+            let rpc_client = rpc_client.clone();
+            let clock = clock.clone();
+            tokio::task::spawn_blocking(move || clock.refresh_sysvar(&rpc_client))
+        };
+        if let Err(e) = refresh.await.unwrap_or_else(|e| Err(e.into())) {
+            error!("Failed to refresh Clock sysvar: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refuses_blockhash_close_to_expiry() {
+        let clock = ChainClock::new(30);
+        let mut ticks = clock.subscribe();
+        assert_eq!(clock.blockhash(), None);
+
+        clock.set_slot(1_000);
+        let hash = Hash::new_unique();
+        clock.set_blockhash(BlockhashInfo {
+            hash,
+            last_valid_block_height: 950,
+            block_height: 800,
+            slot: 1_000,
+        });
+        assert_eq!(clock.blockhash(), Some(hash));
+
+        // Slots already seen don't tick
+        clock.set_slot(1_119);
+        clock.set_slot(1_100);
+        assert_eq!(ticks.try_recv().unwrap(), 1_000);
+        assert_eq!(ticks.try_recv().unwrap(), 1_119);
+        assert!(ticks.try_recv().is_err());
+        assert_eq!(clock.slot(), 1_119);
+
+        assert_eq!(clock.blockhash_info().unwrap().remaining_blocks(1_119), 31);
+        assert_eq!(clock.blockhash(), Some(hash));
+        clock.set_slot(1_121);
+        assert_eq!(clock.blockhash(), None);
+    }
+
+    #[test]
+    fn test_sysvar_follows_the_slot() {
+        let clock = ChainClock::new(30);
+        assert_eq!(clock.sysvar(), None);
+        clock.set_slot(1_000);
+        clock.set_sysvar(Clock {
+            slot: 1_000,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        });
+        assert_eq!(clock.sysvar().unwrap().unix_timestamp, 1_700_000_000);
+
+        // 25 slots of 400ms later
+        clock.set_slot(1_025);
+        let sysvar = clock.sysvar().unwrap();
+        assert_eq!((sysvar.slot, sysvar.unix_timestamp), (1_025, 1_700_000_010));
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url("https://api.mainnet-beta.solana.com"),
+            "wss://api.mainnet-beta.solana.com"
+        );
+        assert_eq!(
+            websocket_url("http://127.0.0.1:8899"),
+            "ws://127.0.0.1:8900"
+        );
+        assert_eq!(
+            websocket_url("https://rpc.example.com:8899/api-key"),
+            "wss://rpc.example.com:8900/api-key"
+        );
+        assert_eq!(
+            websocket_url("http://[::1]/path:8899"),
+            "ws://[::1]/path:8899"
+        );
+    }
+}
@@ -7,6 +7,12 @@ pub struct Config {
     pub routing: RoutingConfig,
     pub rpc: RpcConfig,
     pub spam: Option<SpamConfig>,
+    pub sending: Option<SendingConfig>,
+    pub priority_fee: Option<PriorityFeeConfig>,
+    pub simulation: Option<SimulationConfig>,
+    pub nonce: Option<NonceConfig>,
+    pub clock: Option<ClockConfig>,
+    pub state_source: Option<StateSourceConfig>,
     pub wallet: WalletConfig,
     pub flashloan: Option<FlashloanConfig>,
 }
@@ -14,6 +20,13 @@ pub struct Config {
 #[derive(Debug, Deserialize, Clone)]
 pub struct BotConfig {
     pub compute_unit_limit: u32,
+    /// Default profit, in lamports, the executor requires before it lets the swap land
+    pub minimum_profit: Option<u64>,
+    /// Default for succeeding without trading when no profitable route is found
+    pub no_failure_mode: Option<bool>,
+    /// Default for deriving the minimum profit from the quoted profit, less the priority
+    /// fee and tip
+    pub dynamic_minimum_profit: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -42,21 +55,215 @@ pub struct MintConfig {
     pub vertigo_pool_list: Option<Vec<String>>,
 
     pub lookup_table_accounts: Option<Vec<String>>,
+    /// Least time between two evaluations of this mint, in milliseconds
     pub process_delay: u64,
+    /// Also sends this many milliseconds after the last evaluation when none of the
+    /// mint's accounts changed, whether or not the quote is profitable
+    pub heartbeat_ms: Option<u64>,
+
+    /// Overrides `bot.minimum_profit` for this mint
+    pub minimum_profit: Option<u64>,
+    /// Overrides `bot.no_failure_mode` for this mint
+    pub no_failure_mode: Option<bool>,
+    /// Overrides `bot.dynamic_minimum_profit` for this mint
+    pub dynamic_minimum_profit: Option<bool>,
+    /// How to price this mint's transactions, fixed when unset
+    pub bidding: Option<BiddingConfig>,
+    /// How to price the sends made without a quote, fixed when unset
+    pub spam_bidding: Option<BiddingConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum BiddingConfig {
+    /// The fixed or estimated compute unit price and the smallest sender tip
+    Fixed,
+    /// Spends a fraction of the expected profit on the priority fee and tip
+    ProfitShare {
+        profit_fraction: f64,
+        /// Share of the spend that goes to the tip when a sender takes tips
+        tip_fraction: Option<f64>,
+        /// Bounds on the compute unit price, in micro-lamports
+        min_compute_unit_price: u64,
+        max_compute_unit_price: u64,
+        /// Bounds on the sender tip, in lamports
+        min_tip_lamports: Option<u64>,
+        max_tip_lamports: Option<u64>,
+    },
+    /// Draws the compute unit price and tip uniformly between their bounds on every send
+    Random {
+        /// Bounds on the compute unit price, in micro-lamports
+        min_compute_unit_price: u64,
+        max_compute_unit_price: u64,
+        /// Bounds on the sender tip, in lamports. The smallest sender tip when unset.
+        min_tip_lamports: Option<u64>,
+        max_tip_lamports: Option<u64>,
+    },
+}
+
+/// Profit settings for one mint, after applying its overrides to the bot defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfitConfig {
+    pub minimum_profit: u64,
+    pub no_failure_mode: bool,
+    pub dynamic_minimum_profit: bool,
+}
+
+impl ProfitConfig {
+    /// Whether a quoted profit is worth sending a transaction for
+    pub fn clears_minimum_profit(&self, expected_profit: i64) -> bool {
+        expected_profit > 0 && expected_profit as u64 >= self.minimum_profit
+    }
+
+    /// Minimum profit to encode in the next transaction, or None when it is not worth
+    /// sending.
+    ///
+    /// With a fixed minimum this is always the configured value. In dynamic mode it is the
+    /// quoted profit less what landing the transaction costs, and the transaction is
+    /// skipped unless that still clears the configured minimum. Without a quote the
+    /// executor is asked for at least the costs on top of the configured minimum.
+    pub fn resolve_minimum_profit(
+        &self,
+        expected_profit: Option<i64>,
+        priority_fee: u64,
+        tip: u64,
+    ) -> Option<u64> {
+        if !self.dynamic_minimum_profit {
+            return Some(self.minimum_profit);
+        }
+        let costs = priority_fee.saturating_add(tip);
+        match expected_profit {
+            Some(profit) => {
+                let net = i128::from(profit) - i128::from(costs);
+                if net <= 0 || net < i128::from(self.minimum_profit) {
+                    None
+                } else {
+                    Some(net.min(i128::from(u64::MAX)) as u64)
+                }
+            }
+            None => Some(self.minimum_profit.saturating_add(costs)),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RpcConfig {
     #[serde(deserialize_with = "serde_string_or_env")]
     pub url: String,
+    /// Websocket endpoint for subscriptions, derived from `url` when unset
+    pub ws_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct SpamConfig {
     pub enabled: bool,
+    /// Deprecated, RPC senders used only when `[sending]` is unset
+    #[serde(default)]
     pub sending_rpc_urls: Vec<String>,
     pub compute_unit_price: u64,
+    /// Deprecated, see `sending_rpc_urls`
     pub max_retries: Option<u64>,
+    /// Deprecated, see `sending_rpc_urls`
+    pub send_timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SendingConfig {
+    /// Senders picked per transaction, every eligible one when unset
+    pub fanout: Option<usize>,
+    /// Per-sender send timeout in milliseconds
+    pub timeout_ms: Option<u64>,
+    pub senders: Vec<SenderConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SenderConfig {
+    /// Name in logs and landing stats, defaults to the URL
+    pub name: Option<String>,
+    /// Relative chance of being picked, defaults to 1. 0 disables the sender
+    pub weight: Option<u32>,
+    /// Rate limit of the provider
+    pub max_per_second: Option<f64>,
+    #[serde(flatten)]
+    pub kind: SenderKind,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SenderKind {
+    /// `sendTransaction` to an RPC node
+    Rpc {
+        url: String,
+        max_retries: Option<usize>,
+    },
+    /// Single-transaction bundles to a Jito block engine
+    Bundle {
+        url: String,
+        /// Lamports tipped with every bundle, at least
+        tip_lamports: u64,
+        /// Tip accounts to rotate through, defaults to the mainnet ones
+        tip_accounts: Option<Vec<String>>,
+    },
+    /// HTTP service forwarding `sendTransaction` over staked connections for a tip
+    Staked {
+        url: String,
+        /// Lamports tipped with every transaction, at least
+        tip_lamports: u64,
+        tip_accounts: Vec<String>,
+    },
+    /// QUIC straight to the TPU ports of the current and upcoming leaders
+    Tpu {
+        /// Upcoming leaders to send to after the current one
+        leaders: Option<u64>,
+    },
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PriorityFeeConfig {
+    pub enabled: bool,
+    /// Percentile of recent prioritization fees to pay, 0 to 100
+    pub percentile: u8,
+    /// Bounds on the compute unit price, in micro-lamports
+    pub min_compute_unit_price: u64,
+    pub max_compute_unit_price: u64,
+    pub refresh_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimulationConfig {
+    pub enabled: bool,
+    /// Compute unit limit as a multiple of the simulated consumption
+    pub compute_unit_margin: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NonceConfig {
+    pub enabled: bool,
+    /// Durable nonce accounts with the wallet as authority, one per in-flight transaction
+    pub accounts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClockConfig {
+    /// Slots between blockhash refreshes
+    pub refresh_every_slots: Option<u64>,
+    /// Blocks a blockhash must have left before expiry to be signed with
+    pub min_remaining_blocks: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StateSourceConfig {
+    /// `accountSubscribe` on the `[rpc]` websocket
+    Websocket,
+    /// `getMultipleAccounts` on the `[rpc]` node
+    Polling { interval_ms: Option<u64> },
+    /// Account stream of a Yellowstone gRPC Geyser plugin, needs the `geyser` feature
+    Geyser {
+        url: String,
+        #[serde(default, deserialize_with = "serde_option_string_or_env")]
+        x_token: Option<String>,
+    },
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -83,6 +290,13 @@ where
     Ok(value)
 }
 
+pub fn serde_option_string_or_env<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    serde_string_or_env(deserializer).map(Some)
+}
+
 impl Config {
     pub fn load(path: &str) -> anyhow::Result<Self> {
         let mut file = File::open(path)?;
@@ -92,4 +306,100 @@ impl Config {
         let config: Config = toml::from_str(&contents)?;
         Ok(config)
     }
+
+    pub fn profit_config(&self, mint_config: &MintConfig) -> ProfitConfig {
+        ProfitConfig {
+            minimum_profit: mint_config
+                .minimum_profit
+                .or(self.bot.minimum_profit)
+                .unwrap_or(0),
+            no_failure_mode: mint_config
+                .no_failure_mode
+                .or(self.bot.no_failure_mode)
+                .unwrap_or(false),
+            dynamic_minimum_profit: mint_config
+                .dynamic_minimum_profit
+                .or(self.bot.dynamic_minimum_profit)
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profit(minimum_profit: u64, dynamic_minimum_profit: bool) -> ProfitConfig {
+        ProfitConfig {
+            minimum_profit,
+            no_failure_mode: false,
+            dynamic_minimum_profit,
+        }
+    }
+
+    #[test]
+    fn test_clears_minimum_profit() {
+        let config = profit(10_000, false);
+        assert!(config.clears_minimum_profit(10_000));
+        assert!(config.clears_minimum_profit(50_000));
+        assert!(!config.clears_minimum_profit(9_999));
+        assert!(!config.clears_minimum_profit(-50_000));
+        // A zero minimum still needs some profit
+        assert!(!profit(0, false).clears_minimum_profit(0));
+        assert!(profit(0, false).clears_minimum_profit(1));
+    }
+
+    #[test]
+    fn test_fixed_minimum_profit() {
+        let config = profit(10_000, false);
+        assert_eq!(
+            config.resolve_minimum_profit(Some(50_000), 5_000, 1_000),
+            Some(10_000)
+        );
+        assert_eq!(
+            config.resolve_minimum_profit(Some(-50_000), 5_000, 1_000),
+            Some(10_000)
+        );
+        assert_eq!(
+            config.resolve_minimum_profit(None, 5_000, 1_000),
+            Some(10_000)
+        );
+    }
+
+    #[test]
+    fn test_dynamic_minimum_profit() {
+        let config = profit(10_000, true);
+        // The executor is asked for the profit left after the fee and tip
+        assert_eq!(
+            config.resolve_minimum_profit(Some(50_000), 5_000, 1_000),
+            Some(44_000)
+        );
+        assert_eq!(
+            config.resolve_minimum_profit(Some(16_000), 5_000, 1_000),
+            Some(10_000)
+        );
+        // Net of costs below the configured minimum
+        assert_eq!(
+            config.resolve_minimum_profit(Some(15_999), 5_000, 1_000),
+            None
+        );
+        // Costs eat the whole profit even with no minimum
+        assert_eq!(
+            profit(0, true).resolve_minimum_profit(Some(6_000), 5_000, 1_000),
+            None
+        );
+        assert_eq!(
+            config.resolve_minimum_profit(Some(-50_000), 5_000, 1_000),
+            None
+        );
+        // Without a quote the costs go on top of the minimum
+        assert_eq!(
+            config.resolve_minimum_profit(None, 5_000, 1_000),
+            Some(16_000)
+        );
+        assert_eq!(
+            config.resolve_minimum_profit(None, u64::MAX, 1_000),
+            Some(u64::MAX)
+        );
+    }
 }
@@ -0,0 +1,516 @@
+use anyhow::Result;
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::TransactionStatus;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+/// `getSignatureStatuses` accepts at most this many signatures
+pub const MAX_SIGNATURES_PER_REQUEST: usize = 256;
+
+/// `getMultipleAccounts` accepts at most this many accounts
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Blockhashes expire after 150 slots, about a minute, and the cached one can be a few
+/// seconds old when the transaction is built. Transactions using a durable nonce don't
+/// expire with time.
+pub const DEFAULT_EXPIRY: Duration = Duration::from_secs(90);
+
+/// Landing rates cover outcomes from this far back
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(600);
+
+/// How often the tracker logs landing rates
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Executed successfully at confirmed commitment
+    Landed,
+    /// Included in a block but failed
+    Failed(TransactionError),
+    /// Never seen before its blockhash expired, or before its durable nonce advanced
+    Expired,
+}
+
+impl Outcome {
+    /// Custom program error code of a failed transaction, e.g. the executor's
+    /// insufficient profit error
+    pub fn error_code(&self) -> Option<u32> {
+        match self {
+            Outcome::Failed(TransactionError::InstructionError(
+                _,
+                InstructionError::Custom(code),
+            )) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome counts for one endpoint or mint
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LandingStats {
+    pub landed: u64,
+    pub failed: u64,
+    pub expired: u64,
+}
+
+impl LandingStats {
+    pub fn total(&self) -> u64 {
+        self.landed + self.failed + self.expired
+    }
+
+    /// Share of transactions that landed, failed or not, None before any resolved
+    pub fn landing_rate(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some((self.landed + self.failed) as f64 / total as f64),
+        }
+    }
+
+    /// Share of transactions that landed and succeeded, None before any resolved
+    pub fn success_rate(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(self.landed as f64 / total as f64),
+        }
+    }
+
+    fn record(&mut self, outcome: &Outcome) {
+        match outcome {
+            Outcome::Landed => self.landed += 1,
+            Outcome::Failed(_) => self.failed += 1,
+            Outcome::Expired => self.expired += 1,
+        }
+    }
+}
+
+struct Sent {
+    signature: Signature,
+    mint: Pubkey,
+    /// Version of the pool snapshot the transaction was built from
+    snapshot_version: u64,
+    /// Endpoints that accepted the transaction
+    endpoints: Vec<String>,
+    /// Nonce account and value the transaction signed over, instead of a blockhash
+    nonce: Option<(Pubkey, Hash)>,
+    sent_at: Instant,
+}
+
+struct Resolved {
+    resolved_at: Instant,
+    mint: Pubkey,
+    endpoints: Vec<String>,
+    outcome: Outcome,
+}
+
+/// Follows sent transactions until they land or expire, and keeps rolling landing
+/// statistics per endpoint and mint.
+///
+/// Transactions signed over a durable nonce are followed until the nonce advances. If
+/// that was not their doing they can no longer land.
+///
+/// A transaction sent through several endpoints counts towards each of them, since
+/// there is no telling which one delivered it.
+pub struct ConfirmationTracker {
+    expiry: Duration,
+    window: Duration,
+    pending: Mutex<Vec<Sent>>,
+    resolved: Mutex<VecDeque<Resolved>>,
+}
+
+impl Default for ConfirmationTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_EXPIRY, DEFAULT_WINDOW)
+    }
+}
+
+impl ConfirmationTracker {
+    pub fn new(expiry: Duration, window: Duration) -> Self {
+        Self {
+            expiry,
+            window,
+            pending: Mutex::new(Vec::new()),
+            resolved: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn track(
+        &self,
+        signature: Signature,
+        mint: Pubkey,
+        snapshot_version: u64,
+        endpoints: Vec<String>,
+        nonce: Option<(Pubkey, Hash)>,
+    ) {
+        self.pending.lock().unwrap().push(Sent {
+            signature,
+            mint,
+            snapshot_version,
+            endpoints,
+            nonce,
+            sent_at: Instant::now(),
+        });
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Looks up every pending signature and returns the ones that resolved, with the pool
+    /// snapshot version each was built from
+    pub fn poll(&self, rpc_client: &RpcClient) -> Result<Vec<(Signature, u64, Outcome)>> {
+        let (signatures, mut nonce_accounts) = {
+            let pending = self.pending.lock().unwrap();
+            let signatures: Vec<Signature> = pending.iter().map(|sent| sent.signature).collect();
+            let nonce_accounts: Vec<Pubkey> = pending
+                .iter()
+                .filter_map(|sent| sent.nonce.map(|(account, _)| account))
+                .collect();
+            (signatures, nonce_accounts)
+        };
+        nonce_accounts.sort();
+        nonce_accounts.dedup();
+        // Nonces are read before the statuses, so a transaction that advanced its nonce
+        // is seen to have landed rather than taken for expired
+        let mut nonce_values = BTreeMap::new();
+        for chunk in nonce_accounts.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            for (account, data) in chunk.iter().zip(rpc_client.get_multiple_accounts(chunk)?) {
+                let value = data
+                    .and_then(|data| nonce_utils::data_from_account(&data).ok())
+                    .map(|data| data.blockhash());
+                nonce_values.insert(*account, value);
+            }
+        }
+        let mut statuses = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(MAX_SIGNATURES_PER_REQUEST) {
+            statuses.extend(rpc_client.get_signature_statuses(chunk)?.value);
+        }
+        Ok(self.record_statuses(&signatures, statuses, &nonce_values, Instant::now()))
+    }
+
+    /// Resolves pending signatures from their statuses, in the order of `signatures`.
+    /// `nonce_values` holds the current value of the nonce accounts in use, None for the
+    /// ones that are gone.
+    fn record_statuses(
+        &self,
+        signatures: &[Signature],
+        statuses: Vec<Option<TransactionStatus>>,
+        nonce_values: &BTreeMap<Pubkey, Option<Hash>>,
+        now: Instant,
+    ) -> Vec<(Signature, u64, Outcome)> {
+        let outcomes: BTreeMap<Signature, Outcome> = signatures
+            .iter()
+            .zip(statuses)
+            .filter_map(|(signature, status)| {
+                let status =
+                    status.filter(|s| s.satisfies_commitment(CommitmentConfig::confirmed()))?;
+                let outcome = match status.err {
+                    None => Outcome::Landed,
+                    Some(err) => Outcome::Failed(err),
+                };
+                Some((*signature, outcome))
+            })
+            .collect();
+
+        let mut resolved = Vec::new();
+        let mut history = self.resolved.lock().unwrap();
+        self.pending.lock().unwrap().retain(|sent| {
+            let outcome = match outcomes.get(&sent.signature) {
+                Some(outcome) => outcome.clone(),
+                None if self.has_expired(sent, nonce_values, now) => Outcome::Expired,
+                None => return true,
+            };
+            resolved.push((sent.signature, sent.snapshot_version, outcome.clone()));
+            history.push_back(Resolved {
+                resolved_at: now,
+                mint: sent.mint,
+                endpoints: sent.endpoints.clone(),
+                outcome,
+            });
+            false
+        });
+        self.prune(&mut history, now);
+        resolved
+    }
+
+    fn has_expired(
+        &self,
+        sent: &Sent,
+        nonce_values: &BTreeMap<Pubkey, Option<Hash>>,
+        now: Instant,
+    ) -> bool {
+        match sent.nonce {
+            Some((account, value)) => nonce_values
+                .get(&account)
+                .is_some_and(|current| *current != Some(value)),
+            None => now.duration_since(sent.sent_at) >= self.expiry,
+        }
+    }
+
+    /// Outcomes within the window, per endpoint
+    pub fn endpoint_stats(&self) -> BTreeMap<String, LandingStats> {
+        let mut stats = BTreeMap::<String, LandingStats>::new();
+        for resolved in self.recent().iter() {
+            for endpoint in &resolved.endpoints {
+                stats
+                    .entry(endpoint.clone())
+                    .or_default()
+                    .record(&resolved.outcome);
+            }
+        }
+        stats
+    }
+
+    /// Outcomes within the window, per mint
+    pub fn mint_stats(&self) -> BTreeMap<Pubkey, LandingStats> {
+        let mut stats = BTreeMap::<Pubkey, LandingStats>::new();
+        for resolved in self.recent().iter() {
+            stats
+                .entry(resolved.mint)
+                .or_default()
+                .record(&resolved.outcome);
+        }
+        stats
+    }
+
+    fn recent(&self) -> MutexGuard<'_, VecDeque<Resolved>> {
+        let mut history = self.resolved.lock().unwrap();
+        self.prune(&mut history, Instant::now());
+        history
+    }
+
+    fn prune(&self, history: &mut VecDeque<Resolved>, now: Instant) {
+        while history
+            .front()
+            .is_some_and(|r| now.duration_since(r.resolved_at) > self.window)
+        {
+            history.pop_front();
+        }
+    }
+
+    fn log_stats(&self) {
+        for (endpoint, stats) in self.endpoint_stats() {
+            info!(
+                "Endpoint {} landing stats: {}",
+                endpoint,
+                format_stats(&stats)
+            );
+        }
+        for (mint, stats) in self.mint_stats() {
+            info!("Mint {} landing stats: {}", mint, format_stats(&stats));
+        }
+    }
+}
+
+fn format_stats(stats: &LandingStats) -> String {
+    format!(
+        "{} landed, {} failed, {} expired, landing rate {:.1}%, success rate {:.1}%",
+        stats.landed,
+        stats.failed,
+        stats.expired,
+        stats.landing_rate().unwrap_or(0.0) * 100.0,
+        stats.success_rate().unwrap_or(0.0) * 100.0
+    )
+}
+
+pub async fn confirmation_tracker(
+    rpc_client: Arc<RpcClient>,
+    tracker: Arc<ConfirmationTracker>,
+    poll_interval: Duration,
+) {
+    let mut last_report = Instant::now();
+    loop {
+        if tracker.pending_count() > 0 {
+            // The lookups use the blocking client, so they run off the async workers
+            let poll = {
+                let rpc_client = rpc_client.clone();
+                let tracker = tracker.clone();
+                tokio::task::spawn_blocking(move || tracker.poll(&rpc_client))
+            };
+            match poll.await.unwrap_or_else(|e| Err(e.into())) {
+                Ok(resolved) => {
+                    for (signature, version, outcome) in resolved {
+                        match &outcome {
+                            Outcome::Landed => info!(
+                                "Transaction landed: {} (pool snapshot {})",
+                                signature, version
+                            ),
+                            Outcome::Failed(err) => match outcome.error_code() {
+                                Some(code) => debug!(
+                                    "Transaction failed: {} (pool snapshot {}): error {}",
+                                    signature, version, code
+                                ),
+                                None => debug!(
+                                    "Transaction failed: {} (pool snapshot {}): {}",
+                                    signature, version, err
+                                ),
+                            },
+                            Outcome::Expired => debug!(
+                                "Transaction expired: {} (pool snapshot {})",
+                                signature, version
+                            ),
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to poll signature statuses: {:?}", e);
+                }
+            }
+        }
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            tracker.log_stats();
+            last_report = Instant::now();
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn status(err: Option<TransactionError>, confirmed: bool) -> Option<TransactionStatus> {
+        Some(TransactionStatus {
+            slot: 1,
+            confirmations: None,
+            status: match &err {
+                None => Ok(()),
+                Some(err) => Err(err.clone()),
+            },
+            err,
+            confirmation_status: Some(if confirmed {
+                TransactionConfirmationStatus::Confirmed
+            } else {
+                TransactionConfirmationStatus::Processed
+            }),
+        })
+    }
+
+    #[test]
+    fn test_classifies_outcomes_per_endpoint_and_mint() {
+        let tracker = ConfirmationTracker::new(Duration::from_secs(60), DEFAULT_WINDOW);
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let both = vec!["a".to_string(), "b".to_string()];
+        let signatures: Vec<Signature> = (0..5).map(|_| Signature::new_unique()).collect();
+        tracker.track(signatures[0], mint_a, 1, both.clone(), None);
+        tracker.track(signatures[1], mint_a, 2, vec!["a".to_string()], None);
+        tracker.track(signatures[2], mint_b, 1, vec!["b".to_string()], None);
+        tracker.track(signatures[3], mint_b, 2, both.clone(), None);
+        tracker.track(signatures[4], mint_b, 3, both, None);
+
+        let failed = TransactionError::InstructionError(2, InstructionError::Custom(6001));
+        let statuses = vec![
+            status(None, true),
+            status(Some(failed.clone()), true),
+            None,
+            // Only processed, so still pending
+            status(None, false),
+            None,
+        ];
+        let no_nonces = BTreeMap::new();
+        let resolved = tracker.record_statuses(&signatures, statuses, &no_nonces, Instant::now());
+        assert_eq!(
+            resolved,
+            [
+                (signatures[0], 1, Outcome::Landed),
+                (signatures[1], 2, Outcome::Failed(failed)),
+            ]
+        );
+        assert_eq!(resolved[1].2.error_code(), Some(6001));
+        assert_eq!(tracker.pending_count(), 3);
+
+        // Whatever is still unconfirmed once the expiry passes has expired
+        let later = Instant::now() + Duration::from_secs(61);
+        let resolved = tracker.record_statuses(
+            &signatures[2..],
+            vec![None, None, status(None, true)],
+            &no_nonces,
+            later,
+        );
+        assert_eq!(
+            resolved,
+            [
+                (signatures[2], 1, Outcome::Expired),
+                (signatures[3], 2, Outcome::Expired),
+                (signatures[4], 3, Outcome::Landed),
+            ]
+        );
+        assert_eq!(tracker.pending_count(), 0);
+
+        let endpoints = tracker.endpoint_stats();
+        assert_eq!(
+            endpoints["a"],
+            LandingStats {
+                landed: 2,
+                failed: 1,
+                expired: 1,
+            }
+        );
+        assert_eq!(
+            endpoints["b"],
+            LandingStats {
+                landed: 2,
+                failed: 0,
+                expired: 2,
+            }
+        );
+        assert_eq!(endpoints["b"].landing_rate(), Some(0.5));
+        let mints = tracker.mint_stats();
+        assert_eq!(mints[&mint_a].success_rate(), Some(0.5));
+        assert_eq!(mints[&mint_b].total(), 3);
+    }
+
+    #[test]
+    fn test_nonce_transactions_expire_when_the_nonce_advances() {
+        let tracker = ConfirmationTracker::new(Duration::from_secs(60), DEFAULT_WINDOW);
+        let mint = Pubkey::new_unique();
+        let (nonce_a, nonce_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (value_a, value_b) = (Hash::new_unique(), Hash::new_unique());
+        let signatures: Vec<Signature> = (0..3).map(|_| Signature::new_unique()).collect();
+        tracker.track(
+            signatures[0],
+            mint,
+            1,
+            vec!["a".to_string()],
+            Some((nonce_a, value_a)),
+        );
+        tracker.track(
+            signatures[1],
+            mint,
+            1,
+            vec!["a".to_string()],
+            Some((nonce_b, value_b)),
+        );
+        tracker.track(signatures[2], mint, 1, vec!["a".to_string()], None);
+
+        // Long past the blockhash expiry, but neither nonce has moved
+        let later = Instant::now() + Duration::from_secs(600);
+        let unchanged = BTreeMap::from([(nonce_a, Some(value_a)), (nonce_b, Some(value_b))]);
+        let resolved = tracker.record_statuses(&signatures, vec![None; 3], &unchanged, later);
+        assert_eq!(resolved, [(signatures[2], 1, Outcome::Expired)]);
+        assert_eq!(tracker.pending_count(), 2);
+
+        // One nonce advanced without its transaction, the other account was closed
+        let advanced = BTreeMap::from([(nonce_a, Some(Hash::new_unique())), (nonce_b, None)]);
+        let resolved = tracker.record_statuses(&signatures[..2], vec![None; 2], &advanced, later);
+        assert_eq!(
+            resolved,
+            [
+                (signatures[0], 1, Outcome::Expired),
+                (signatures[1], 1, Outcome::Expired),
+            ]
+        );
+        assert_eq!(tracker.pending_count(), 0);
+    }
+}
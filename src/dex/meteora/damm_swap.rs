@@ -0,0 +1,490 @@
+use crate::quote::U256;
+use anyhow::{anyhow, bail, Result};
+use meteora_damm_cpi::{CurveType, DepegType, Pool};
+use meteora_vault_cpi::Vault;
+
+const N_COINS: u8 = 2;
+const MAX_ITERATIONS: usize = 256;
+/// Precision of `Depeg::base_virtual_price`
+const DEPEG_PRECISION: u64 = 1_000_000;
+
+/// One side of a DAMM v1 pool: the vault holding the tokens and the pool's share of it
+#[derive(Debug, Clone)]
+pub struct VaultReserve {
+    pub vault: Vault,
+    /// Vault LP tokens held by the pool
+    pub pool_lp_amount: u64,
+    pub lp_supply: u64,
+    /// Balance of the vault's token account, an upper bound on what can be withdrawn
+    pub token_vault_amount: u64,
+}
+
+impl VaultReserve {
+    /// Tokens the pool's LP share is worth at `current_time`
+    pub fn pool_amount(&self, current_time: u64) -> Result<u64> {
+        self.vault
+            .get_amount_by_share(current_time, self.pool_lp_amount, self.lp_supply)
+            .ok_or_else(|| anyhow!("Vault share math overflow"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DammSwapResult {
+    pub amount_out: u64,
+    /// Trade fee left in the pool, the protocol fee excluded
+    pub fee_amount: u64,
+    pub protocol_fee_amount: u64,
+}
+
+/// Exact-in swap as the program runs it: the protocol fee is taken first, the rest is
+/// deposited into the input vault, and the curve output is withdrawn from the output
+/// vault, each step going through vault share rounding.
+///
+/// Depeg stable pools use the cached `base_virtual_price`, which the program may refresh
+/// from the stake pool before swapping.
+pub fn simulate_swap(
+    pool: &Pool,
+    vault_a: &VaultReserve,
+    vault_b: &VaultReserve,
+    amount_in: u64,
+    a_to_b: bool,
+    current_time: u64,
+) -> Result<DammSwapResult> {
+    if !pool.enabled {
+        bail!("Pool is disabled");
+    }
+
+    let token_a_amount = vault_a.pool_amount(current_time)?;
+    let token_b_amount = vault_b.pool_amount(current_time)?;
+    let (input, output) = if a_to_b {
+        (vault_a, vault_b)
+    } else {
+        (vault_b, vault_a)
+    };
+    let (swap_source_amount, swap_destination_amount) = if a_to_b {
+        (token_a_amount, token_b_amount)
+    } else {
+        (token_b_amount, token_a_amount)
+    };
+
+    let overflow = || anyhow!("DAMM swap math overflow");
+    let trade_fee = pool
+        .fees
+        .trading_fee(u128::from(amount_in))
+        .ok_or_else(overflow)?;
+    let protocol_fee = pool
+        .fees
+        .owner_trading_fee(trade_fee)
+        .ok_or_else(overflow)?;
+    let trade_fee = u64::try_from(trade_fee - protocol_fee)?;
+    let protocol_fee = u64::try_from(protocol_fee)?;
+    let in_amount_after_protocol_fee = amount_in.checked_sub(protocol_fee).ok_or_else(overflow)?;
+
+    // Deposit into the input vault and measure what the pool actually gained
+    let mut in_vault = input.vault.clone();
+    let before_in_amount = input.pool_amount(current_time)?;
+    let in_lp = in_vault
+        .get_unmint_amount(current_time, in_amount_after_protocol_fee, input.lp_supply)
+        .ok_or_else(overflow)?;
+    in_vault.total_amount = in_vault
+        .total_amount
+        .checked_add(in_amount_after_protocol_fee)
+        .ok_or_else(overflow)?;
+    let after_in_amount = in_vault
+        .get_amount_by_share(
+            current_time,
+            input.pool_lp_amount + in_lp,
+            input.lp_supply + in_lp,
+        )
+        .ok_or_else(overflow)?;
+    let actual_in_amount = after_in_amount
+        .checked_sub(before_in_amount)
+        .ok_or_else(overflow)?;
+    let actual_in_amount_after_fee = actual_in_amount
+        .checked_sub(trade_fee)
+        .ok_or_else(|| anyhow!("Input {} does not cover the trade fee", amount_in))?;
+
+    let destination_amount = match &pool.curve_type {
+        CurveType::ConstantProduct => constant_product_swap(
+            actual_in_amount_after_fee,
+            swap_source_amount,
+            swap_destination_amount,
+        )?,
+        CurveType::Stable {
+            amp,
+            token_multiplier,
+            depeg,
+            ..
+        } => {
+            let depeg_price = match depeg.depeg_type {
+                DepegType::None => None,
+                _ => Some(depeg.base_virtual_price),
+            };
+            let scale_a = Scale {
+                multiplier: token_multiplier.token_a_multiplier,
+                virtual_price: depeg_price.map(|_| DEPEG_PRECISION),
+            };
+            let scale_b = Scale {
+                multiplier: token_multiplier.token_b_multiplier,
+                virtual_price: depeg_price,
+            };
+            let (scale_in, scale_out) = if a_to_b {
+                (scale_a, scale_b)
+            } else {
+                (scale_b, scale_a)
+            };
+            stable_swap(
+                *amp,
+                actual_in_amount_after_fee,
+                swap_source_amount,
+                swap_destination_amount,
+                scale_in,
+                scale_out,
+            )?
+        }
+    };
+
+    // Withdraw from the output vault
+    let out_lp = output
+        .vault
+        .get_unmint_amount(current_time, destination_amount, output.lp_supply)
+        .ok_or_else(overflow)?;
+    let amount_out = output
+        .vault
+        .get_amount_by_share(current_time, out_lp, output.lp_supply)
+        .ok_or_else(overflow)?;
+    if amount_out >= output.token_vault_amount {
+        bail!(
+            "Output {} exceeds the vault reserve {}",
+            amount_out,
+            output.token_vault_amount
+        );
+    }
+
+    Ok(DammSwapResult {
+        amount_out,
+        fee_amount: trade_fee,
+        protocol_fee_amount: protocol_fee,
+    })
+}
+
+/// Output of the constant product invariant, with the new destination balance rounded
+/// up as the curve does
+fn constant_product_swap(
+    source_amount: u64,
+    swap_source_amount: u64,
+    swap_destination_amount: u64,
+) -> Result<u64> {
+    let invariant = u128::from(swap_source_amount) * u128::from(swap_destination_amount);
+    let new_source_amount = u128::from(swap_source_amount) + u128::from(source_amount);
+    let new_destination_amount = invariant.div_ceil(new_source_amount);
+    let amount_out = u128::from(swap_destination_amount) - new_destination_amount;
+    if amount_out == 0 {
+        bail!("Swap output is zero");
+    }
+    Ok(amount_out as u64)
+}
+
+/// Normalization of a token amount before it enters the stable invariant
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    multiplier: u64,
+    virtual_price: Option<u64>,
+}
+
+impl Scale {
+    fn upscale(&self, amount: u64) -> U256 {
+        let amount = U256::from(amount) * U256::from(self.multiplier);
+        match self.virtual_price {
+            Some(price) => amount * U256::from(price),
+            None => amount,
+        }
+    }
+
+    fn downscale(&self, amount: U256) -> U256 {
+        let amount = amount / U256::from(self.multiplier);
+        match self.virtual_price {
+            Some(price) => amount / U256::from(price),
+            None => amount,
+        }
+    }
+}
+
+fn stable_swap(
+    amp: u64,
+    source_amount: u64,
+    swap_source_amount: u64,
+    swap_destination_amount: u64,
+    scale_in: Scale,
+    scale_out: Scale,
+) -> Result<u64> {
+    for scale in [scale_in, scale_out] {
+        if scale.multiplier == 0 || scale.virtual_price == Some(0) {
+            bail!("Stable pool has a zero scaling factor");
+        }
+    }
+    let source = scale_in.upscale(swap_source_amount);
+    let destination = scale_out.upscale(swap_destination_amount);
+    let d = compute_d(amp, source, destination)?;
+    let new_destination = compute_y(amp, source + scale_in.upscale(source_amount), d)?;
+    let amount_out = scale_out.downscale(destination.saturating_sub(new_destination));
+    if amount_out.is_zero() {
+        bail!("Swap output is zero");
+    }
+    if amount_out > U256::from(u64::MAX) {
+        bail!("Swap output overflows u64");
+    }
+    Ok(amount_out.as_u64())
+}
+
+/// StableSwap invariant D for two balances, by Newton's method
+fn compute_d(amp: u64, amount_a: U256, amount_b: U256) -> Result<U256> {
+    let sum = amount_a + amount_b;
+    if sum.is_zero() {
+        return Ok(U256::zero());
+    }
+    if amount_a.is_zero() || amount_b.is_zero() {
+        bail!("Stable pool has an empty side");
+    }
+
+    let n = U256::from(N_COINS);
+    let ann = U256::from(amp) * n;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let d_product = d * d / (amount_a * n) * d / (amount_b * n);
+        let d_prev = d;
+        let numerator = d * (d_product * n + sum * ann);
+        let denominator = d * (ann - 1) + d_product * (n + 1);
+        d = numerator / denominator;
+        if abs_diff(d, d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+    bail!("Stable invariant did not converge")
+}
+
+/// Balance of the other side that keeps D unchanged when one side holds `x`
+fn compute_y(amp: u64, x: U256, d: U256) -> Result<U256> {
+    let n = U256::from(N_COINS);
+    let ann = U256::from(amp) * n;
+    let c = d * d / (x * n) * d / (ann * n);
+    let b = d / ann + x;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let denominator = (y + y + b)
+            .checked_sub(d)
+            .ok_or_else(|| anyhow!("Stable swap math underflow"))?;
+        y = (y * y + c) / denominator;
+        if abs_diff(y, y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+    bail!("Stable swap did not converge")
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meteora_damm_cpi::{Depeg, Padding, PoolFees, PoolType, TokenMultiplier};
+    use meteora_vault_cpi::{LockedProfitTracker, VaultBumps};
+    use solana_program::pubkey::Pubkey;
+
+    /// 0.25% trade fee, a fifth of it to the protocol
+    fn pool(curve_type: CurveType) -> Pool {
+        Pool {
+            lp_mint: Pubkey::default(),
+            token_a_mint: Pubkey::default(),
+            token_b_mint: Pubkey::default(),
+            a_vault: Pubkey::default(),
+            b_vault: Pubkey::default(),
+            a_vault_lp: Pubkey::default(),
+            b_vault_lp: Pubkey::default(),
+            a_vault_lp_bump: 0,
+            enabled: true,
+            admin_token_a_fee: Pubkey::default(),
+            admin_token_b_fee: Pubkey::default(),
+            admin: Pubkey::default(),
+            fees: PoolFees {
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10_000,
+                owner_trade_fee_numerator: 1,
+                owner_trade_fee_denominator: 5,
+            },
+            pool_type: PoolType::Permissionless,
+            stake: Pubkey::default(),
+            padding: Padding {
+                padding0: [0; 15],
+                padding: [0; 29],
+            },
+            curve_type,
+        }
+    }
+
+    fn stable(amp: u64, depeg: Option<(DepegType, u64)>) -> CurveType {
+        let (depeg_type, base_virtual_price) = depeg.unwrap_or((DepegType::None, 0));
+        CurveType::Stable {
+            amp,
+            token_multiplier: TokenMultiplier {
+                token_a_multiplier: 1,
+                token_b_multiplier: 1,
+                precision_factor: 9,
+            },
+            depeg: Depeg {
+                base_virtual_price,
+                base_cache_updated: 0,
+                depeg_type,
+            },
+            last_amp_updated_timestamp: 0,
+        }
+    }
+
+    fn reserve(
+        total_amount: u64,
+        locked_profit_tracker: LockedProfitTracker,
+        pool_lp_amount: u64,
+        lp_supply: u64,
+    ) -> VaultReserve {
+        VaultReserve {
+            vault: Vault {
+                enabled: 1,
+                bumps: VaultBumps {
+                    vault_bump: 0,
+                    token_vault_bump: 0,
+                },
+                total_amount,
+                token_vault: Pubkey::default(),
+                fee_vault: Pubkey::default(),
+                token_mint: Pubkey::default(),
+                lp_mint: Pubkey::default(),
+                strategies: [Pubkey::default(); 30],
+                base: Pubkey::default(),
+                admin: Pubkey::default(),
+                operator: Pubkey::default(),
+                locked_profit_tracker,
+            },
+            pool_lp_amount,
+            lp_supply,
+            token_vault_amount: total_amount,
+        }
+    }
+
+    fn unlocked() -> LockedProfitTracker {
+        LockedProfitTracker {
+            last_updated_locked_profit: 0,
+            last_report: 0,
+            locked_profit_degradation: 0,
+        }
+    }
+
+    /// A reserve the pool owns outright, one share per token
+    fn owned(total_amount: u64) -> VaultReserve {
+        reserve(total_amount, unlocked(), total_amount, total_amount)
+    }
+
+    /// 60% of a vault still releasing a 10M profit over six hours, and 40% of another
+    fn shared_reserves() -> (VaultReserve, VaultReserve) {
+        let releasing = LockedProfitTracker {
+            last_updated_locked_profit: 10_000_000,
+            last_report: 0,
+            locked_profit_degradation: 46_296_296,
+        };
+        (
+            reserve(1_000_000_003, releasing, 600_000_000, 999_999_999),
+            reserve(5_000_000_000, unlocked(), 2_000_000_000, 5_000_000_000),
+        )
+    }
+
+    #[test]
+    fn test_pool_amount_excludes_locked_profit() {
+        let (vault_a, vault_b) = shared_reserves();
+        // Half the profit is released after three hours
+        assert_eq!(vault_a.pool_amount(10_800).unwrap(), 597_000_002);
+        assert_eq!(vault_a.pool_amount(21_600).unwrap(), 600_000_002);
+        assert_eq!(vault_b.pool_amount(10_800).unwrap(), 2_000_000_000);
+    }
+
+    #[test]
+    fn test_constant_product_swap_through_vault_shares() {
+        let pool = pool(CurveType::ConstantProduct);
+        let (vault_a, vault_b) = shared_reserves();
+
+        let a_to_b = simulate_swap(&pool, &vault_a, &vault_b, 1_000_000, true, 10_800).unwrap();
+        assert_eq!(
+            a_to_b,
+            DammSwapResult {
+                amount_out: 3_336_134,
+                fee_amount: 2_000,
+                protocol_fee_amount: 500,
+            }
+        );
+        let b_to_a = simulate_swap(&pool, &vault_a, &vault_b, 1_000_000, false, 10_800).unwrap();
+        assert_eq!(b_to_a.amount_out, 297_604);
+
+        let mut disabled = pool.clone();
+        disabled.enabled = false;
+        assert!(simulate_swap(&disabled, &vault_a, &vault_b, 1_000_000, true, 10_800).is_err());
+
+        // The pool's share is worth more than the vault has on hand
+        let mut drained = vault_b.clone();
+        drained.token_vault_amount = 3_000_000;
+        assert!(simulate_swap(&pool, &vault_a, &drained, 1_000_000, true, 10_800).is_err());
+    }
+
+    #[test]
+    fn test_stable_swap() {
+        let (vault_a, vault_b) = (owned(1_000_000_000), owned(1_000_000_000));
+        let stable_pool = pool(stable(100, None));
+        let small = simulate_swap(&stable_pool, &vault_a, &vault_b, 1_000_000, true, 0).unwrap();
+        assert_eq!(small.amount_out, 997_491);
+
+        // Far less slippage than constant product on a large trade
+        let large = 500_000_000;
+        let stable_out = simulate_swap(&stable_pool, &vault_a, &vault_b, large, true, 0).unwrap();
+        let constant_product_out = simulate_swap(
+            &pool(CurveType::ConstantProduct),
+            &vault_a,
+            &vault_b,
+            large,
+            true,
+            0,
+        )
+        .unwrap();
+        assert_eq!(stable_out.amount_out, 495_523_948);
+        assert_eq!(constant_product_out.amount_out, 332_777_314);
+    }
+
+    #[test]
+    fn test_stable_invariant() {
+        let amount = U256::from(1_000_000_000u64);
+        // Balanced pools have D equal to the sum of balances
+        let d = compute_d(100, amount, amount).unwrap();
+        assert_eq!(d, amount * 2);
+        assert!(abs_diff(compute_y(100, amount, d).unwrap(), amount) <= U256::one());
+
+        let d = compute_d(100, amount, amount * 2).unwrap();
+        assert_eq!(d, U256::from(2_998_146_985u64));
+        assert!(compute_d(100, amount, U256::zero()).is_err());
+    }
+
+    #[test]
+    fn test_depeg_swap_uses_virtual_price() {
+        // Token B is a staked token worth 1.1 of token A
+        let depeg_pool = pool(stable(100, Some((DepegType::Marinade, 1_100_000))));
+        let (vault_a, vault_b) = (owned(1_100_000_000), owned(1_000_000_000));
+
+        let a_to_b = simulate_swap(&depeg_pool, &vault_a, &vault_b, 1_000_000, true, 0).unwrap();
+        assert_eq!(a_to_b.amount_out, 906_810);
+        let b_to_a = simulate_swap(&depeg_pool, &vault_a, &vault_b, 1_000_000, false, 0).unwrap();
+        assert_eq!(b_to_a.amount_out, 1_097_239);
+    }
+}
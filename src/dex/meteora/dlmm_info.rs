@@ -1,4 +1,4 @@
-use crate::dex::meteora::constants::{dlmm_program_id, BIN_ARRAY};
+use crate::dex::meteora::constants::{dlmm_program_id, BIN_ARRAY, MAX_BIN_PER_ARRAY, MAX_FEE_RATE};
 use anyhow::Result;
 use solana_program::pubkey::Pubkey;
 // use std::mem::size_of;
@@ -8,14 +8,14 @@ use core::ptr::read_unaligned;
 
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ProtocolFee {
     pub amount_x: u64,
     pub amount_y: u64,
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct RewardInfo {
     pub mint: Pubkey,
     pub vault: Pubkey,
@@ -28,7 +28,7 @@ pub struct RewardInfo {
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct StaticParameters {
     pub base_factor: u16,
     pub filter_period: u16,
@@ -39,11 +39,12 @@ pub struct StaticParameters {
     pub min_bin_id: i32,
     pub max_bin_id: i32,
     pub protocol_share: u16,
-    pub _padding: [u8; 6],
+    pub base_fee_power_factor: u8,
+    pub _padding: [u8; 5],
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct VariableParameters {
     pub volatility_accumulator: u32,
     pub volatility_reference: u32,
@@ -54,7 +55,7 @@ pub struct VariableParameters {
 }
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct LbPair {
     pub parameters: StaticParameters,
     pub v_parameters: VariableParameters,
@@ -149,6 +150,32 @@ impl DlmmInfo {
         (token_vault, sol_vault)
     }
 
+    /// Base fee rate scaled by `FEE_PRECISION` (10^9)
+    pub fn base_fee_rate(&self) -> u128 {
+        let parameters = &self.lb_pair.parameters;
+        u128::from(parameters.base_factor)
+            * u128::from(self.lb_pair.bin_step)
+            * 10
+            * 10u128.pow(u32::from(parameters.base_fee_power_factor))
+    }
+
+    /// Variable fee rate for a volatility accumulator value, scaled by `FEE_PRECISION`
+    pub fn variable_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        let parameters = &self.lb_pair.parameters;
+        if parameters.variable_fee_control == 0 {
+            return 0;
+        }
+        let square_vfa_bin =
+            (u128::from(volatility_accumulator) * u128::from(self.lb_pair.bin_step)).pow(2);
+        let v_fee = u128::from(parameters.variable_fee_control) * square_vfa_bin;
+        v_fee.div_ceil(100_000_000_000)
+    }
+
+    /// Base plus variable fee, capped at `MAX_FEE_RATE`
+    pub fn total_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        (self.base_fee_rate() + self.variable_fee_rate(volatility_accumulator)).min(MAX_FEE_RATE)
+    }
+
     pub fn calculate_bin_arrays(&self, pair_pubkey: &Pubkey) -> Result<Vec<Pubkey>> {
         let bin_array_index = self.bin_id_to_bin_array_index(self.active_id)?;
 
@@ -164,10 +191,22 @@ impl DlmmInfo {
         Ok(bin_arrays)
     }
 
-    fn bin_id_to_bin_array_index(&self, bin_id: i32) -> Result<i32> {
-        // Use a constant bin per array size of 100 as used in the meteora protocol
-        let bin_per_array = 100;
-        Ok(bin_id.div_euclid(bin_per_array))
+    /// Bin array addresses a swap walks from the active bin, in order
+    pub fn swap_bin_arrays(
+        &self,
+        pair_pubkey: &Pubkey,
+        swap_for_y: bool,
+        count: usize,
+    ) -> Result<Vec<Pubkey>> {
+        let start = self.bin_id_to_bin_array_index(self.active_id)?;
+        let step = if swap_for_y { -1 } else { 1 };
+        (0..count as i32)
+            .map(|i| self.derive_bin_array_pda(pair_pubkey, (start + i * step) as i64))
+            .collect()
+    }
+
+    pub fn bin_id_to_bin_array_index(&self, bin_id: i32) -> Result<i32> {
+        Ok(bin_id.div_euclid(MAX_BIN_PER_ARRAY as i32))
     }
 
     fn derive_bin_array_pda(&self, lb_pair: &Pubkey, index: i64) -> Result<Pubkey> {
@@ -190,3 +229,62 @@ impl LbPair {
         Ok(lb_pair)
     }
 }
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub price: u128,
+    pub liquidity_supply: u128,
+    pub reward_per_token_stored: [u128; 2],
+    pub fee_amount_x_per_token_stored: u128,
+    pub fee_amount_y_per_token_stored: u128,
+    pub amount_x_in: u128,
+    pub amount_y_in: u128,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BinArray {
+    pub index: i64,
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub lb_pair: Pubkey,
+    pub bins: [Bin; MAX_BIN_PER_ARRAY],
+}
+
+impl BinArray {
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 + size_of::<BinArray>() {
+            return Err(anyhow::anyhow!("Invalid data length for BinArray"));
+        }
+
+        let bin_array = unsafe { read_unaligned(data[8..].as_ptr() as *const BinArray) };
+
+        Ok(bin_array)
+    }
+
+    pub fn lower_bin_id(&self) -> i32 {
+        (self.index * MAX_BIN_PER_ARRAY as i64) as i32
+    }
+
+    pub fn upper_bin_id(&self) -> i32 {
+        self.lower_bin_id() + MAX_BIN_PER_ARRAY as i32 - 1
+    }
+
+    pub fn is_bin_id_within_range(&self, bin_id: i32) -> bool {
+        bin_id >= self.lower_bin_id() && bin_id <= self.upper_bin_id()
+    }
+
+    pub fn get_bin(&self, bin_id: i32) -> Result<&Bin> {
+        if !self.is_bin_id_within_range(bin_id) {
+            return Err(anyhow::anyhow!(
+                "Bin {} is outside of bin array {}",
+                bin_id,
+                self.index
+            ));
+        }
+        Ok(&self.bins[(bin_id - self.lower_bin_id()) as usize])
+    }
+}
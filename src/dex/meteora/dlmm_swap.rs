@@ -0,0 +1,355 @@
+use crate::dex::meteora::constants::{BASIS_POINT_MAX, FEE_PRECISION};
+use crate::dex::meteora::dlmm_info::{Bin, BinArray, DlmmInfo};
+use crate::quote::U256;
+use anyhow::{anyhow, bail, Result};
+use solana_program::pubkey::Pubkey;
+
+const SCALE_OFFSET: u32 = 64;
+const ONE: u128 = 1u128 << SCALE_OFFSET;
+// 19 bits of exponent are enough to cover every bin id
+const MAX_EXPONENTIAL: u32 = 0x80000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DlmmSwapResult {
+    /// Input consumed, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub end_active_id: i32,
+    /// Bin arrays the swap read, in order
+    pub bin_arrays: Vec<Pubkey>,
+}
+
+/// Q64.64 price of a bin: (1 + bin_step / 10000) ^ bin_id
+pub fn get_price_from_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    let bps = (u128::from(bin_step) << SCALE_OFFSET) / BASIS_POINT_MAX as u128;
+    pow(ONE + bps, bin_id).ok_or_else(|| anyhow!("Price overflow for bin {}", bin_id))
+}
+
+// Same square-and-multiply as the program, inverting the base first so that
+// squaring never needs more than 128 bits
+fn pow(base: u128, exp: i32) -> Option<u128> {
+    if exp == 0 {
+        return Some(ONE);
+    }
+
+    let mut invert = exp.is_negative();
+    let exp = exp.unsigned_abs();
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+
+    let mut squared_base = base;
+    let mut result = ONE;
+    if squared_base >= result {
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        invert = !invert;
+    }
+
+    for bit in 0..19 {
+        if bit > 0 {
+            squared_base = squared_base.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+        if exp & (1 << bit) > 0 {
+            result = result.checked_mul(squared_base)? >> SCALE_OFFSET;
+        }
+    }
+
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+    Some(result)
+}
+
+/// Simulate an exact-in swap starting at the active bin. `bin_arrays` must be ordered in
+/// the swap direction, the first one holding the active bin. The variable fee is
+/// rebuilt from the pair's volatility state as of `current_timestamp`.
+///
+/// Fails when the arrays run out before `amount_in` is fully consumed.
+pub fn simulate_swap(
+    dlmm_info: &DlmmInfo,
+    bin_arrays: &[(Pubkey, BinArray)],
+    amount_in: u64,
+    swap_for_y: bool,
+    current_timestamp: i64,
+) -> Result<DlmmSwapResult> {
+    let lb_pair = &dlmm_info.lb_pair;
+    let parameters = &lb_pair.parameters;
+
+    // Mirror the program's reference update at the start of a swap
+    let mut v_parameters = lb_pair.v_parameters;
+    let elapsed = current_timestamp - v_parameters.last_update_timestamp;
+    if elapsed >= i64::from(parameters.filter_period) {
+        v_parameters.index_reference = dlmm_info.active_id;
+        v_parameters.volatility_reference = if elapsed < i64::from(parameters.decay_period) {
+            v_parameters.volatility_accumulator * u32::from(parameters.reduction_factor)
+                / BASIS_POINT_MAX as u32
+        } else {
+            0
+        };
+    }
+
+    let mut active_id = dlmm_info.active_id;
+    let mut amount_left = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut used_arrays = Vec::new();
+
+    for (address, bin_array) in bin_arrays {
+        if !bin_array.is_bin_id_within_range(active_id) {
+            bail!(
+                "Bin array {} does not hold bin {}, arrays are out of order",
+                address,
+                active_id
+            );
+        }
+        used_arrays.push(*address);
+
+        while amount_left > 0 && bin_array.is_bin_id_within_range(active_id) {
+            let delta_id = u64::from(v_parameters.index_reference.abs_diff(active_id));
+            let volatility_accumulator =
+                (u64::from(v_parameters.volatility_reference) + delta_id * BASIS_POINT_MAX as u64)
+                    .min(u64::from(parameters.max_volatility_accumulator)) as u32;
+            let fee_rate = dlmm_info.total_fee_rate(volatility_accumulator);
+
+            let bin = bin_array.get_bin(active_id)?;
+            let (consumed, out, fee) = swap_in_bin(
+                bin,
+                active_id,
+                lb_pair.bin_step,
+                amount_left,
+                swap_for_y,
+                fee_rate,
+            )?;
+            amount_left -= consumed;
+            amount_out += out;
+            fee_amount += fee;
+
+            if amount_left > 0 {
+                active_id = if swap_for_y {
+                    active_id - 1
+                } else {
+                    active_id + 1
+                };
+                if active_id < parameters.min_bin_id || active_id > parameters.max_bin_id {
+                    bail!("Swap moved past the pair's bin range");
+                }
+            }
+        }
+
+        if amount_left == 0 {
+            break;
+        }
+    }
+
+    if amount_left > 0 {
+        bail!(
+            "Not enough liquidity in the provided bin arrays, {} of {} unfilled",
+            amount_left,
+            amount_in
+        );
+    }
+
+    Ok(DlmmSwapResult {
+        amount_in,
+        amount_out,
+        fee_amount,
+        end_active_id: active_id,
+        bin_arrays: used_arrays,
+    })
+}
+
+/// Swap as much of `amount_in` as one bin can take. Returns (input consumed including
+/// fee, output, fee).
+fn swap_in_bin(
+    bin: &Bin,
+    bin_id: i32,
+    bin_step: u16,
+    amount_in: u64,
+    swap_for_y: bool,
+    fee_rate: u128,
+) -> Result<(u64, u64, u64)> {
+    let max_amount_out = if swap_for_y {
+        bin.amount_y
+    } else {
+        bin.amount_x
+    };
+    if max_amount_out == 0 {
+        return Ok((0, 0, 0));
+    }
+
+    let price = U256::from(get_price_from_id(bin_id, bin_step)?);
+    let max_amount_out_u256 = U256::from(max_amount_out);
+    // Input needed to drain the bin, rounded up
+    let max_amount_in = if swap_for_y {
+        ((max_amount_out_u256 << SCALE_OFFSET) + price - 1) / price
+    } else {
+        (max_amount_out_u256 * price + U256::from(ONE - 1)) >> SCALE_OFFSET
+    };
+    if max_amount_in > U256::from(u64::MAX) {
+        bail!("Bin {} input overflows u64", bin_id);
+    }
+    let max_amount_in = max_amount_in.as_u64();
+    // Fee on top of the amount, as the program charges when a bin is fully crossed
+    let max_fee = (u128::from(max_amount_in) * fee_rate).div_ceil(FEE_PRECISION - fee_rate) as u64;
+    let max_amount_in_with_fee = max_amount_in
+        .checked_add(max_fee)
+        .ok_or_else(|| anyhow!("Bin {} input overflows u64", bin_id))?;
+
+    if amount_in >= max_amount_in_with_fee {
+        return Ok((max_amount_in_with_fee, max_amount_out, max_fee));
+    }
+
+    let fee = (u128::from(amount_in) * fee_rate).div_ceil(FEE_PRECISION) as u64;
+    let amount_in_after_fee = U256::from(amount_in - fee);
+    let amount_out = if swap_for_y {
+        (amount_in_after_fee * price) >> SCALE_OFFSET
+    } else {
+        (amount_in_after_fee << SCALE_OFFSET) / price
+    };
+    let amount_out = amount_out.min(max_amount_out_u256).as_u64();
+
+    Ok((amount_in, amount_out, fee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::meteora::constants::{MAX_BIN_PER_ARRAY, MAX_FEE_RATE};
+    use crate::dex::meteora::dlmm_info::{LbPair, StaticParameters, VariableParameters};
+
+    const BIN_STEP: u16 = 10;
+    const NOW: i64 = 1_000_000;
+
+    /// Pair at bin 0 with a 0.1% base fee and the given volatility state
+    fn dlmm_info(variable_fee_control: u32, v_parameters: VariableParameters) -> DlmmInfo {
+        let lb_pair = LbPair {
+            parameters: StaticParameters {
+                base_factor: 10_000,
+                filter_period: 30,
+                decay_period: 600,
+                reduction_factor: 5_000,
+                variable_fee_control,
+                max_volatility_accumulator: 350_000,
+                min_bin_id: -443_636,
+                max_bin_id: 443_636,
+                ..Default::default()
+            },
+            v_parameters,
+            bin_step: BIN_STEP,
+            ..Default::default()
+        };
+        DlmmInfo {
+            token_x_mint: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            token_x_vault: Pubkey::new_unique(),
+            token_y_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            active_id: 0,
+            lb_pair,
+        }
+    }
+
+    /// Bin 0 holds 1 of token Y, bin -1 in the array below holds 10
+    fn bin_arrays() -> Vec<(Pubkey, BinArray)> {
+        let bin_array = |index: i64, bin: usize, amount_y: u64| {
+            let mut bins = [Bin::default(); MAX_BIN_PER_ARRAY];
+            bins[bin].amount_y = amount_y;
+            let bin_array = BinArray {
+                index,
+                version: 1,
+                _padding: [0; 7],
+                lb_pair: Pubkey::default(),
+                bins,
+            };
+            (Pubkey::new_unique(), bin_array)
+        };
+        vec![
+            bin_array(0, 0, 1_000_000),
+            bin_array(-1, MAX_BIN_PER_ARRAY - 1, 10_000_000),
+        ]
+    }
+
+    #[test]
+    fn test_price_from_id() {
+        assert_eq!(get_price_from_id(0, BIN_STEP).unwrap(), ONE);
+        // 1.001 ^ 1 and 1.001 ^ -1 in Q64.64
+        assert_eq!(
+            get_price_from_id(1, BIN_STEP).unwrap(),
+            18_465_190_817_783_261_167
+        );
+        assert_eq!(
+            get_price_from_id(-1, BIN_STEP).unwrap(),
+            18_428_315_757_951_600_016
+        );
+        assert!(get_price_from_id(MAX_EXPONENTIAL as i32, BIN_STEP).is_err());
+    }
+
+    #[test]
+    fn test_variable_fee_rate() {
+        let info = dlmm_info(4_000_000, VariableParameters::default());
+        assert_eq!(info.total_fee_rate(0), 1_000_000);
+        // 0.004 rounds up to 1
+        assert_eq!(info.total_fee_rate(1), 1_000_001);
+        assert_eq!(info.total_fee_rate(10_000), 1_400_000);
+        assert_eq!(info.total_fee_rate(350_000), MAX_FEE_RATE);
+
+        let no_variable_fee = dlmm_info(0, VariableParameters::default());
+        assert_eq!(no_variable_fee.total_fee_rate(350_000), 1_000_000);
+    }
+
+    #[test]
+    fn test_swap_crosses_bins() {
+        let info = dlmm_info(0, VariableParameters::default());
+        let arrays = bin_arrays();
+        let result = simulate_swap(&info, &arrays, 1_500_000, true, NOW).unwrap();
+
+        // Bin 0 is drained at price 1, its fee charged on top, and the rest fills at
+        // 1 / 1.001 in bin -1
+        assert_eq!(result.amount_out, 1_000_000 + 498_000);
+        assert_eq!(result.fee_amount, 1_002 + 499);
+        assert_eq!(result.end_active_id, -1);
+        assert_eq!(result.bin_arrays, vec![arrays[0].0, arrays[1].0]);
+
+        let small = simulate_swap(&info, &arrays, 1_000, true, NOW).unwrap();
+        assert_eq!((small.amount_out, small.fee_amount), (999, 1));
+        assert_eq!(small.bin_arrays, vec![arrays[0].0]);
+
+        assert!(simulate_swap(&info, &arrays[..1], 1_500_000, true, NOW).is_err());
+        // The first array must hold the active bin
+        assert!(simulate_swap(&info, &arrays[1..], 1_000, true, NOW).is_err());
+    }
+
+    #[test]
+    fn test_swap_applies_variable_fee() {
+        // Swapped just now, so the references stay and each bin crossed adds volatility
+        let recent = VariableParameters {
+            volatility_accumulator: 10_000,
+            last_update_timestamp: NOW,
+            ..Default::default()
+        };
+        let result = simulate_swap(
+            &dlmm_info(4_000_000, recent),
+            &bin_arrays(),
+            1_500_000,
+            true,
+            NOW,
+        )
+        .unwrap();
+        assert_eq!((result.amount_out, result.fee_amount), (1_497_801, 1_701));
+
+        // Past the filter period the reference decays to half the last accumulator
+        let result = simulate_swap(
+            &dlmm_info(4_000_000, recent),
+            &bin_arrays(),
+            1_500_000,
+            true,
+            NOW + 60,
+        )
+        .unwrap();
+        assert_eq!((result.amount_out, result.fee_amount), (1_497_452, 2_050));
+    }
+}
@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 pub const PUMP_PROGRAM_ID: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
 pub const PUMP_FEE_WALLET: &str = "JCRGumoE9Qi5BBgULTgdgTLjSgkCMSbF62ZZfGs84JeU";
+pub const PUMP_GLOBAL_CONFIG: &str = "ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw";
 
 pub fn pump_program_id() -> Pubkey {
     Pubkey::from_str(PUMP_PROGRAM_ID).unwrap()
@@ -11,3 +12,7 @@ pub fn pump_program_id() -> Pubkey {
 pub fn pump_fee_wallet() -> Pubkey {
     Pubkey::from_str(PUMP_FEE_WALLET).unwrap()
 }
+
+pub fn pump_global_config() -> Pubkey {
+    Pubkey::from_str(PUMP_GLOBAL_CONFIG).unwrap()
+}
@@ -1,6 +1,10 @@
 use solana_program::pubkey::Pubkey;
 use anyhow::Result;
 
+const SWAP_FEE_NUMERATOR_OFFSET: usize = 176; // fees.swap_fee_numerator
+const SWAP_FEE_DENOMINATOR_OFFSET: usize = 184; // fees.swap_fee_denominator
+const NEED_TAKE_PNL_COIN_OFFSET: usize = 192; // state_data.need_take_pnl_coin
+const NEED_TAKE_PNL_PC_OFFSET: usize = 200; // state_data.need_take_pnl_pc
 const COIN_VAULT_OFFSET: usize = 336; // coinVault/tokenVaultA
 const PC_VAULT_OFFSET: usize = 368; // pcVault/tokenVaultB
 const COIN_MINT_OFFSET: usize = 400; // coinMint/tokenMintA
@@ -12,6 +16,10 @@ pub struct RaydiumAmmInfo {
     pub pc_mint: Pubkey,
     pub coin_vault: Pubkey,
     pub pc_vault: Pubkey,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
 }
 
 impl RaydiumAmmInfo {
@@ -24,12 +32,24 @@ impl RaydiumAmmInfo {
         let pc_vault = Pubkey::try_from(&data[PC_VAULT_OFFSET..PC_VAULT_OFFSET + 32])?;
         let coin_mint = Pubkey::try_from(&data[COIN_MINT_OFFSET..COIN_MINT_OFFSET + 32])?;
         let pc_mint = Pubkey::try_from(&data[PC_MINT_OFFSET..PC_MINT_OFFSET + 32])?;
+        let swap_fee_numerator = read_u64(data, SWAP_FEE_NUMERATOR_OFFSET);
+        let swap_fee_denominator = read_u64(data, SWAP_FEE_DENOMINATOR_OFFSET);
+        let need_take_pnl_coin = read_u64(data, NEED_TAKE_PNL_COIN_OFFSET);
+        let need_take_pnl_pc = read_u64(data, NEED_TAKE_PNL_PC_OFFSET);
         
         Ok(Self {
             coin_mint,
             pc_mint,
             coin_vault,
             pc_vault,
+            swap_fee_numerator,
+            swap_fee_denominator,
+            need_take_pnl_coin,
+            need_take_pnl_pc,
         })
     }
 }
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
@@ -5,19 +5,14 @@ pub fn raydium_program_id() -> Pubkey {
     Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap()
 }
 
-pub fn raydium_authority() -> Pubkey {
-    Pubkey::from_str("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1").unwrap()
-}
-
 
 
 pub fn raydium_cp_program_id() -> Pubkey {
     Pubkey::from_str("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C").unwrap()
 }
 
-pub fn raydium_cp_authority() -> Pubkey {
-    Pubkey::from_str("GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL").unwrap()
-}
+// Fee rates of CPMM and CLMM configs are in hundredths of a bip
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
 
 pub fn raydium_clmm_program_id() -> Pubkey {
     Pubkey::from_str("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK").unwrap()
@@ -11,6 +11,14 @@ const TOKEN_1_MINT_OFFSET: usize = 200; // token_1_mint
 const TOKEN_0_PROGRAM_OFFSET: usize = 232; // token_0_program
 const TOKEN_1_PROGRAM_OFFSET: usize = 264; // token_1_program
 const OBSERVATION_KEY_OFFSET: usize = 296; // observation_key
+const PROTOCOL_FEES_TOKEN_0_OFFSET: usize = 341; // protocol_fees_token_0
+const PROTOCOL_FEES_TOKEN_1_OFFSET: usize = 349; // protocol_fees_token_1
+const FUND_FEES_TOKEN_0_OFFSET: usize = 357; // fund_fees_token_0
+const FUND_FEES_TOKEN_1_OFFSET: usize = 365; // fund_fees_token_1
+const CREATOR_FEES_TOKEN_0_OFFSET: usize = 397; // creator_fees_token_0
+const CREATOR_FEES_TOKEN_1_OFFSET: usize = 405; // creator_fees_token_1
+
+const TRADE_FEE_RATE_OFFSET: usize = 12; // AmmConfig.trade_fee_rate
 
 #[derive(Debug)]
 pub struct RaydiumCpAmmInfo {
@@ -20,6 +28,9 @@ pub struct RaydiumCpAmmInfo {
     pub token_1_vault: Pubkey,
     pub amm_config: Pubkey,
     pub observation_key: Pubkey,
+    // Fees sitting in the vaults that do not belong to the swap reserves
+    pub pending_fees_token_0: u64,
+    pub pending_fees_token_1: u64,
 }
 
 impl RaydiumCpAmmInfo {
@@ -34,6 +45,13 @@ impl RaydiumCpAmmInfo {
         let token_1_mint = Pubkey::try_from(&data[TOKEN_1_MINT_OFFSET..TOKEN_1_MINT_OFFSET + 32])?;
         let amm_config = Pubkey::try_from(&data[AMM_CONFIG_OFFSET..AMM_CONFIG_OFFSET + 32])?;
         let observation_key = Pubkey::try_from(&data[OBSERVATION_KEY_OFFSET..OBSERVATION_KEY_OFFSET + 32])?;
+
+        let pending_fees_token_0 = read_u64(data, PROTOCOL_FEES_TOKEN_0_OFFSET)
+            + read_u64(data, FUND_FEES_TOKEN_0_OFFSET)
+            + read_u64(data, CREATOR_FEES_TOKEN_0_OFFSET);
+        let pending_fees_token_1 = read_u64(data, PROTOCOL_FEES_TOKEN_1_OFFSET)
+            + read_u64(data, FUND_FEES_TOKEN_1_OFFSET)
+            + read_u64(data, CREATOR_FEES_TOKEN_1_OFFSET);
         
         Ok(Self {
             token_0_mint,
@@ -42,6 +60,33 @@ impl RaydiumCpAmmInfo {
             token_1_vault,
             amm_config,
             observation_key,
+            pending_fees_token_0,
+            pending_fees_token_1,
         })
     }
 }
+
+#[derive(Debug)]
+pub struct RaydiumCpAmmConfig {
+    /// Trade fee, denominated in hundredths of a bip (10^-6)
+    pub trade_fee_rate: u64,
+}
+
+impl RaydiumCpAmmConfig {
+    pub fn load_checked(data: &[u8]) -> Result<Self> {
+        if data.len() < TRADE_FEE_RATE_OFFSET + 8 {
+            return Err(anyhow::anyhow!("Invalid data length for RaydiumCpAmmConfig"));
+        }
+
+        Ok(Self {
+            trade_fee_rate: read_u64(data, TRADE_FEE_RATE_OFFSET),
+        })
+    }
+}
+
+// Reads past the end of older, shorter accounts yield zero
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .unwrap_or_default()
+}
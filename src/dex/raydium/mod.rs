@@ -5,5 +5,5 @@ pub mod clmm_info;
 
 pub use amm_info::RaydiumAmmInfo;
 pub use constants::*;
-pub use cp_amm_info::RaydiumCpAmmInfo;
-pub use clmm_info::{PoolState, get_tick_array_pubkeys};
+pub use cp_amm_info::{RaydiumCpAmmConfig, RaydiumCpAmmInfo};
+pub use clmm_info::{AmmConfig, PoolState, TickArrayState, get_tick_array_pubkeys};
@@ -1,6 +1,9 @@
 use solana_program::pubkey::Pubkey;
 use std::str::FromStr;
 
+// SolFi does not publish its fee schedule, assume a conservative rate
+pub const SOLFI_ESTIMATED_FEE_BPS: u64 = 30;
+
 pub fn solfi_program_id() -> Pubkey {
     Pubkey::from_str("SoLFiHG9TfgtdUXUjWAxi3LtvYuFyDLVhBWxdMZxyCe").unwrap()
 }
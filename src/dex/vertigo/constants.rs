@@ -1,6 +1,9 @@
 use solana_program::pubkey::Pubkey;
 use std::str::FromStr;
 
+// Vertigo fees are set per pool and not decoded yet, assume a conservative rate
+pub const VERTIGO_ESTIMATED_FEE_BPS: u64 = 30;
+
 pub fn vertigo_program_id() -> Pubkey {
     Pubkey::from_str("vrTGoBuy5rYSxAfV3jaRJWHH6nN9WK4NRExGxsk1bCJ").unwrap()
 }
@@ -1,10 +1,11 @@
 pub mod constants;
 pub mod state;
 
-use crate::dex::whirlpool::state::{Whirlpool, TICK_ARRAY_SIZE};
+use crate::dex::whirlpool::state::{TickArray, Whirlpool, TICK_ARRAY_SIZE};
 use crate::dex::whirlpool::constants::{MAX_TICK_INDEX, MIN_TICK_INDEX};
 use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
+use whirlpool_cpi::{swap_exact_in, SwapResult, WhirlpoolSwapState};
 
 pub type TickArrayStartIndexes = (i32, Option<i32>, Option<i32>);
 
@@ -111,3 +112,48 @@ pub fn update_tick_array_accounts_for_onchain(
     ];
     tick_array_pks
 }
+
+/// Tick array addresses a swap in the given direction walks, in order
+pub fn swap_tick_array_addresses(
+    whirlpool: &Whirlpool,
+    whirlpool_pk: &Pubkey,
+    whirlpool_program_id: &Pubkey,
+    a_to_b: bool,
+) -> Vec<Pubkey> {
+    let (start, second, third) = derive_tick_array_start_indexes(
+        whirlpool.tick_current_index,
+        whirlpool.tick_spacing,
+        a_to_b,
+    );
+    std::iter::once(start)
+        .chain(second)
+        .chain(third)
+        .map(|start_index| get_tick_array_address(whirlpool_pk, start_index, whirlpool_program_id))
+        .collect()
+}
+
+/// Simulate an exact-in swap over `tick_arrays`, ordered as returned by
+/// `swap_tick_array_addresses`. Returns the result along with the addresses of the
+/// arrays the swap actually needed.
+pub fn simulate_swap(
+    whirlpool: &Whirlpool,
+    tick_arrays: &[(Pubkey, TickArray)],
+    amount_in: u64,
+    a_to_b: bool,
+) -> anyhow::Result<(SwapResult, Vec<Pubkey>)> {
+    let state = WhirlpoolSwapState {
+        liquidity: whirlpool.liquidity,
+        sqrt_price: whirlpool.sqrt_price,
+        tick_current_index: whirlpool.tick_current_index,
+        tick_spacing: whirlpool.tick_spacing,
+        fee_rate: whirlpool.fee_rate,
+    };
+    let arrays: Vec<TickArray> = tick_arrays.iter().map(|(_, array)| array.clone()).collect();
+    let result = swap_exact_in(&state, &arrays, amount_in, a_to_b)?;
+    let touched = tick_arrays
+        .iter()
+        .take(result.tick_arrays_touched)
+        .map(|(address, _)| *address)
+        .collect();
+    Ok((result, touched))
+}
@@ -1,5 +1,7 @@
 use solana_program::pubkey::Pubkey;
 
+pub use whirlpool_cpi::{TickArray, TICK_ARRAY_SIZE};
+
 pub const NUM_REWARDS: usize = 3;
 
 #[derive(Clone, Copy, Debug)]
@@ -50,31 +52,6 @@ pub struct WhirlpoolRewardInfo {
     pub growth_global_x64: u128,
 }
 
-#[derive(Clone, Debug)]
-pub struct TickArray {
-    pub start_tick_index: i32,
-    pub ticks: [Tick; TICK_ARRAY_SIZE],
-    pub whirlpool: Pubkey,
-}
-
-#[derive(Copy, Clone, Default, Debug)]
-pub struct Tick {
-    pub initialized: bool,
-    pub liquidity_net: i128,
-    pub liquidity_gross: u128,
-    pub fee_growth_outside_a: u128,
-    pub fee_growth_outside_b: u128,
-    pub reward_growths_outside: [u128; NUM_REWARDS],
-}
-
-impl Tick {
-    pub fn check_is_valid_start_tick(tick_index: i32, tick_spacing: u16) -> bool {
-        tick_index % (tick_spacing as i32 * TICK_ARRAY_SIZE as i32) == 0
-    }
-}
-
-pub const TICK_ARRAY_SIZE: usize = 88;
-
 use std::io::{Error, ErrorKind, Result};
 
 impl Whirlpool {
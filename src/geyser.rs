@@ -0,0 +1,167 @@
+use crate::state_cache::{AccountSource, StateCache};
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use futures::{SinkExt, StreamExt};
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tracing::info;
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestPing, SubscribeUpdateAccount,
+};
+
+/// Account updates streamed by a Yellowstone gRPC Geyser plugin, filtered to the watched
+/// accounts and the programs that own them
+pub struct GeyserSource {
+    url: String,
+    x_token: Option<String>,
+    /// Loads the current state of newly subscribed accounts
+    rpc_client: Arc<RpcClient>,
+}
+
+impl GeyserSource {
+    pub fn new(url: String, x_token: Option<String>, rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            url,
+            x_token,
+            rpc_client,
+        }
+    }
+
+    /// Subscription to `keys`, replacing any earlier one on the same stream
+    fn request(cache: &StateCache, keys: &BTreeSet<Pubkey>) -> SubscribeRequest {
+        let owners: BTreeSet<Pubkey> = keys
+            .iter()
+            .filter_map(|key| cache.get(key))
+            .map(|cached| cached.account.owner)
+            .collect();
+        let filter = SubscribeRequestFilterAccounts {
+            account: keys.iter().map(ToString::to_string).collect(),
+            owner: owners.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+        SubscribeRequest {
+            accounts: HashMap::from([("pool_state".to_string(), filter)]),
+            commitment: Some(CommitmentLevel::Processed as i32),
+            ..Default::default()
+        }
+    }
+
+    async fn follow_updates(&self, cache: &StateCache) -> Result<()> {
+        let mut client = GeyserGrpcClient::connect(self.url.clone(), self.x_token.clone(), None)?;
+        let (mut requests, mut updates) = client.subscribe().await?;
+        let mut subscribed = BTreeSet::new();
+        loop {
+            let added: Vec<Pubkey> = cache
+                .watched()
+                .into_iter()
+                .filter(|key| !subscribed.contains(key))
+                .collect();
+            if !added.is_empty() {
+                // Updates only start with the next change, and the owner filter needs
+                // the owners of the new accounts
+                cache.poll(&self.rpc_client, &added).await?;
+                subscribed.extend(added);
+                requests.send(Self::request(cache, &subscribed)).await?;
+                info!(
+                    "Subscribed to {} accounts at {}",
+                    subscribed.len(),
+                    self.url
+                );
+            }
+
+            tokio::select! {
+                update = updates.next() => {
+                    let update = update.ok_or_else(|| anyhow!("Geyser stream closed"))??;
+                    match update.update_oneof {
+                        Some(UpdateOneof::Account(update)) => {
+                            if let Some((key, account, slot)) = account_update(update) {
+                                cache.update(key, Some(account), slot);
+                            }
+                        }
+                        Some(UpdateOneof::Ping(_)) => {
+                            // Keeps load balancers from closing an idle stream
+                            let ping = SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            };
+                            requests.send(ping).await?;
+                        }
+                        _ => {}
+                    }
+                }
+                _ = cache.watch_added() => {}
+            }
+        }
+    }
+}
+
+impl AccountSource for GeyserSource {
+    fn name(&self) -> &str {
+        "geyser"
+    }
+
+    fn follow<'a>(&'a self, cache: &'a StateCache) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.follow_updates(cache))
+    }
+}
+
+/// Address, state and slot of a streamed account, None when the update is malformed
+fn account_update(update: SubscribeUpdateAccount) -> Option<(Pubkey, Account, u64)> {
+    let info = update.account?;
+    let account = Account {
+        lamports: info.lamports,
+        data: info.data,
+        owner: Pubkey::try_from(info.owner.as_slice()).ok()?,
+        executable: info.executable,
+        rent_epoch: info.rent_epoch,
+    };
+    Some((
+        Pubkey::try_from(info.pubkey.as_slice()).ok()?,
+        account,
+        update.slot,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yellowstone_grpc_proto::prelude::SubscribeUpdateAccountInfo;
+
+    #[test]
+    fn test_account_update_conversion() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let update = SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: key.to_bytes().to_vec(),
+                lamports: 42,
+                owner: owner.to_bytes().to_vec(),
+                data: vec![1, 2, 3],
+                ..Default::default()
+            }),
+            slot: 7,
+            ..Default::default()
+        };
+        let (converted_key, account, slot) = account_update(update).unwrap();
+        assert_eq!(converted_key, key);
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.lamports, 42);
+        assert_eq!(account.data, vec![1, 2, 3]);
+        assert_eq!(slot, 7);
+
+        let truncated = SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: vec![0; 31],
+                owner: owner.to_bytes().to_vec(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(account_update(truncated).is_none());
+    }
+}
@@ -0,0 +1,423 @@
+use crate::sender::{TipAccounts, TransactionSender};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// Mainnet tip accounts, see https://docs.jito.wtf/lowlatencytxnsend/#gettipaccounts
+pub const DEFAULT_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+const BUNDLES_PATH: &str = "/api/v1/bundles";
+
+/// Status of a bundle as reported by `getInflightBundleStatuses`. The block engine only
+/// knows about bundles from roughly the last five minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleStatus {
+    /// Not found, or dropped before reaching a leader
+    Invalid,
+    Pending,
+    /// Every leader slot it was forwarded to passed without it landing
+    Failed,
+    Landed {
+        slot: u64,
+    },
+}
+
+impl BundleStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, BundleStatus::Pending)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InflightBundleStatus {
+    bundle_id: String,
+    status: String,
+    landed_slot: Option<u64>,
+}
+
+/// Submits bundles to a Jito block engine over its JSON-RPC API
+pub struct JitoClient {
+    http: reqwest::Client,
+    bundles_url: String,
+    tip_accounts: TipAccounts,
+    pub tip_lamports: u64,
+}
+
+impl JitoClient {
+    pub fn new(
+        block_engine_url: &str,
+        tip_accounts: Vec<Pubkey>,
+        tip_lamports: u64,
+    ) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()?,
+            bundles_url: format!("{}{}", block_engine_url.trim_end_matches('/'), BUNDLES_PATH),
+            tip_accounts: TipAccounts::new(tip_accounts)?,
+            tip_lamports,
+        })
+    }
+
+    /// Transfer of `lamports` from `payer` to the next tip account. Append it to the last
+    /// transaction of the bundle so the tip is only paid when everything before it
+    /// succeeds.
+    pub fn tip_instruction(&self, payer: &Pubkey, lamports: u64) -> Instruction {
+        self.tip_accounts.transfer(payer, lamports)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .http
+            .post(&self.bundles_url)
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("{} request failed", method))?
+            .json()
+            .await
+            .with_context(|| format!("{} returned an invalid response", method))?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("{} response has no result", method))
+    }
+
+    /// Submits up to five signed transactions, executed atomically and in order. Returns
+    /// the bundle id.
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        if transactions.is_empty() || transactions.len() > 5 {
+            bail!(
+                "A bundle holds 1 to 5 transactions, got {}",
+                transactions.len()
+            );
+        }
+        let encoded = transactions
+            .iter()
+            .map(|tx| Ok(STANDARD.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let result = self
+            .call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("sendBundle returned a non-string bundle id"))
+    }
+
+    /// Statuses of up to five bundles, in the order of `bundle_ids`
+    pub async fn get_inflight_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<BundleStatus>> {
+        let result = self
+            .call("getInflightBundleStatuses", json!([bundle_ids]))
+            .await?;
+        let statuses: Vec<InflightBundleStatus> = serde_json::from_value(
+            result
+                .get("value")
+                .cloned()
+                .ok_or_else(|| anyhow!("getInflightBundleStatuses response has no value"))?,
+        )?;
+        Ok(bundle_ids
+            .iter()
+            .map(
+                |id| match statuses.iter().find(|status| &status.bundle_id == id) {
+                    Some(status) => match (status.status.as_str(), status.landed_slot) {
+                        ("Pending", _) => BundleStatus::Pending,
+                        ("Failed", _) => BundleStatus::Failed,
+                        ("Landed", Some(slot)) => BundleStatus::Landed { slot },
+                        _ => BundleStatus::Invalid,
+                    },
+                    None => BundleStatus::Invalid,
+                },
+            )
+            .collect())
+    }
+}
+
+/// Bundle ids `getInflightBundleStatuses` takes per call
+const MAX_STATUS_BATCH: usize = 5;
+
+const BUNDLE_STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a bundle is followed before it is logged as still pending
+const BUNDLE_STATUS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Follows the status of sent bundles from a single background task, which asks the
+/// block engine about up to five of them per call and logs each once it is final
+pub struct BundleTracker {
+    bundles: mpsc::UnboundedSender<String>,
+}
+
+impl BundleTracker {
+    pub fn spawn(client: Arc<JitoClient>) -> Self {
+        let (bundles, received) = mpsc::unbounded_channel();
+        tokio::spawn(track_bundles(
+            client,
+            received,
+            BUNDLE_STATUS_INTERVAL,
+            BUNDLE_STATUS_TIMEOUT,
+        ));
+        Self { bundles }
+    }
+
+    pub fn track(&self, bundle_id: String) {
+        let _ = self.bundles.send(bundle_id);
+    }
+}
+
+async fn track_bundles(
+    client: Arc<JitoClient>,
+    mut bundles: mpsc::UnboundedReceiver<String>,
+    interval: Duration,
+    timeout: Duration,
+) {
+    let mut pending = Vec::new();
+    loop {
+        if pending.is_empty() {
+            match bundles.recv().await {
+                Some(bundle_id) => pending.push((bundle_id, Instant::now())),
+                None => return,
+            }
+        }
+        tokio::time::sleep(interval).await;
+        while let Ok(bundle_id) = bundles.try_recv() {
+            pending.push((bundle_id, Instant::now()));
+        }
+        for (bundle_id, status) in poll_bundles(&client, &mut pending, timeout).await {
+            info!("Bundle {} status: {:?}", bundle_id, status);
+        }
+    }
+}
+
+/// Asks for the status of every pending bundle, in batches. Returns the bundles that are
+/// final or past `timeout`, which are no longer pending. Bundles whose batch could not be
+/// queried stay pending until they time out.
+async fn poll_bundles(
+    client: &JitoClient,
+    pending: &mut Vec<(String, Instant)>,
+    timeout: Duration,
+) -> Vec<(String, BundleStatus)> {
+    let mut done = Vec::new();
+    let mut still_pending = Vec::new();
+    for batch in pending.chunks(MAX_STATUS_BATCH) {
+        let bundle_ids: Vec<String> = batch.iter().map(|(id, _)| id.clone()).collect();
+        let statuses = match client.get_inflight_bundle_statuses(&bundle_ids).await {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                error!("Failed to get status of bundles {:?}: {}", bundle_ids, e);
+                vec![BundleStatus::Pending; batch.len()]
+            }
+        };
+        for ((bundle_id, sent_at), status) in batch.iter().cloned().zip(statuses) {
+            if status.is_final() || sent_at.elapsed() >= timeout {
+                done.push((bundle_id, status));
+            } else {
+                still_pending.push((bundle_id, sent_at));
+            }
+        }
+    }
+    *pending = still_pending;
+    done
+}
+
+/// Sends each transaction as a bundle of its own, tipped by its last instruction
+pub struct BundleSender {
+    name: String,
+    client: Arc<JitoClient>,
+    tracker: BundleTracker,
+}
+
+impl BundleSender {
+    /// Must be called from within a tokio runtime, which runs the bundle tracker
+    pub fn new(name: String, client: Arc<JitoClient>) -> Self {
+        let tracker = BundleTracker::spawn(client.clone());
+        Self {
+            name,
+            client,
+            tracker,
+        }
+    }
+}
+
+impl TransactionSender for BundleSender {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn min_tip_lamports(&self) -> Option<u64> {
+        Some(self.client.tip_lamports)
+    }
+
+    fn tip_instruction(&self, payer: &Pubkey, lamports: u64) -> Option<Instruction> {
+        Some(self.client.tip_instruction(payer, lamports))
+    }
+
+    /// Succeeds with the transaction's signature once the block engine takes the bundle,
+    /// whose status is then logged in the background
+    fn send<'a>(&'a self, tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move {
+            let bundle_id = self.client.send_bundle(std::slice::from_ref(tx)).await?;
+            info!("Bundle sent through {}: {}", self.name, bundle_id);
+            self.tracker.track(bundle_id);
+            Ok(tx.signatures[0])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rpc_stand_in, signed_transaction};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+    use std::sync::Mutex;
+
+    fn tip_accounts() -> Vec<Pubkey> {
+        (1..=3).map(|i| Pubkey::new_from_array([i; 32])).collect()
+    }
+
+    #[test]
+    fn test_tip_accounts_rotate() {
+        let client = JitoClient::new("http://localhost", tip_accounts(), 1_000).unwrap();
+        let payer = Pubkey::new_unique();
+        let tipped: Vec<Pubkey> = (0..4)
+            .map(|_| client.tip_instruction(&payer, 1_000).accounts[1].pubkey)
+            .collect();
+        let expected = tip_accounts();
+        assert_eq!(tipped, [expected[0], expected[1], expected[2], expected[0]]);
+        assert!(JitoClient::new("http://localhost", vec![], 1_000).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_and_track_status() {
+        let results = Mutex::new(
+            vec![
+                json!("bundle-1"),
+                json!({ "context": { "slot": 100 }, "value": [
+                    { "bundle_id": "bundle-1", "status": "Pending", "landed_slot": null },
+                ]}),
+                json!({ "context": { "slot": 101 }, "value": [
+                    { "bundle_id": "bundle-1", "status": "Landed", "landed_slot": 101 },
+                ]}),
+            ]
+            .into_iter(),
+        );
+        let mut block_engine = rpc_stand_in(Duration::ZERO, move |_| {
+            results.lock().unwrap().next().unwrap_or(Value::Null)
+        })
+        .await;
+        let client = JitoClient::new(&block_engine.url, tip_accounts(), 5_000).unwrap();
+
+        let payer = Keypair::new();
+        let tx = signed_transaction(
+            &payer,
+            &[client.tip_instruction(&payer.pubkey(), client.tip_lamports)],
+            Hash::default(),
+        );
+
+        let bundle_id = client.send_bundle(std::slice::from_ref(&tx)).await.unwrap();
+        assert_eq!(bundle_id, "bundle-1");
+        let request = block_engine.requests.recv().await.unwrap();
+        assert_eq!(request["method"], "sendBundle");
+        assert_eq!(request["params"][1]["encoding"], "base64");
+        let sent = STANDARD
+            .decode(request["params"][0][0].as_str().unwrap())
+            .unwrap();
+        let sent: VersionedTransaction = bincode::deserialize(&sent).unwrap();
+        assert_eq!(sent, tx);
+
+        let mut pending = vec![(bundle_id, Instant::now())];
+        let timeout = Duration::from_secs(60);
+        assert!(poll_bundles(&client, &mut pending, timeout)
+            .await
+            .is_empty());
+        assert_eq!(pending.len(), 1);
+        let done = poll_bundles(&client, &mut pending, timeout).await;
+        assert_eq!(
+            done,
+            [("bundle-1".to_string(), BundleStatus::Landed { slot: 101 })]
+        );
+        assert!(pending.is_empty());
+        let request = block_engine.requests.recv().await.unwrap();
+        assert_eq!(request["method"], "getInflightBundleStatuses");
+        assert_eq!(request["params"], json!([["bundle-1"]]));
+    }
+
+    #[tokio::test]
+    async fn test_bundle_statuses_polled_in_batches() {
+        // Every bundle but the last has landed
+        let block_engine = rpc_stand_in(Duration::ZERO, |request| {
+            let value: Vec<Value> = request["params"][0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|id| match id.as_str() {
+                    Some("bundle-6") => json!({ "bundle_id": id, "status": "Pending" }),
+                    _ => json!({ "bundle_id": id, "status": "Landed", "landed_slot": 7 }),
+                })
+                .collect();
+            json!({ "context": { "slot": 7 }, "value": value })
+        })
+        .await;
+        let mut requests = block_engine.requests;
+        let client = JitoClient::new(&block_engine.url, tip_accounts(), 5_000).unwrap();
+
+        let now = Instant::now();
+        let mut pending: Vec<_> = (0..7).map(|i| (format!("bundle-{}", i), now)).collect();
+        let done = poll_bundles(&client, &mut pending, Duration::from_secs(60)).await;
+        assert_eq!(done.len(), 6);
+        assert!(done
+            .iter()
+            .all(|(_, status)| *status == BundleStatus::Landed { slot: 7 }));
+        assert_eq!(pending, [("bundle-6".to_string(), now)]);
+        // Given up on once it times out
+        let done = poll_bundles(&client, &mut pending, Duration::ZERO).await;
+        assert_eq!(done, [("bundle-6".to_string(), BundleStatus::Pending)]);
+        assert!(pending.is_empty());
+        let batches: Vec<usize> = [
+            requests.recv().await,
+            requests.recv().await,
+            requests.recv().await,
+        ]
+        .iter()
+        .map(|request| {
+            request.as_ref().unwrap()["params"][0]
+                .as_array()
+                .unwrap()
+                .len()
+        })
+        .collect();
+        assert_eq!(batches, [5, 2, 1]);
+    }
+}
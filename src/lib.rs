@@ -1,7 +1,27 @@
+pub mod bidding;
 pub mod bot;
+pub mod clock;
 pub mod config;
+pub mod confirmation;
 pub mod constants;
 pub mod dex;
+#[cfg(feature = "geyser")]
+pub mod geyser;
+pub mod jito;
+pub mod nonce;
 pub mod pools;
+pub mod priority_fee;
+pub mod quote;
+pub mod reconnect;
 pub mod refresh;
+pub mod sender;
+pub mod simulation;
+pub mod snapshot;
+pub mod sizing;
+pub mod state_cache;
+pub mod tpu;
 pub mod transaction;
+pub mod trigger;
+
+#[cfg(test)]
+mod test_utils;
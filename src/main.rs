@@ -1,12 +1,32 @@
+mod bidding;
 mod bot;
+mod clock;
 mod config;
+mod confirmation;
 mod constants;
 mod dex;
+#[cfg(feature = "geyser")]
+mod geyser;
+mod jito;
+mod nonce;
 mod pools;
+mod priority_fee;
+mod quote;
+mod reconnect;
 mod refresh;
+mod sender;
+mod simulation;
+mod snapshot;
+mod sizing;
+mod state_cache;
+mod tpu;
 mod transaction;
+mod trigger;
 
-use clap::{App, Arg};
+#[cfg(test)]
+mod test_utils;
+
+use clap::{App, Arg, SubCommand};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -33,11 +53,29 @@ async fn main() -> anyhow::Result<()> {
                 .takes_value(true)
                 .default_value("config.toml"),
         )
+        .subcommand(
+            SubCommand::with_name("create-nonce-accounts")
+                .about("Creates durable nonce accounts owned by the wallet")
+                .arg(
+                    Arg::with_name("count")
+                        .short('n')
+                        .long("count")
+                        .value_name("COUNT")
+                        .help("Number of nonce accounts to create")
+                        .takes_value(true)
+                        .default_value("4"),
+                ),
+        )
         .get_matches();
 
     let config_path = matches.value_of("config").unwrap();
     info!("Using config file: {}", config_path);
 
+    if let Some(matches) = matches.subcommand_matches("create-nonce-accounts") {
+        let count = matches.value_of("count").unwrap().parse()?;
+        return bot::create_nonce_accounts(config_path, count);
+    }
+
     bot::run_bot(config_path).await?;
 
     Ok(())
@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use solana_client::nonce_utils;
+use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::Instruction;
+use solana_program::system_instruction;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::nonce::State;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, warn};
+
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A nonce whose transaction hasn't advanced it after this long is handed out again.
+/// Only one transaction per nonce value can ever land, so the two can't both execute.
+pub const DEFAULT_REUSE_AFTER: Duration = Duration::from_secs(30);
+
+struct NonceSlot {
+    account: Pubkey,
+    /// Current nonce value, None until loaded or when the account is unusable
+    value: Option<Hash>,
+    leased: bool,
+    /// Value the last sent transaction used, and when
+    last_sent: Option<(Hash, Instant)>,
+}
+
+impl NonceSlot {
+    fn is_available(&self, now: Instant, reuse_after: Duration) -> bool {
+        match (self.leased, self.value, self.last_sent) {
+            (false, Some(value), Some((sent_value, sent_at))) => {
+                value != sent_value || now.duration_since(sent_at) >= reuse_after
+            }
+            (false, Some(_), None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Durable nonce accounts, handed out one per in-flight transaction.
+///
+/// A transaction using a nonce starts by advancing it and signs over its current value
+/// instead of a recent blockhash, so it never expires. Once a transaction is sent its
+/// nonce is held back until it advances, so parallel transactions each get their own.
+pub struct NoncePool {
+    authority: Pubkey,
+    reuse_after: Duration,
+    slots: Mutex<Vec<NonceSlot>>,
+}
+
+/// A nonce checked out of the pool, returned to it when dropped
+pub struct NonceLease<'a> {
+    pool: &'a NoncePool,
+    index: usize,
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    /// Signs in place of a recent blockhash
+    pub value: Hash,
+}
+
+impl NonceLease<'_> {
+    /// Must be the first instruction of the transaction
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.account, &self.authority)
+    }
+
+    /// Holds this nonce back until it advances, or for the reuse delay
+    pub fn mark_sent(&self) {
+        self.pool.slots.lock().unwrap()[self.index].last_sent = Some((self.value, Instant::now()));
+    }
+}
+
+impl Drop for NonceLease<'_> {
+    fn drop(&mut self) {
+        self.pool.slots.lock().unwrap()[self.index].leased = false;
+    }
+}
+
+impl NoncePool {
+    pub fn new(authority: Pubkey, accounts: Vec<Pubkey>, reuse_after: Duration) -> Self {
+        Self {
+            authority,
+            reuse_after,
+            slots: Mutex::new(
+                accounts
+                    .into_iter()
+                    .map(|account| NonceSlot {
+                        account,
+                        value: None,
+                        leased: false,
+                        last_sent: None,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Next nonce that is loaded and not in flight, if any
+    pub fn acquire(&self) -> Option<NonceLease<'_>> {
+        let now = Instant::now();
+        let mut slots = self.slots.lock().unwrap();
+        let index = slots
+            .iter()
+            .position(|slot| slot.is_available(now, self.reuse_after))?;
+        let slot = &mut slots[index];
+        slot.leased = true;
+        Some(NonceLease {
+            pool: self,
+            index,
+            account: slot.account,
+            authority: self.authority,
+            value: slot.value.unwrap(),
+        })
+    }
+
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        self.slots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|slot| slot.account)
+            .collect()
+    }
+
+    fn set_value(&self, account: &Pubkey, value: Option<Hash>) {
+        let mut slots = self.slots.lock().unwrap();
+        if let Some(slot) = slots.iter_mut().find(|slot| slot.account == *account) {
+            slot.value = value;
+        }
+    }
+
+    /// Reloads every nonce value. Accounts that are missing, uninitialized or owned by
+    /// another authority are taken out of rotation until they are fixed.
+    pub fn refresh(&self, rpc_client: &RpcClient) -> Result<()> {
+        let accounts = self.accounts();
+        for (account, data) in accounts
+            .iter()
+            .zip(rpc_client.get_multiple_accounts(&accounts)?)
+        {
+            let value = match data.map(|data| nonce_utils::data_from_account(&data)) {
+                Some(Ok(data)) if data.authority == self.authority => Some(data.blockhash()),
+                Some(Ok(data)) => {
+                    warn!(
+                        "Nonce account {} has authority {}, not the wallet",
+                        account, data.authority
+                    );
+                    None
+                }
+                Some(Err(e)) => {
+                    warn!("Nonce account {} is unusable: {}", account, e);
+                    None
+                }
+                None => {
+                    warn!("Nonce account {} does not exist", account);
+                    None
+                }
+            };
+            self.set_value(account, value);
+        }
+        Ok(())
+    }
+}
+
+pub async fn nonce_refresher(
+    rpc_client: Arc<RpcClient>,
+    pool: Arc<NoncePool>,
+    refresh_interval: Duration,
+) {
+    loop {
+        // The blocking client runs off the async workers
+        let refresh = {
+            let rpc_client = rpc_client.clone();
+            let pool = pool.clone();
+            tokio::task::spawn_blocking(move || pool.refresh(&rpc_client))
+        };
+        match refresh.await.unwrap_or_else(|e| Err(e.into())) {
+            Ok(()) => debug!("Nonce values refreshed"),
+            Err(e) => error!("Failed to refresh nonce accounts: {:?}", e),
+        }
+        tokio::time::sleep(refresh_interval).await;
+    }
+}
+
+/// Creates `count` nonce accounts with `payer` as their authority, one transaction each,
+/// and returns their addresses
+pub fn create_nonce_accounts(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    count: usize,
+) -> Result<Vec<Pubkey>> {
+    let lamports = rpc_client.get_minimum_balance_for_rent_exemption(State::size())?;
+    let mut accounts = Vec::with_capacity(count);
+    for _ in 0..count {
+        let nonce_kp = Keypair::new();
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(10_000)];
+        instructions.extend(system_instruction::create_nonce_account(
+            &payer.pubkey(),
+            &nonce_kp.pubkey(),
+            &payer.pubkey(),
+            lamports,
+        ));
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, &nonce_kp],
+            rpc_client.get_latest_blockhash()?,
+        );
+        rpc_client
+            .send_and_confirm_transaction(&tx)
+            .with_context(|| format!("Failed to create nonce account {}", nonce_kp.pubkey()))?;
+        accounts.push(nonce_kp.pubkey());
+    }
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonces_are_not_shared_while_in_flight() {
+        let accounts = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let pool = NoncePool::new(
+            Pubkey::new_unique(),
+            accounts.clone(),
+            Duration::from_secs(60),
+        );
+        // Nothing is handed out before the values are loaded
+        assert!(pool.acquire().is_none());
+        let (first, second) = (Hash::new_unique(), Hash::new_unique());
+        pool.set_value(&accounts[0], Some(first));
+        pool.set_value(&accounts[1], Some(second));
+
+        let lease = pool.acquire().unwrap();
+        assert_eq!((lease.account, lease.value), (accounts[0], first));
+        assert_eq!(lease.advance_instruction().accounts[0].pubkey, accounts[0]);
+        // Leased nonces are skipped
+        let other = pool.acquire().unwrap();
+        assert_eq!(other.account, accounts[1]);
+        assert!(pool.acquire().is_none());
+
+        // A nonce that was not sent with is available again as soon as it is returned
+        drop(other);
+        lease.mark_sent();
+        drop(lease);
+        assert_eq!(pool.acquire().unwrap().account, accounts[1]);
+
+        // A sent nonce comes back once it has advanced
+        pool.set_value(&accounts[1], None);
+        assert!(pool.acquire().is_none());
+        pool.set_value(&accounts[0], Some(Hash::new_unique()));
+        assert_eq!(pool.acquire().unwrap().account, accounts[0]);
+    }
+}
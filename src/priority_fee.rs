@@ -0,0 +1,140 @@
+use crate::config::PriorityFeeConfig;
+use anyhow::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_response::RpcPrioritizationFee;
+use solana_program::pubkey::Pubkey;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// `getRecentPrioritizationFees` accepts at most this many accounts
+pub const MAX_FEE_ACCOUNTS: usize = 128;
+
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Nearest-rank percentile of `values`, 0 when empty
+pub fn percentile(values: &[u64], percentile: u8) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = (usize::from(percentile.min(100)) * sorted.len()).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+/// Tracks the compute unit price to bid for transactions touching a set of accounts,
+/// from the fees recently paid to write them
+pub struct PriorityFeeOracle {
+    accounts: Vec<Pubkey>,
+    percentile: u8,
+    min_compute_unit_price: u64,
+    max_compute_unit_price: u64,
+    compute_unit_price: AtomicU64,
+}
+
+impl PriorityFeeOracle {
+    /// Only the first `MAX_FEE_ACCOUNTS` accounts are used. Starts at the minimum price
+    /// until the first refresh.
+    pub fn new(config: &PriorityFeeConfig, mut accounts: Vec<Pubkey>) -> Self {
+        accounts.truncate(MAX_FEE_ACCOUNTS);
+        Self {
+            accounts,
+            percentile: config.percentile,
+            min_compute_unit_price: config.min_compute_unit_price,
+            max_compute_unit_price: config.max_compute_unit_price,
+            compute_unit_price: AtomicU64::new(config.min_compute_unit_price),
+        }
+    }
+
+    /// Current compute unit price in micro-lamports
+    pub fn compute_unit_price(&self) -> u64 {
+        self.compute_unit_price.load(Ordering::Relaxed)
+    }
+
+    pub fn clamp(&self, compute_unit_price: u64) -> u64 {
+        compute_unit_price
+            .max(self.min_compute_unit_price)
+            .min(self.max_compute_unit_price)
+    }
+
+    /// Sets the price from per-slot fees and returns it
+    pub fn update(&self, fees: &[RpcPrioritizationFee]) -> u64 {
+        let values: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+        let compute_unit_price = self.clamp(percentile(&values, self.percentile));
+        self.compute_unit_price
+            .store(compute_unit_price, Ordering::Relaxed);
+        compute_unit_price
+    }
+
+    pub fn refresh(&self, rpc_client: &RpcClient) -> Result<u64> {
+        let fees = rpc_client.get_recent_prioritization_fees(&self.accounts)?;
+        Ok(self.update(&fees))
+    }
+}
+
+pub async fn priority_fee_refresher(
+    rpc_client: Arc<RpcClient>,
+    oracle: Arc<PriorityFeeOracle>,
+    refresh_interval: Duration,
+) {
+    loop {
+        // The blocking client runs off the async workers
+        let refresh = {
+            let rpc_client = rpc_client.clone();
+            let oracle = oracle.clone();
+            tokio::task::spawn_blocking(move || oracle.refresh(&rpc_client))
+        };
+        match refresh.await.unwrap_or_else(|e| Err(e.into())) {
+            Ok(compute_unit_price) => {
+                debug!("Compute unit price refreshed: {}", compute_unit_price);
+            }
+            Err(e) => {
+                error!("Failed to refresh priority fees: {:?}", e);
+            }
+        }
+        tokio::time::sleep(refresh_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_and_clamp() {
+        let values = [0, 0, 10, 20, 30, 40, 50, 60, 70, 1_000_000];
+        assert_eq!(percentile(&values, 0), 0);
+        assert_eq!(percentile(&values, 50), 30);
+        assert_eq!(percentile(&values, 75), 60);
+        assert_eq!(percentile(&values, 100), 1_000_000);
+        assert_eq!(percentile(&[], 50), 0);
+
+        let oracle = PriorityFeeOracle::new(
+            &PriorityFeeConfig {
+                enabled: true,
+                percentile: 75,
+                min_compute_unit_price: 100,
+                max_compute_unit_price: 50_000,
+                refresh_interval_ms: None,
+            },
+            vec![],
+        );
+        assert_eq!(oracle.compute_unit_price(), 100);
+        let fees = |values: &[u64]| -> Vec<RpcPrioritizationFee> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(slot, fee)| RpcPrioritizationFee {
+                    slot: slot as u64,
+                    prioritization_fee: *fee,
+                })
+                .collect()
+        };
+        assert_eq!(oracle.update(&fees(&values)), 100);
+        assert_eq!(oracle.update(&fees(&[0, 5_000, 10_000, 20_000])), 10_000);
+        assert_eq!(oracle.update(&fees(&[90_000; 4])), 50_000);
+        assert_eq!(oracle.compute_unit_price(), 50_000);
+    }
+}
@@ -0,0 +1,1197 @@
+use crate::constants::sol_mint;
+use crate::dex::meteora::damm_swap::{self, VaultReserve};
+use crate::dex::meteora::dammv2_info::MeteoraDAmmV2Info;
+use crate::dex::meteora::dlmm_info::{BinArray, DlmmInfo};
+use crate::dex::meteora::dlmm_swap;
+use crate::dex::pump::pump_global_config;
+use crate::dex::raydium::{
+    clmm_info, get_tick_array_pubkeys, raydium_clmm_program_id, AmmConfig, PoolState,
+    RaydiumAmmInfo, RaydiumCpAmmConfig, RaydiumCpAmmInfo, TickArrayState, FEE_RATE_DENOMINATOR,
+};
+use crate::dex::solfi::constants::SOLFI_ESTIMATED_FEE_BPS;
+use crate::dex::vertigo::VERTIGO_ESTIMATED_FEE_BPS;
+use crate::dex::whirlpool::constants::whirlpool_program_id;
+use crate::dex::whirlpool::state::{TickArray, Whirlpool};
+use crate::dex::whirlpool::{simulate_swap, swap_tick_array_addresses};
+use crate::pools::*;
+use anyhow::{anyhow, bail, Result};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::{create_account_with_fields, from_account, Account};
+use solana_sdk::clock::Clock;
+use solana_sdk::sysvar::clock;
+use std::collections::HashMap;
+
+// The arithmetic the macro generates trips lints that can't be fixed from here
+#[allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+pub use u256::U256;
+
+/// Raw account data the quoters read from, keyed by address
+pub type AccountMap = HashMap<Pubkey, Account>;
+
+/// Every pool in `MintPoolData` pairs SOL with the configured mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwapDirection {
+    SolToToken,
+    TokenToSol,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Fee kept by the pool, in units of the token it was charged on
+    pub fee_amount: u64,
+}
+
+pub trait PoolQuoter: Send + Sync {
+    fn pool_address(&self) -> Pubkey;
+
+    /// Accounts whose data `quote_exact_in` reads
+    fn quote_accounts(&self) -> Vec<Pubkey>;
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote>;
+}
+
+impl MintPoolData {
+    pub fn quoters(&self) -> Vec<&dyn PoolQuoter> {
+        let mut quoters: Vec<&dyn PoolQuoter> = Vec::new();
+        quoters.extend(self.raydium_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.raydium_cp_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.pump_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.dlmm_pairs.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.whirlpool_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.raydium_clmm_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.meteora_damm_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.meteora_damm_v2_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.solfi_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters.extend(self.vertigo_pools.iter().map(|p| p as &dyn PoolQuoter));
+        quoters
+    }
+
+    /// Every account the quoters read, sorted and deduplicated
+    pub fn quote_accounts(&self) -> Vec<Pubkey> {
+        let mut keys: Vec<Pubkey> = self
+            .quoters()
+            .iter()
+            .flat_map(|q| q.quote_accounts())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+fn account<'a>(accounts: &'a AccountMap, key: &Pubkey) -> Result<&'a Account> {
+    accounts
+        .get(key)
+        .ok_or_else(|| anyhow!("Account {} missing from quote state", key))
+}
+
+/// The Clock sysvar. It changes every slot, so quoters that read it leave it out of
+/// `quote_accounts`, and callers add it to the quote state with [`clock_sysvar_account`].
+fn clock_sysvar(accounts: &AccountMap) -> Result<Clock> {
+    from_account(account(accounts, &clock::id())?)
+        .ok_or_else(|| anyhow!("Invalid Clock sysvar data"))
+}
+
+/// Account holding `clock` as the Clock sysvar
+pub fn clock_sysvar_account(clock: &Clock) -> Account {
+    create_account_with_fields(clock, (1, 0))
+}
+
+/// Cluster time from the Clock sysvar
+fn unix_timestamp(accounts: &AccountMap) -> Result<i64> {
+    Ok(clock_sysvar(accounts)?.unix_timestamp)
+}
+
+/// Amount held by an SPL Token or Token-2022 account
+pub fn token_account_amount(data: &[u8]) -> Result<u64> {
+    if data.len() < 72 {
+        bail!("Invalid data length for token account");
+    }
+    Ok(u64::from_le_bytes(data[64..72].try_into()?))
+}
+
+/// Supply of an SPL Token or Token-2022 mint
+pub fn mint_supply(data: &[u8]) -> Result<u64> {
+    if data.len() < 44 {
+        bail!("Invalid data length for mint");
+    }
+    Ok(u64::from_le_bytes(data[36..44].try_into()?))
+}
+
+fn token_balance(accounts: &AccountMap, key: &Pubkey) -> Result<u64> {
+    token_account_amount(&account(accounts, key)?.data)
+}
+
+/// Fee on `amount`, rounded up in the pool's favor
+pub fn fee_amount(amount: u64, fee_numerator: u128, fee_denominator: u128) -> Result<u64> {
+    if fee_denominator == 0 {
+        bail!("Fee denominator is zero");
+    }
+    Ok((u128::from(amount) * fee_numerator).div_ceil(fee_denominator) as u64)
+}
+
+/// x * y = k output for an input that already had its fee removed
+pub fn constant_product_out(amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        bail!("Pool has no liquidity");
+    }
+    let amount_out = u128::from(reserve_out) * u128::from(amount_in)
+        / (u128::from(reserve_in) + u128::from(amount_in));
+    Ok(amount_out as u64)
+}
+
+impl PoolQuoter for RaydiumPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![self.pool, self.token_vault, self.sol_vault]
+    }
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let amm_info = RaydiumAmmInfo::load_checked(&account(accounts, &self.pool)?.data)?;
+        let (sol_pnl, token_pnl) = if amm_info.coin_vault == self.sol_vault {
+            (amm_info.need_take_pnl_coin, amm_info.need_take_pnl_pc)
+        } else {
+            (amm_info.need_take_pnl_pc, amm_info.need_take_pnl_coin)
+        };
+        let sol_reserve = token_balance(accounts, &self.sol_vault)?.saturating_sub(sol_pnl);
+        let token_reserve = token_balance(accounts, &self.token_vault)?.saturating_sub(token_pnl);
+        let (reserve_in, reserve_out) = match direction {
+            SwapDirection::SolToToken => (sol_reserve, token_reserve),
+            SwapDirection::TokenToSol => (token_reserve, sol_reserve),
+        };
+
+        let fee = fee_amount(
+            amount_in,
+            u128::from(amm_info.swap_fee_numerator),
+            u128::from(amm_info.swap_fee_denominator),
+        )?;
+        let amount_out = constant_product_out(amount_in - fee, reserve_in, reserve_out)?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out,
+            fee_amount: fee,
+        })
+    }
+}
+
+impl PoolQuoter for RaydiumCpPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![self.pool, self.amm_config, self.token_vault, self.sol_vault]
+    }
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let amm_info = RaydiumCpAmmInfo::load_checked(&account(accounts, &self.pool)?.data)?;
+        let amm_config =
+            RaydiumCpAmmConfig::load_checked(&account(accounts, &self.amm_config)?.data)?;
+        let (sol_pending_fees, token_pending_fees) = if amm_info.token_0_vault == self.sol_vault {
+            (amm_info.pending_fees_token_0, amm_info.pending_fees_token_1)
+        } else {
+            (amm_info.pending_fees_token_1, amm_info.pending_fees_token_0)
+        };
+        let sol_reserve =
+            token_balance(accounts, &self.sol_vault)?.saturating_sub(sol_pending_fees);
+        let token_reserve =
+            token_balance(accounts, &self.token_vault)?.saturating_sub(token_pending_fees);
+        let (reserve_in, reserve_out) = match direction {
+            SwapDirection::SolToToken => (sol_reserve, token_reserve),
+            SwapDirection::TokenToSol => (token_reserve, sol_reserve),
+        };
+
+        let fee = fee_amount(
+            amount_in,
+            u128::from(amm_config.trade_fee_rate),
+            u128::from(FEE_RATE_DENOMINATOR),
+        )?;
+        let amount_out = constant_product_out(amount_in - fee, reserve_in, reserve_out)?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out,
+            fee_amount: fee,
+        })
+    }
+}
+
+impl PoolQuoter for PumpPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![
+            self.pool,
+            pump_global_config(),
+            self.token_vault,
+            self.sol_vault,
+        ]
+    }
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let pool = pump_amm_cpi::Pool::load_checked(&account(accounts, &self.pool)?.data)
+            .map_err(|e| anyhow!("Invalid Pump pool {}: {:?}", self.pool, e))?;
+        let global_config = pump_amm_cpi::GlobalConfig::load_checked(
+            &account(accounts, &pump_global_config())?.data,
+        )
+        .map_err(|e| anyhow!("Invalid Pump global config: {:?}", e))?;
+        let context = pump_amm_cpi::SwapContext {
+            pool: &pool,
+            global_config: &global_config,
+            base_reserve: token_balance(accounts, &pool.pool_base_token_account)?,
+            quote_reserve: token_balance(accounts, &pool.pool_quote_token_account)?,
+        };
+
+        let base_is_sol = pool.base_mint == sol_mint();
+        let input_is_base = match direction {
+            SwapDirection::SolToToken => base_is_sol,
+            SwapDirection::TokenToSol => !base_is_sol,
+        };
+
+        let (amount_out, fee) = if input_is_base {
+            let result = context
+                .sell_base_input(amount_in, 0.0)
+                .map_err(|e| anyhow!("Pump sell quote failed: {:?}", e))?;
+            (
+                result.ui_quote,
+                result.internal_quote_amount_out - result.ui_quote,
+            )
+        } else {
+            let result = context
+                .buy_quote_input(amount_in, 0.0)
+                .map_err(|e| anyhow!("Pump buy quote failed: {:?}", e))?;
+            (
+                result.base,
+                pump_amm_cpi::U256::from(amount_in) - result.internal_quote_without_fees,
+            )
+        };
+
+        Ok(Quote {
+            amount_in,
+            amount_out: amount_out.as_u64(),
+            fee_amount: fee.as_u64(),
+        })
+    }
+}
+
+impl PoolQuoter for DlmmPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pair
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.pair];
+        accounts.extend(self.bin_arrays.iter().copied());
+        accounts
+    }
+
+    /// Walks bins from the active one. Only bin arrays present in `accounts` are used.
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let dlmm_info = DlmmInfo::load_checked(&account(accounts, &self.pair)?.data)?;
+        let x_is_sol = dlmm_info.token_x_mint == sol_mint();
+        let swap_for_y = match direction {
+            SwapDirection::SolToToken => x_is_sol,
+            SwapDirection::TokenToSol => !x_is_sol,
+        };
+
+        let mut bin_arrays = Vec::new();
+        for address in dlmm_info.swap_bin_arrays(&self.pair, swap_for_y, self.bin_arrays.len())? {
+            match accounts.get(&address) {
+                Some(account) => bin_arrays.push((address, BinArray::load_checked(&account.data)?)),
+                None => break,
+            }
+        }
+
+        let result = dlmm_swap::simulate_swap(
+            &dlmm_info,
+            &bin_arrays,
+            amount_in,
+            swap_for_y,
+            unix_timestamp(accounts)?,
+        )?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.fee_amount,
+        })
+    }
+}
+
+impl PoolQuoter for WhirlpoolPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.pool];
+        accounts.extend(self.tick_arrays.iter().copied());
+        accounts
+    }
+
+    /// Walks the tick arrays in the swap direction. Only arrays present in `accounts`
+    /// are used, so the quote fails if it would need one the executor is not given.
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let whirlpool = Whirlpool::try_deserialize(&account(accounts, &self.pool)?.data)?;
+        let a_is_sol = whirlpool.token_mint_a == sol_mint();
+        let a_to_b = match direction {
+            SwapDirection::SolToToken => a_is_sol,
+            SwapDirection::TokenToSol => !a_is_sol,
+        };
+
+        let mut tick_arrays = Vec::new();
+        for address in
+            swap_tick_array_addresses(&whirlpool, &self.pool, &whirlpool_program_id(), a_to_b)
+        {
+            match accounts.get(&address) {
+                Some(account) => {
+                    tick_arrays.push((address, TickArray::try_deserialize(&account.data)?))
+                }
+                None => break,
+            }
+        }
+
+        let (result, _) = simulate_swap(&whirlpool, &tick_arrays, amount_in, a_to_b)?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.fee_amount,
+        })
+    }
+}
+
+impl PoolQuoter for RaydiumClmmPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = vec![self.pool, self.amm_config];
+        accounts.extend(self.tick_arrays.iter().copied());
+        accounts
+    }
+
+    /// Walks the tick arrays in the swap direction. Only arrays present in `accounts`
+    /// are used, so the quote fails if it would need one the executor is not given.
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let pool_state = PoolState::load_checked(&account(accounts, &self.pool)?.data)?;
+        let amm_config = AmmConfig::load_checked(&account(accounts, &self.amm_config)?.data)?;
+        let mint_0_is_sol = pool_state.token_mint_0 == sol_mint();
+        let zero_for_one = match direction {
+            SwapDirection::SolToToken => mint_0_is_sol,
+            SwapDirection::TokenToSol => !mint_0_is_sol,
+        };
+
+        let offsets: Vec<i32> = (0..self.tick_arrays.len() as i32)
+            .map(|i| if zero_for_one { -i } else { i })
+            .collect();
+        let mut tick_arrays = Vec::new();
+        for address in get_tick_array_pubkeys(
+            &self.pool,
+            pool_state.tick_current,
+            pool_state.tick_spacing,
+            &offsets,
+            &raydium_clmm_program_id(),
+        )? {
+            match accounts.get(&address) {
+                Some(account) => {
+                    tick_arrays.push((address, TickArrayState::load_checked(&account.data)?))
+                }
+                None => break,
+            }
+        }
+
+        let (result, _) = clmm_info::simulate_swap(
+            &pool_state,
+            &amm_config,
+            &tick_arrays,
+            amount_in,
+            zero_for_one,
+        )?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.fee_amount,
+        })
+    }
+}
+
+impl PoolQuoter for MeteoraDAmmPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![
+            self.pool,
+            self.token_x_vault,
+            self.token_sol_vault,
+            self.token_x_token_vault,
+            self.token_sol_token_vault,
+            self.token_x_pool_lp,
+            self.token_sol_pool_lp,
+            self.token_x_lp_mint,
+            self.token_sol_lp_mint,
+        ]
+    }
+
+    /// Reserves are the pool's vault shares valued at the current cluster time
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let pool =
+            meteora_damm_cpi::Pool::deserialize_unchecked(&account(accounts, &self.pool)?.data)?;
+        let current_time = u64::try_from(unix_timestamp(accounts)?)?;
+
+        let reserve = |vault: &Pubkey,
+                       token_vault: &Pubkey,
+                       pool_lp: &Pubkey,
+                       lp_mint: &Pubkey|
+         -> Result<VaultReserve> {
+            Ok(VaultReserve {
+                vault: meteora_vault_cpi::Vault::deserialize_unchecked(
+                    &account(accounts, vault)?.data,
+                )?,
+                pool_lp_amount: token_balance(accounts, pool_lp)?,
+                lp_supply: mint_supply(&account(accounts, lp_mint)?.data)?,
+                token_vault_amount: token_balance(accounts, token_vault)?,
+            })
+        };
+        let token_reserve = reserve(
+            &self.token_x_vault,
+            &self.token_x_token_vault,
+            &self.token_x_pool_lp,
+            &self.token_x_lp_mint,
+        )?;
+        let sol_reserve = reserve(
+            &self.token_sol_vault,
+            &self.token_sol_token_vault,
+            &self.token_sol_pool_lp,
+            &self.token_sol_lp_mint,
+        )?;
+
+        let a_is_sol = pool.token_a_mint == sol_mint();
+        let (vault_a, vault_b) = if a_is_sol {
+            (&sol_reserve, &token_reserve)
+        } else {
+            (&token_reserve, &sol_reserve)
+        };
+        let a_to_b = match direction {
+            SwapDirection::SolToToken => a_is_sol,
+            SwapDirection::TokenToSol => !a_is_sol,
+        };
+
+        let result =
+            damm_swap::simulate_swap(&pool, vault_a, vault_b, amount_in, a_to_b, current_time)?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.fee_amount + result.protocol_fee_amount,
+        })
+    }
+}
+
+impl PoolQuoter for MeteoraDAmmV2Pool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![self.pool]
+    }
+
+    /// Quote with the fee in effect at the current slot and time, following the pool's
+    /// fee scheduler and dynamic fee
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        let pool = MeteoraDAmmV2Info::load_checked(&account(accounts, &self.pool)?.data)?;
+        let clock = clock_sysvar(accounts)?;
+        let a_is_sol = pool.token_a_mint == sol_mint();
+        let a_to_b = match direction {
+            SwapDirection::SolToToken => a_is_sol,
+            SwapDirection::TokenToSol => !a_is_sol,
+        };
+
+        let result = pool
+            .get_swap_result(
+                amount_in,
+                a_to_b,
+                clock.slot,
+                u64::try_from(clock.unix_timestamp)?,
+            )
+            .map_err(|e| anyhow!("DAMM V2 pool {}: {}", self.pool, e))?;
+
+        Ok(Quote {
+            amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.trading_fee,
+        })
+    }
+}
+
+// SolFi and Vertigo do not publish their pricing curves, so these pools are
+// quoted as constant-product pools over their vault balances.
+fn vault_balance_quote(
+    accounts: &AccountMap,
+    token_vault: &Pubkey,
+    sol_vault: &Pubkey,
+    fee_bps: u64,
+    amount_in: u64,
+    direction: SwapDirection,
+) -> Result<Quote> {
+    let sol_reserve = token_balance(accounts, sol_vault)?;
+    let token_reserve = token_balance(accounts, token_vault)?;
+    let (reserve_in, reserve_out) = match direction {
+        SwapDirection::SolToToken => (sol_reserve, token_reserve),
+        SwapDirection::TokenToSol => (token_reserve, sol_reserve),
+    };
+
+    let fee = fee_amount(amount_in, u128::from(fee_bps), 10_000)?;
+    let amount_out = constant_product_out(amount_in - fee, reserve_in, reserve_out)?;
+
+    Ok(Quote {
+        amount_in,
+        amount_out,
+        fee_amount: fee,
+    })
+}
+
+impl PoolQuoter for SolfiPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        vault_balance_quote(
+            accounts,
+            &self.token_x_vault,
+            &self.token_sol_vault,
+            SOLFI_ESTIMATED_FEE_BPS,
+            amount_in,
+            direction,
+        )
+    }
+}
+
+impl PoolQuoter for VertigoPool {
+    fn pool_address(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn quote_accounts(&self) -> Vec<Pubkey> {
+        vec![self.token_x_vault, self.token_sol_vault]
+    }
+
+    fn quote_exact_in(
+        &self,
+        accounts: &AccountMap,
+        amount_in: u64,
+        direction: SwapDirection,
+    ) -> Result<Quote> {
+        vault_balance_quote(
+            accounts,
+            &self.token_x_vault,
+            &self.token_sol_vault,
+            VERTIGO_ESTIMATED_FEE_BPS,
+            amount_in,
+            direction,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::meteora::dlmm_info::LbPair;
+    use crate::dex::raydium::clmm_info::TICK_ARRAY_SIZE;
+    use borsh::BorshSerialize;
+    use std::mem::size_of;
+
+    const AMOUNT_IN: u64 = 1_000_000;
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn data_account(data: Vec<u8>) -> Account {
+        Account {
+            data,
+            ..Account::default()
+        }
+    }
+
+    fn token_account(amount: u64) -> Account {
+        let mut data = vec![0; 165];
+        put(&mut data, 64, &amount.to_le_bytes());
+        data_account(data)
+    }
+
+    fn mint_account(supply: u64) -> Account {
+        let mut data = vec![0; 82];
+        put(&mut data, 36, &supply.to_le_bytes());
+        data_account(data)
+    }
+
+    fn clock_account(slot: u64, unix_timestamp: i64) -> Account {
+        clock_sysvar_account(&Clock {
+            slot,
+            unix_timestamp,
+            ..Clock::default()
+        })
+    }
+
+    /// The SOL vault holds `sol`, the token vault `token`
+    fn vault_accounts(token_vault: Pubkey, sol_vault: Pubkey, token: u64, sol: u64) -> AccountMap {
+        HashMap::from([
+            (token_vault, token_account(token)),
+            (sol_vault, token_account(sol)),
+        ])
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(token_account_amount(&token_account(42).data).unwrap(), 42);
+        assert!(token_account_amount(&[0; 71]).is_err());
+        assert_eq!(mint_supply(&mint_account(7).data).unwrap(), 7);
+
+        // Rounded up in the pool's favor
+        assert_eq!(fee_amount(1_000, 25, 10_000).unwrap(), 3);
+        assert_eq!(fee_amount(400, 25, 10_000).unwrap(), 1);
+        assert_eq!(fee_amount(0, 25, 10_000).unwrap(), 0);
+        assert!(fee_amount(1_000, 25, 0).is_err());
+
+        assert_eq!(
+            constant_product_out(1_000, 1_000_000, 2_000_000).unwrap(),
+            1_998
+        );
+        assert!(constant_product_out(1_000, 0, 2_000_000).is_err());
+        assert!(constant_product_out(1_000, 1_000_000, 0).is_err());
+    }
+
+    // 0.25% fee on 1 SOL of reserve against 2 of token, the pools below quote alike
+    const SOL_TO_TOKEN_OUT: u64 = 1_993_011;
+    const TOKEN_TO_SOL_OUT: u64 = 498_501;
+
+    #[test]
+    fn test_raydium_quote_excludes_pnl() {
+        let quoter = RaydiumPool {
+            pool: Pubkey::new_unique(),
+            token_vault: Pubkey::new_unique(),
+            sol_vault: Pubkey::new_unique(),
+        };
+        let mut data = vec![0; 752];
+        put(&mut data, 176, &25u64.to_le_bytes());
+        put(&mut data, 184, &10_000u64.to_le_bytes());
+        // The coin side is the token, and pnl waiting to be taken is no reserve
+        put(&mut data, 192, &1_000u64.to_le_bytes());
+        put(&mut data, 200, &500u64.to_le_bytes());
+        put(&mut data, 336, quoter.token_vault.as_ref());
+        put(&mut data, 368, quoter.sol_vault.as_ref());
+        let mut accounts = vault_accounts(
+            quoter.token_vault,
+            quoter.sol_vault,
+            2_000_001_000,
+            1_000_000_500,
+        );
+        accounts.insert(quoter.pool, data_account(data));
+
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert_eq!(
+            quote,
+            Quote {
+                amount_in: AMOUNT_IN,
+                amount_out: SOL_TO_TOKEN_OUT,
+                fee_amount: 2_500,
+            }
+        );
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::TokenToSol)
+            .unwrap();
+        assert_eq!(quote.amount_out, TOKEN_TO_SOL_OUT);
+    }
+
+    #[test]
+    fn test_raydium_cp_quote_excludes_pending_fees() {
+        let quoter = RaydiumCpPool {
+            pool: Pubkey::new_unique(),
+            token_vault: Pubkey::new_unique(),
+            sol_vault: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            observation: Pubkey::new_unique(),
+        };
+        let mut data = vec![0; 637];
+        put(&mut data, 72, quoter.sol_vault.as_ref());
+        put(&mut data, 104, quoter.token_vault.as_ref());
+        // Protocol fees on token 0, fund fees on token 1
+        put(&mut data, 341, &100u64.to_le_bytes());
+        put(&mut data, 365, &200u64.to_le_bytes());
+        let mut config = vec![0; 236];
+        put(&mut config, 12, &2_500u64.to_le_bytes());
+        let mut accounts = vault_accounts(
+            quoter.token_vault,
+            quoter.sol_vault,
+            2_000_000_200,
+            1_000_000_100,
+        );
+        accounts.insert(quoter.pool, data_account(data));
+        accounts.insert(quoter.amm_config, data_account(config));
+
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert_eq!(
+            (quote.amount_out, quote.fee_amount),
+            (SOL_TO_TOKEN_OUT, 2_500)
+        );
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::TokenToSol)
+            .unwrap();
+        assert_eq!(quote.amount_out, TOKEN_TO_SOL_OUT);
+    }
+
+    #[test]
+    fn test_pump_quote_buys_and_sells() {
+        let quoter = PumpPool {
+            pool: Pubkey::new_unique(),
+            token_vault: Pubkey::new_unique(),
+            sol_vault: Pubkey::new_unique(),
+            fee_token_wallet: Pubkey::new_unique(),
+            coin_creator_vault_ata: Pubkey::new_unique(),
+            coin_creator_vault_authority: Pubkey::new_unique(),
+        };
+        let mut pool = vec![0; pump_amm_cpi::Pool::LEN];
+        put(&mut pool, 43, Pubkey::new_unique().as_ref());
+        put(&mut pool, 75, sol_mint().as_ref());
+        put(&mut pool, 139, quoter.token_vault.as_ref());
+        put(&mut pool, 171, quoter.sol_vault.as_ref());
+        let mut global_config = vec![0; pump_amm_cpi::GlobalConfig::LEN];
+        put(&mut global_config, 40, &20u64.to_le_bytes());
+        put(&mut global_config, 48, &5u64.to_le_bytes());
+        let mut accounts = vault_accounts(
+            quoter.token_vault,
+            quoter.sol_vault,
+            2_000_000_000,
+            1_000_000_000,
+        );
+        accounts.insert(quoter.pool, data_account(pool));
+        accounts.insert(pump_global_config(), data_account(global_config));
+
+        // Buying takes the fee from the SOL going in, selling from the SOL coming out
+        let buy = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert_eq!((buy.amount_out, buy.fee_amount), (1_993_023, 2_494));
+        let sell = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::TokenToSol)
+            .unwrap();
+        assert_eq!((sell.amount_out, sell.fee_amount), (498_502, 1_248));
+    }
+
+    #[test]
+    fn test_dlmm_quote_walks_bin_arrays() {
+        let mut pair = vec![0; 8 + size_of::<LbPair>()];
+        // 0.1% base fee, no variable fee
+        put(&mut pair, 8, &10_000u16.to_le_bytes());
+        put(&mut pair, 24, &(-443_636i32).to_le_bytes());
+        put(&mut pair, 28, &443_636i32.to_le_bytes());
+        put(&mut pair, 80, &10u16.to_le_bytes());
+        put(&mut pair, 88, sol_mint().as_ref());
+        put(&mut pair, 120, Pubkey::new_unique().as_ref());
+        let pair_address = Pubkey::new_unique();
+        let arrays = DlmmInfo::load_checked(&pair)
+            .unwrap()
+            .swap_bin_arrays(&pair_address, true, 2)
+            .unwrap();
+        let quoter = DlmmPool {
+            pair: pair_address,
+            token_vault: Pubkey::new_unique(),
+            sol_vault: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            bin_arrays: arrays.clone(),
+            memo_program: None,
+        };
+        assert!(!quoter.quote_accounts().contains(&clock::id()));
+
+        // Selling SOL walks down from bin 0 into bin -1, which ends the array before
+        let bin_array = |index: i64, bin: usize, amount_y: u64| {
+            let mut data = vec![0; 8 + size_of::<BinArray>()];
+            put(&mut data, 8, &index.to_le_bytes());
+            put(&mut data, 56 + 144 * bin + 8, &amount_y.to_le_bytes());
+            data_account(data)
+        };
+        let mut accounts = HashMap::from([
+            (pair_address, data_account(pair)),
+            (clock::id(), clock_account(1, 1_000)),
+            (arrays[0], bin_array(0, 0, 1_000_000)),
+        ]);
+        assert!(quoter
+            .quote_exact_in(&accounts, 1_500_000, SwapDirection::SolToToken)
+            .is_err());
+
+        accounts.insert(arrays[1], bin_array(-1, 69, 10_000_000));
+        let quote = quoter
+            .quote_exact_in(&accounts, 1_500_000, SwapDirection::SolToToken)
+            .unwrap();
+        // All of bin 0 at price 1, the rest at 1 / 1.001
+        assert_eq!((quote.amount_out, quote.fee_amount), (1_498_000, 1_501));
+    }
+
+    #[test]
+    fn test_whirlpool_quote() {
+        let mut data = vec![0; Whirlpool::LEN];
+        put(&mut data, 41, &64u16.to_le_bytes());
+        put(&mut data, 45, &3_000u16.to_le_bytes());
+        put(&mut data, 49, &1_000_000_000_000u128.to_le_bytes());
+        put(&mut data, 65, &(1u128 << 64).to_le_bytes());
+        put(&mut data, 101, Pubkey::new_unique().as_ref());
+        put(&mut data, 181, sol_mint().as_ref());
+        let pool = Pubkey::new_unique();
+        let whirlpool = Whirlpool::try_deserialize(&data).unwrap();
+        let tick_arrays =
+            swap_tick_array_addresses(&whirlpool, &pool, &whirlpool_program_id(), false);
+        let quoter = WhirlpoolPool {
+            pool,
+            oracle: Pubkey::new_unique(),
+            x_vault: Pubkey::new_unique(),
+            y_vault: Pubkey::new_unique(),
+            tick_arrays: tick_arrays.clone(),
+            memo_program: None,
+        };
+        // Uninitialized ticks from 0, enough for a swap that stays in range
+        let accounts = HashMap::from([
+            (pool, data_account(data)),
+            (tick_arrays[0], data_account(vec![0; TickArray::LEN])),
+        ]);
+
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert!((2_999..=3_000).contains(&quote.fee_amount));
+        assert!(quote.amount_out > 996_000 && quote.amount_out < 997_000);
+    }
+
+    #[test]
+    fn test_raydium_clmm_quote_stops_at_missing_array() {
+        let tick_spacing = 10u16;
+        let pool = Pubkey::new_unique();
+        let tick_arrays = get_tick_array_pubkeys(
+            &pool,
+            5,
+            tick_spacing,
+            &[0, 1, 2],
+            &raydium_clmm_program_id(),
+        )
+        .unwrap();
+        let quoter = RaydiumClmmPool {
+            pool,
+            amm_config: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            bitmap_extension: Pubkey::new_unique(),
+            x_vault: Pubkey::new_unique(),
+            y_vault: Pubkey::new_unique(),
+            tick_arrays: tick_arrays.clone(),
+            memo_program: None,
+        };
+
+        let mut state = vec![0; PoolState::LEN];
+        put(&mut state, 73, Pubkey::new_unique().as_ref());
+        put(&mut state, 105, sol_mint().as_ref());
+        put(&mut state, 235, &tick_spacing.to_le_bytes());
+        put(&mut state, 237, &1_000_000_000u128.to_le_bytes());
+        let sqrt_price = raydium_clmm_cpi::get_sqrt_price_at_tick(5).unwrap();
+        put(&mut state, 253, &sqrt_price.to_le_bytes());
+        put(&mut state, 269, &5i32.to_le_bytes());
+        let mut config = vec![0; AmmConfig::LEN];
+        put(&mut config, 47, &3_000u32.to_le_bytes());
+        let tick_array = |start_tick_index: i32| {
+            let mut array = vec![0; TickArrayState::LEN];
+            put(&mut array, 8, pool.as_ref());
+            put(&mut array, 40, &start_tick_index.to_le_bytes());
+            for i in 0..TICK_ARRAY_SIZE as usize {
+                let tick = start_tick_index + i as i32 * i32::from(tick_spacing);
+                put(&mut array, 44 + 168 * i, &tick.to_le_bytes());
+            }
+            array
+        };
+        // A position from tick 0 up, and an empty crossing at 300 that small swaps stop
+        // short of
+        let mut array = tick_array(0);
+        put(&mut array, 48, &1_000_000_000i128.to_le_bytes());
+        put(&mut array, 64, &1_000_000_000u128.to_le_bytes());
+        put(&mut array, 44 + 168 * 30 + 20, &1u128.to_le_bytes());
+        let mut accounts = HashMap::from([
+            (pool, data_account(state)),
+            (quoter.amm_config, data_account(config)),
+            (tick_arrays[0], data_account(array)),
+        ]);
+
+        let quote = quoter
+            .quote_exact_in(&accounts, 1_000, SwapDirection::SolToToken)
+            .unwrap();
+        assert_eq!(quote.fee_amount, 3);
+        assert!(quote.amount_out > 0 && quote.amount_out < 997);
+
+        // Crossing out of the first array needs the second, and the third is no
+        // substitute for it. Each has an empty crossing for the swap to head for.
+        let crossing_at = |start_tick_index: i32| {
+            let mut array = tick_array(start_tick_index);
+            put(&mut array, 44 + 168 * 50 + 20, &1u128.to_le_bytes());
+            array[44 + 168 * TICK_ARRAY_SIZE as usize] = 1;
+            data_account(array)
+        };
+        accounts.insert(tick_arrays[2], crossing_at(1_200));
+        assert!(quoter
+            .quote_exact_in(&accounts, 40_000_000, SwapDirection::SolToToken)
+            .is_err());
+        accounts.insert(tick_arrays[1], crossing_at(600));
+        assert!(quoter
+            .quote_exact_in(&accounts, 40_000_000, SwapDirection::SolToToken)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_meteora_damm_quote_through_vaults() {
+        let quoter = MeteoraDAmmPool {
+            pool: Pubkey::new_unique(),
+            token_x_vault: Pubkey::new_unique(),
+            token_sol_vault: Pubkey::new_unique(),
+            token_x_token_vault: Pubkey::new_unique(),
+            token_sol_token_vault: Pubkey::new_unique(),
+            token_x_lp_mint: Pubkey::new_unique(),
+            token_sol_lp_mint: Pubkey::new_unique(),
+            token_x_pool_lp: Pubkey::new_unique(),
+            token_sol_pool_lp: Pubkey::new_unique(),
+            admin_token_fee_x: Pubkey::new_unique(),
+            admin_token_fee_sol: Pubkey::new_unique(),
+        };
+        let pool = meteora_damm_cpi::Pool {
+            lp_mint: Pubkey::new_unique(),
+            token_a_mint: sol_mint(),
+            token_b_mint: Pubkey::new_unique(),
+            a_vault: quoter.token_sol_vault,
+            b_vault: quoter.token_x_vault,
+            a_vault_lp: quoter.token_sol_pool_lp,
+            b_vault_lp: quoter.token_x_pool_lp,
+            a_vault_lp_bump: 0,
+            enabled: true,
+            admin_token_a_fee: quoter.admin_token_fee_sol,
+            admin_token_b_fee: quoter.admin_token_fee_x,
+            admin: Pubkey::new_unique(),
+            // 0.25%, a fifth of it to the protocol
+            fees: meteora_damm_cpi::PoolFees {
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10_000,
+                owner_trade_fee_numerator: 1,
+                owner_trade_fee_denominator: 5,
+            },
+            pool_type: meteora_damm_cpi::PoolType::Permissionless,
+            stake: Pubkey::default(),
+            padding: meteora_damm_cpi::Padding {
+                padding0: [0; 15],
+                padding: [0; 29],
+            },
+            curve_type: meteora_damm_cpi::CurveType::ConstantProduct,
+        };
+        // The pool holds every share of both vaults, one share per token
+        let vault = |total_amount: u64, token_vault: Pubkey| {
+            let vault = meteora_vault_cpi::Vault {
+                enabled: 1,
+                bumps: meteora_vault_cpi::VaultBumps {
+                    vault_bump: 0,
+                    token_vault_bump: 0,
+                },
+                total_amount,
+                token_vault,
+                fee_vault: Pubkey::default(),
+                token_mint: Pubkey::default(),
+                lp_mint: Pubkey::default(),
+                strategies: [Pubkey::default(); 30],
+                base: Pubkey::default(),
+                admin: Pubkey::default(),
+                operator: Pubkey::default(),
+                locked_profit_tracker: meteora_vault_cpi::LockedProfitTracker {
+                    last_updated_locked_profit: 0,
+                    last_report: 0,
+                    locked_profit_degradation: 0,
+                },
+            };
+            data_account([vec![0; 8], vault.try_to_vec().unwrap()].concat())
+        };
+        let mut accounts = vault_accounts(
+            quoter.token_x_token_vault,
+            quoter.token_sol_token_vault,
+            2_000_000_000,
+            1_000_000_000,
+        );
+        accounts.extend([
+            (
+                quoter.pool,
+                data_account([vec![0; 8], pool.try_to_vec().unwrap()].concat()),
+            ),
+            (clock::id(), clock_account(1, 1_000)),
+            (
+                quoter.token_x_vault,
+                vault(2_000_000_000, quoter.token_x_token_vault),
+            ),
+            (
+                quoter.token_sol_vault,
+                vault(1_000_000_000, quoter.token_sol_token_vault),
+            ),
+            (quoter.token_x_pool_lp, token_account(2_000_000_000)),
+            (quoter.token_sol_pool_lp, token_account(1_000_000_000)),
+            (quoter.token_x_lp_mint, mint_account(2_000_000_000)),
+            (quoter.token_sol_lp_mint, mint_account(1_000_000_000)),
+        ]);
+
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert_eq!(
+            (quote.amount_out, quote.fee_amount),
+            (SOL_TO_TOKEN_OUT, 2_500)
+        );
+    }
+
+    #[test]
+    fn test_meteora_damm_v2_quote_waits_for_activation() {
+        let quoter = MeteoraDAmmV2Pool {
+            pool: Pubkey::new_unique(),
+            token_x_vault: Pubkey::new_unique(),
+            token_sol_vault: Pubkey::new_unique(),
+        };
+        let one = 1u128 << 64;
+        let mut data = vec![0; meteora_damm2_cpi::Pool::LEN];
+        put(&mut data, 0, &meteora_damm2_cpi::POOL_DISCRIMINATOR);
+        // 0.25% flat fee
+        put(&mut data, 8, &2_500_000u64.to_le_bytes());
+        put(&mut data, 168, sol_mint().as_ref());
+        put(&mut data, 200, Pubkey::new_unique().as_ref());
+        put(&mut data, 360, &(1_000_000_000u128 << 64).to_le_bytes());
+        put(&mut data, 424, &(one / 2).to_le_bytes());
+        put(&mut data, 440, &(one * 2).to_le_bytes());
+        put(&mut data, 456, &one.to_le_bytes());
+        // Activates at timestamp 1000
+        put(&mut data, 472, &1_000u64.to_le_bytes());
+        data[480] = 1;
+        let mut accounts = HashMap::from([
+            (quoter.pool, data_account(data)),
+            (clock::id(), clock_account(1, 999)),
+        ]);
+        assert!(quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .is_err());
+
+        accounts.insert(clock::id(), clock_account(1, 1_000));
+        let quote = quoter
+            .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+            .unwrap();
+        assert!((2_490..=2_500).contains(&quote.fee_amount));
+        assert!(quote.amount_out > 996_000 && quote.amount_out < 997_500);
+    }
+
+    #[test]
+    fn test_vault_balance_quotes() {
+        let solfi = SolfiPool {
+            pool: Pubkey::new_unique(),
+            token_x_vault: Pubkey::new_unique(),
+            token_sol_vault: Pubkey::new_unique(),
+        };
+        let vertigo = VertigoPool {
+            pool: Pubkey::new_unique(),
+            pool_owner: Pubkey::new_unique(),
+            token_x_vault: solfi.token_x_vault,
+            token_sol_vault: solfi.token_sol_vault,
+        };
+        let accounts = vault_accounts(
+            solfi.token_x_vault,
+            solfi.token_sol_vault,
+            2_000_000_000,
+            1_000_000_000,
+        );
+        let expected = Quote {
+            amount_in: AMOUNT_IN,
+            amount_out: 1_992_013,
+            fee_amount: 3_000,
+        };
+
+        for quoter in [&solfi as &dyn PoolQuoter, &vertigo] {
+            let quote = quoter
+                .quote_exact_in(&accounts, AMOUNT_IN, SwapDirection::SolToToken)
+                .unwrap();
+            assert_eq!(quote, expected);
+            assert!(quoter
+                .quote_exact_in(&HashMap::new(), AMOUNT_IN, SwapDirection::SolToToken)
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// How long to poll before trying the subscription again
+pub const RECONNECT_AFTER: Duration = Duration::from_secs(10);
+
+/// Runs `subscribe` and, whenever it ends, `poll` for [`RECONNECT_AFTER`] before
+/// subscribing again. `poll` is expected to keep polling until it is dropped.
+pub async fn subscribe_or_poll<S, SF, P, PF>(name: &str, mut subscribe: S, mut poll: P)
+where
+    S: FnMut() -> SF,
+    SF: Future<Output = Result<()>>,
+    P: FnMut() -> PF,
+    PF: Future<Output = ()>,
+{
+    loop {
+        match subscribe().await {
+            Ok(()) => warn!("{} ended, polling instead", name),
+            Err(e) => warn!("{} failed, polling instead: {}", name, e),
+        }
+        let _ = tokio::time::timeout(RECONNECT_AFTER, poll()).await;
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::MintConfig;
 use crate::constants::sol_mint;
 use crate::dex::meteora::constants::{damm_program_id, damm_v2_program_id};
 use crate::dex::meteora::dammv2_info::MeteoraDAmmV2Info;
@@ -14,14 +15,22 @@ use crate::dex::whirlpool::{
     constants::whirlpool_program_id, state::Whirlpool, update_tick_array_accounts_for_onchain,
 };
 use crate::pools::*;
+use crate::quote::AccountMap;
+use crate::snapshot::SnapshotCell;
+use crate::state_cache::{StateCache, MAX_ACCOUNTS_PER_REQUEST};
+use futures::future::join_all;
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
 use spl_associated_token_account;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 use std::sync::Arc;
-use tracing::{error, info};
+use tokio::sync::broadcast;
+use tracing::{debug, error, info};
 
-pub async fn initialize_pool_data(
+/// Decodes the mint's pools from accounts fetched by [`fetch_pool_accounts`]
+pub fn initialize_pool_data(
     mint: &str,
     wallet_account: &str,
     raydium_pools: Option<&Vec<String>>,
@@ -34,13 +43,13 @@ pub async fn initialize_pool_data(
     solfi_pools: Option<&Vec<String>>,
     meteora_damm_v2_pools: Option<&Vec<String>>,
     vertigo_pools: Option<&Vec<String>>,
-    rpc_client: Arc<RpcClient>,
+    accounts: &AccountMap,
 ) -> anyhow::Result<MintPoolData> {
     info!("Initializing pool data for mint: {}", mint);
 
     // Fetch mint account to determine token program
     let mint_pubkey = Pubkey::from_str(mint)?;
-    let mint_account = rpc_client.get_account(&mint_pubkey)?;
+    let mint_account = fetched_account(accounts, &mint_pubkey)?;
 
     // Determine token program based on mint account owner
     let token_2022_program_id =
@@ -61,16 +70,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let pump_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&pump_pool_pubkey) {
+            match fetched_account(accounts, &pump_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != pump_program_id() {
                         error!(
-                            "Error: Pump pool account is not owned by the Pump program. Expected: {}, Actual: {}",
+                            "Error: Pump pool account is not owned by the Pump program, skipping. Expected: {}, Actual: {}",
                             pump_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Pump pool account is not owned by the Pump program"
-                        ));
+                        continue;
                     }
 
                     match PumpAmmInfo::load_checked(&account.data) {
@@ -130,19 +137,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing AmmInfo from Pump pool {}: {:?}",
+                                "Error parsing AmmInfo from Pump pool {}, skipping: {:?}",
                                 pump_pool_pubkey, e
                             );
-                            return Err(e);
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching Pump pool account {}: {:?}",
+                        "Error fetching Pump pool account {}, skipping: {:?}",
                         pump_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching Pump pool account"));
+                    continue;
                 }
             }
         }
@@ -152,16 +159,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let raydium_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&raydium_pool_pubkey) {
+            match fetched_account(accounts, &raydium_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != raydium_program_id() {
                         error!(
-                            "Error: Raydium pool account is not owned by the Raydium program. Expected: {}, Actual: {}",
+                            "Error: Raydium pool account is not owned by the Raydium program, skipping. Expected: {}, Actual: {}",
                             raydium_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Raydium pool account is not owned by the Raydium program"
-                        ));
+                        continue;
                     }
 
                     match RaydiumAmmInfo::load_checked(&account.data) {
@@ -173,21 +178,15 @@ pub async fn initialize_pool_data(
                                     "Mint {} is not present in Raydium pool {}, skipping",
                                     pool_data.mint, raydium_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "Invalid Raydium pool: {}",
-                                    raydium_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             if amm_info.coin_mint != sol_mint() && amm_info.pc_mint != sol_mint() {
                                 error!(
-                                    "SOL is not present in Raydium pool {}",
+                                    "SOL is not present in Raydium pool {}, skipping",
                                     raydium_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "SOL is not present in Raydium pool: {}",
-                                    raydium_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             let (sol_vault, token_vault) = if sol_mint() == amm_info.coin_mint {
@@ -210,19 +209,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing AmmInfo from Raydium pool {}: {:?}",
+                                "Error parsing AmmInfo from Raydium pool {}, skipping: {:?}",
                                 raydium_pool_pubkey, e
                             );
-                            return Err(e);
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching Raydium pool account {}: {:?}",
+                        "Error fetching Raydium pool account {}, skipping: {:?}",
                         raydium_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching Raydium pool account"));
+                    continue;
                 }
             }
         }
@@ -232,16 +231,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let raydium_cp_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&raydium_cp_pool_pubkey) {
+            match fetched_account(accounts, &raydium_cp_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != raydium_cp_program_id() {
                         error!(
-                            "Error: Raydium CP pool account is not owned by the Raydium CP program. Expected: {}, Actual: {}",
+                            "Error: Raydium CP pool account is not owned by the Raydium CP program, skipping. Expected: {}, Actual: {}",
                             raydium_cp_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Raydium CP pool account is not owned by the Raydium CP program"
-                        ));
+                        continue;
                     }
 
                     match RaydiumCpAmmInfo::load_checked(&account.data) {
@@ -253,10 +250,7 @@ pub async fn initialize_pool_data(
                                     "Mint {} is not present in Raydium CP pool {}, skipping",
                                     pool_data.mint, raydium_cp_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "Invalid Raydium CP pool: {}",
-                                    raydium_cp_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             let (sol_vault, token_vault) = if sol_mint() == amm_info.token_0_mint {
@@ -265,13 +259,10 @@ pub async fn initialize_pool_data(
                                 (amm_info.token_1_vault, amm_info.token_0_vault)
                             } else {
                                 error!(
-                                    "SOL is not present in Raydium CP pool {}",
+                                    "SOL is not present in Raydium CP pool {}, skipping",
                                     raydium_cp_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "SOL is not present in Raydium CP pool: {}",
-                                    raydium_cp_pool_pubkey
-                                ));
+                                continue;
                             };
 
                             pool_data.add_raydium_cp_pool(
@@ -292,19 +283,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing AmmInfo from Raydium CP pool {}: {:?}",
+                                "Error parsing AmmInfo from Raydium CP pool {}, skipping: {:?}",
                                 raydium_cp_pool_pubkey, e
                             );
-                            return Err(e);
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching Raydium CP pool account {}: {:?}",
+                        "Error fetching Raydium CP pool account {}, skipping: {:?}",
                         raydium_cp_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching Raydium CP pool account"));
+                    continue;
                 }
             }
         }
@@ -313,16 +304,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let dlmm_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&dlmm_pool_pubkey) {
+            match fetched_account(accounts, &dlmm_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != dlmm_program_id() {
                         error!(
-                            "Error: DLMM pool account is not owned by the DLMM program. Expected: {}, Actual: {}",
+                            "Error: DLMM pool account is not owned by the DLMM program, skipping. Expected: {}, Actual: {}",
                             dlmm_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "DLMM pool account is not owned by the DLMM program"
-                        ));
+                        continue;
                     }
 
                     match DlmmInfo::load_checked(&account.data) {
@@ -336,10 +325,10 @@ pub async fn initialize_pool_data(
                                 Ok(arrays) => arrays,
                                 Err(e) => {
                                     error!(
-                                        "Error calculating bin arrays for DLMM pool {}: {:?}",
+                                        "Error calculating bin arrays for DLMM pool {}, skipping: {:?}",
                                         dlmm_pool_pubkey, e
                                     );
-                                    return Err(e);
+                                    continue;
                                 }
                             };
 
@@ -372,19 +361,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing AmmInfo from DLMM pool {}: {:?}",
+                                "Error parsing AmmInfo from DLMM pool {}, skipping: {:?}",
                                 dlmm_pool_pubkey, e
                             );
-                            return Err(e);
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching DLMM pool account {}: {:?}",
+                        "Error fetching DLMM pool account {}, skipping: {:?}",
                         dlmm_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching DLMM pool account"));
+                    continue;
                 }
             }
         }
@@ -394,16 +383,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let whirlpool_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&whirlpool_pool_pubkey) {
+            match fetched_account(accounts, &whirlpool_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != whirlpool_program_id() {
                         error!(
-                            "Error: Whirlpool pool account is not owned by the Whirlpool program. Expected: {}, Actual: {}",
+                            "Error: Whirlpool pool account is not owned by the Whirlpool program, skipping. Expected: {}, Actual: {}",
                             whirlpool_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Whirlpool pool account is not owned by the Whirlpool program"
-                        ));
+                        continue;
                     }
 
                     match Whirlpool::try_deserialize(&account.data) {
@@ -415,10 +402,7 @@ pub async fn initialize_pool_data(
                                     "Mint {} is not present in Whirlpool pool {}, skipping",
                                     pool_data.mint, whirlpool_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "Invalid Whirlpool pool: {}",
-                                    whirlpool_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             let sol_mint = sol_mint();
@@ -428,13 +412,10 @@ pub async fn initialize_pool_data(
                                 (whirlpool.token_vault_b, whirlpool.token_vault_a)
                             } else {
                                 error!(
-                                    "SOL is not present in Whirlpool pool {}",
+                                    "SOL is not present in Whirlpool pool {}, skipping",
                                     whirlpool_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "SOL is not present in Whirlpool pool: {}",
-                                    whirlpool_pool_pubkey
-                                ));
+                                continue;
                             };
 
                             let whirlpool_oracle = Pubkey::find_program_address(
@@ -480,19 +461,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing Whirlpool data from pool {}: {:?}",
+                                "Error parsing Whirlpool data from pool {}, skipping: {:?}",
                                 whirlpool_pool_pubkey, e
                             );
-                            return Err(anyhow::anyhow!("Error parsing Whirlpool data"));
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching Whirlpool pool account {}: {:?}",
+                        "Error fetching Whirlpool pool account {}, skipping: {:?}",
                         whirlpool_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching Whirlpool pool account"));
+                    continue;
                 }
             }
         }
@@ -502,7 +483,7 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let raydium_clmm_program_id = raydium_clmm_program_id();
 
-            match rpc_client.get_account(&Pubkey::from_str(pool_address)?) {
+            match fetched_account(accounts, &Pubkey::from_str(pool_address)?) {
                 Ok(account) => {
                     if account.owner != raydium_clmm_program_id {
                         error!(
@@ -607,16 +588,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let meteora_damm_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&meteora_damm_pool_pubkey) {
+            match fetched_account(accounts, &meteora_damm_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != damm_program_id() {
                         error!(
                             "Error: Meteora DAMM pool account is not owned by the Meteora DAMM program. Expected: {}, Actual: {}",
                             damm_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Meteora DAMM pool account is not owned by the Meteora DAMM program"
-                        ));
+                        continue;
                     }
 
                     match meteora_damm_cpi::Pool::deserialize_unchecked(&account.data) {
@@ -628,22 +607,16 @@ pub async fn initialize_pool_data(
                                     "Mint {} is not present in Meteora DAMM pool {}, skipping",
                                     pool_data.mint, meteora_damm_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "Invalid Meteora DAMM pool: {}",
-                                    meteora_damm_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             let sol_mint = sol_mint();
                             if pool.token_a_mint != sol_mint && pool.token_b_mint != sol_mint {
                                 error!(
-                                    "SOL is not present in Meteora DAMM pool {}",
+                                    "SOL is not present in Meteora DAMM pool {}, skipping",
                                     meteora_damm_pool_pubkey
                                 );
-                                return Err(anyhow::anyhow!(
-                                    "SOL is not present in Meteora DAMM pool: {}",
-                                    meteora_damm_pool_pubkey
-                                ));
+                                continue;
                             }
 
                             let (x_vault, sol_vault) = if sol_mint == pool.token_a_mint {
@@ -653,8 +626,8 @@ pub async fn initialize_pool_data(
                             };
 
                             // Fetch vault accounts
-                            let x_vault_data = rpc_client.get_account(&x_vault)?;
-                            let sol_vault_data = rpc_client.get_account(&sol_vault)?;
+                            let x_vault_data = fetched_account(accounts, &x_vault)?;
+                            let sol_vault_data = fetched_account(accounts, &sol_vault)?;
 
                             let x_vault_obj = meteora_vault_cpi::Vault::deserialize_unchecked(
                                 &mut x_vault_data.data.as_slice(),
@@ -707,19 +680,19 @@ pub async fn initialize_pool_data(
                         }
                         Err(e) => {
                             error!(
-                                "Error parsing Meteora DAMM pool data from pool {}: {:?}",
+                                "Error parsing Meteora DAMM pool data from pool {}, skipping: {:?}",
                                 meteora_damm_pool_pubkey, e
                             );
-                            return Err(anyhow::anyhow!("Error parsing Meteora DAMM pool data"));
+                            continue;
                         }
                     }
                 }
                 Err(e) => {
                     error!(
-                        "Error fetching Meteora DAMM pool account {}: {:?}",
+                        "Error fetching Meteora DAMM pool account {}, skipping: {:?}",
                         meteora_damm_pool_pubkey, e
                     );
-                    return Err(anyhow::anyhow!("Error fetching Meteora DAMM pool account"));
+                    continue;
                 }
             }
         }
@@ -729,7 +702,7 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let meteora_damm_v2_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&meteora_damm_v2_pool_pubkey) {
+            match fetched_account(accounts, &meteora_damm_v2_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != damm_v2_program_id() {
                         error!("Meteora DAMM V2 pool {} is not owned by the Meteora DAMM V2 program, skipping", pool_address);
@@ -740,32 +713,32 @@ pub async fn initialize_pool_data(
                         Ok(meteora_damm_v2_info) => {
                             info!("Meteora DAMM V2 pool added: {}", pool_address);
                             info!(
-                                "    Base mint: {}",
-                                meteora_damm_v2_info.base_mint.to_string()
+                                "    Token A mint: {}",
+                                meteora_damm_v2_info.token_a_mint.to_string()
                             );
                             info!(
-                                "    Quote mint: {}",
-                                meteora_damm_v2_info.quote_mint.to_string()
+                                "    Token B mint: {}",
+                                meteora_damm_v2_info.token_b_mint.to_string()
                             );
                             info!(
-                                "    Base vault: {}",
-                                meteora_damm_v2_info.base_vault.to_string()
+                                "    Token A vault: {}",
+                                meteora_damm_v2_info.token_a_vault.to_string()
                             );
                             info!(
-                                "    Quote vault: {}",
-                                meteora_damm_v2_info.quote_vault.to_string()
+                                "    Token B vault: {}",
+                                meteora_damm_v2_info.token_b_vault.to_string()
                             );
                             info!("");
-                            let token_x_vault = if sol_mint() == meteora_damm_v2_info.base_mint {
-                                meteora_damm_v2_info.quote_vault
+                            let token_x_vault = if sol_mint() == meteora_damm_v2_info.token_a_mint {
+                                meteora_damm_v2_info.token_b_vault
                             } else {
-                                meteora_damm_v2_info.base_vault
+                                meteora_damm_v2_info.token_a_vault
                             };
 
-                            let token_sol_vault = if sol_mint() == meteora_damm_v2_info.base_mint {
-                                meteora_damm_v2_info.base_vault
+                            let token_sol_vault = if sol_mint() == meteora_damm_v2_info.token_a_mint {
+                                meteora_damm_v2_info.token_a_vault
                             } else {
-                                meteora_damm_v2_info.quote_vault
+                                meteora_damm_v2_info.token_b_vault
                             };
                             pool_data.add_meteora_damm_v2_pool(
                                 pool_address,
@@ -797,7 +770,7 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let solfi_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&solfi_pool_pubkey) {
+            match fetched_account(accounts, &solfi_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != solfi_program_id() {
                         error!(
@@ -857,16 +830,14 @@ pub async fn initialize_pool_data(
         for pool_address in pools {
             let vertigo_pool_pubkey = Pubkey::from_str(pool_address)?;
 
-            match rpc_client.get_account(&vertigo_pool_pubkey) {
+            match fetched_account(accounts, &vertigo_pool_pubkey) {
                 Ok(account) => {
                     if account.owner != vertigo_program_id() {
                         error!(
                             "Error: Vertigo pool account is not owned by the Vertigo program. Expected: {}, Actual: {}",
                             vertigo_program_id(), account.owner
                         );
-                        return Err(anyhow::anyhow!(
-                            "Vertigo pool account is not owned by the Vertigo program"
-                        ));
+                        continue;
                     }
 
                     match VertigoInfo::load_checked(&account.data, &vertigo_pool_pubkey) {
@@ -927,3 +898,319 @@ pub async fn initialize_pool_data(
 
     Ok(pool_data)
 }
+
+fn fetched_account<'a>(accounts: &'a AccountMap, key: &Pubkey) -> anyhow::Result<&'a Account> {
+    accounts
+        .get(key)
+        .ok_or_else(|| anyhow::anyhow!("Account {} not found", key))
+}
+
+/// Fetches the mint and pool accounts of every mint, plus the vaults Meteora DAMM pools
+/// are decoded with. Accounts that don't exist or failed to load are left out. Pools
+/// missing from the result are skipped, mints missing from it are not set up.
+pub async fn fetch_pool_accounts(
+    rpc_client: &Arc<RpcClient>,
+    mint_configs: &[MintConfig],
+) -> AccountMap {
+    let mut keys = BTreeSet::new();
+    for mint_config in mint_configs {
+        let pool_lists = [
+            &mint_config.raydium_pool_list,
+            &mint_config.raydium_cp_pool_list,
+            &mint_config.raydium_clmm_pool_list,
+            &mint_config.meteora_dlmm_pool_list,
+            &mint_config.meteora_damm_pool_list,
+            &mint_config.meteora_damm_v2_pool_list,
+            &mint_config.pump_pool_list,
+            &mint_config.whirlpool_pool_list,
+            &mint_config.solfi_pool_list,
+            &mint_config.vertigo_pool_list,
+        ];
+        let addresses = pool_lists.into_iter().flatten().flatten();
+        keys.extend(
+            std::iter::once(&mint_config.mint)
+                .chain(addresses)
+                .filter_map(|address| Pubkey::from_str(address).ok()),
+        );
+    }
+    let mut accounts = fetch_accounts(rpc_client, keys.into_iter().collect()).await;
+
+    let damm_vaults: BTreeSet<Pubkey> = mint_configs
+        .iter()
+        .flat_map(|mint_config| mint_config.meteora_damm_pool_list.iter().flatten())
+        .filter_map(|address| accounts.get(&Pubkey::from_str(address).ok()?))
+        .filter(|account| account.owner == damm_program_id())
+        .filter_map(|account| meteora_damm_cpi::Pool::deserialize_unchecked(&account.data).ok())
+        .flat_map(|pool| [pool.a_vault, pool.b_vault])
+        .collect();
+    let vault_accounts = fetch_accounts(rpc_client, damm_vaults.into_iter().collect()).await;
+    accounts.extend(vault_accounts);
+    info!("Fetched {} pool accounts", accounts.len());
+    accounts
+}
+
+/// `getMultipleAccounts` for `keys`, with every request of up to
+/// `MAX_ACCOUNTS_PER_REQUEST` accounts in flight at once
+async fn fetch_accounts(rpc_client: &Arc<RpcClient>, keys: Vec<Pubkey>) -> AccountMap {
+    let requests = keys.chunks(MAX_ACCOUNTS_PER_REQUEST).map(|chunk| {
+        let rpc_client = rpc_client.clone();
+        let chunk = chunk.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let accounts = rpc_client.get_multiple_accounts(&chunk);
+            (chunk, accounts)
+        })
+    });
+    let mut fetched = AccountMap::new();
+    for request in join_all(requests).await {
+        match request {
+            Ok((chunk, Ok(accounts))) => fetched.extend(
+                chunk
+                    .into_iter()
+                    .zip(accounts)
+                    .filter_map(|(key, account)| Some((key, account?))),
+            ),
+            Ok((chunk, Err(e))) => error!("Failed to fetch {} accounts: {}", chunk.len(), e),
+            Err(e) => error!("Account fetch task failed: {}", e),
+        }
+    }
+    fetched
+}
+
+impl MintPoolData {
+    /// Whether `pool` is a concentrated liquidity pool of the mint, with tick or bin arrays
+    /// that follow its price
+    pub fn has_swap_arrays(&self, pool: &Pubkey) -> bool {
+        self.raydium_clmm_pools.iter().any(|p| &p.pool == pool)
+            || self.dlmm_pairs.iter().any(|p| &p.pair == pool)
+            || self.whirlpool_pools.iter().any(|p| &p.pool == pool)
+    }
+
+    /// Re-derives the tick or bin arrays around the current price of `pool`, or of every
+    /// concentrated liquidity pool when None, from their cached state. Returns whether any
+    /// changed.
+    pub fn refresh_swap_arrays(&mut self, cache: &StateCache, pool: Option<&Pubkey>) -> bool {
+        let cached = |address: &Pubkey, owner: Pubkey| {
+            if pool.is_some_and(|pool| pool != address) {
+                return None;
+            }
+            cache
+                .get(address)
+                .filter(|cached| cached.account.owner == owner)
+        };
+        let mut changed = false;
+
+        for clmm_pool in self.raydium_clmm_pools.iter_mut() {
+            let Some(cached) = cached(&clmm_pool.pool, raydium_clmm_program_id()) else {
+                continue;
+            };
+            let tick_arrays = PoolState::load_checked(&cached.account.data).and_then(|state| {
+                get_tick_array_pubkeys(
+                    &clmm_pool.pool,
+                    state.tick_current,
+                    state.tick_spacing,
+                    &[-1, 0, 1],
+                    &raydium_clmm_program_id(),
+                )
+            });
+            match tick_arrays {
+                Ok(tick_arrays) if tick_arrays != clmm_pool.tick_arrays => {
+                    debug!("Raydium CLMM pool {} moved tick arrays", clmm_pool.pool);
+                    clmm_pool.tick_arrays = tick_arrays;
+                    changed = true;
+                }
+                Ok(_) => {}
+                Err(e) => error!(
+                    "Failed to load Raydium CLMM pool {}: {:?}",
+                    clmm_pool.pool, e
+                ),
+            }
+        }
+
+        for dlmm_pool in self.dlmm_pairs.iter_mut() {
+            let Some(cached) = cached(&dlmm_pool.pair, dlmm_program_id()) else {
+                continue;
+            };
+            match DlmmInfo::load_checked(&cached.account.data) {
+                Ok(dlmm_info) => {
+                    let bin_arrays = dlmm_info
+                        .calculate_bin_arrays(&dlmm_pool.pair)
+                        .unwrap_or_default();
+                    if bin_arrays != dlmm_pool.bin_arrays {
+                        debug!("Meteora DLMM pool {} moved bin arrays", dlmm_pool.pair);
+                        dlmm_pool.bin_arrays = bin_arrays;
+                        changed = true;
+                    }
+                }
+                Err(e) => error!("Failed to load DLMM pool {}: {:?}", dlmm_pool.pair, e),
+            }
+        }
+
+        for whirlpool_pool in self.whirlpool_pools.iter_mut() {
+            let Some(cached) = cached(&whirlpool_pool.pool, whirlpool_program_id()) else {
+                continue;
+            };
+            match Whirlpool::try_deserialize(&cached.account.data) {
+                Ok(whirlpool) => {
+                    let tick_arrays: Vec<Pubkey> = update_tick_array_accounts_for_onchain(
+                        &whirlpool,
+                        &whirlpool_pool.pool,
+                        &whirlpool_program_id(),
+                    )
+                    .iter()
+                    .map(|meta| meta.pubkey)
+                    .collect();
+                    if tick_arrays != whirlpool_pool.tick_arrays {
+                        debug!("Whirlpool {} moved tick arrays", whirlpool_pool.pool);
+                        whirlpool_pool.tick_arrays = tick_arrays;
+                        changed = true;
+                    }
+                }
+                Err(e) => error!(
+                    "Failed to load Whirlpool pool {}: {:?}",
+                    whirlpool_pool.pool, e
+                ),
+            }
+        }
+
+        changed
+    }
+}
+
+/// Keeps the mint's swap arrays current as its pools move, publishing a new snapshot
+/// whenever they do, and the cache watching every account its quotes read
+pub async fn pool_state_refresher(
+    cache: Arc<StateCache>,
+    mint_pool_data: Arc<SnapshotCell<MintPoolData>>,
+) {
+    let mut updates = cache.subscribe();
+    mint_pool_data.update(|current| {
+        let mut next = current.clone();
+        next.refresh_swap_arrays(&cache, None).then_some(next)
+    });
+    cache.watch(mint_pool_data.load().quote_accounts());
+    loop {
+        let pool = match updates.recv().await {
+            Ok(pool) => Some(pool),
+            // Some updates were missed, so check every pool
+            Err(broadcast::error::RecvError::Lagged(_)) => None,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if pool.is_some_and(|pool| !mint_pool_data.load().has_swap_arrays(&pool)) {
+            continue;
+        }
+        let version = mint_pool_data.update(|current| {
+            let mut next = current.clone();
+            next.refresh_swap_arrays(&cache, pool.as_ref()).then_some(next)
+        });
+        if let Some(version) = version {
+            let pool_data = mint_pool_data.load();
+            debug!(
+                "Published pool snapshot {} for mint {}",
+                version, pool_data.mint
+            );
+            cache.watch(pool_data.quote_accounts());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_stand_in;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fetches_accounts_in_chunks() {
+        let keys: Vec<Pubkey> = (0..250).map(|_| Pubkey::new_unique()).collect();
+        let missing = keys[120];
+        let mut node = rpc_stand_in(Duration::ZERO, move |request| {
+            match request["method"].as_str().unwrap() {
+                "getMultipleAccounts" => {
+                    let value: Vec<Value> = request["params"][0]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|key| {
+                            if key.as_str() == Some(&missing.to_string()) {
+                                return Value::Null;
+                            }
+                            json!({
+                                "data": ["", "base64"],
+                                "executable": false,
+                                "lamports": 1_000_000,
+                                "owner": spl_token::ID.to_string(),
+                                "rentEpoch": 0,
+                            })
+                        })
+                        .collect();
+                    json!({ "context": { "slot": 1 }, "value": value })
+                }
+                method => panic!("Unexpected {}", method),
+            }
+        })
+        .await;
+
+        let rpc_client = Arc::new(RpcClient::new(node.url.clone()));
+        let fetched = fetch_accounts(&rpc_client, keys.clone()).await;
+        assert_eq!(fetched.len(), 249);
+        assert!(!fetched.contains_key(&missing));
+
+        let mut sizes = vec![];
+        while let Ok(request) = node.requests.try_recv() {
+            if request["method"] == "getMultipleAccounts" {
+                sizes.push(request["params"][0].as_array().unwrap().len());
+            }
+        }
+        sizes.sort();
+        assert_eq!(sizes, [50, 100, 100]);
+    }
+
+    #[test]
+    fn test_skips_missing_and_undecodable_pools() {
+        let mint = Pubkey::new_unique();
+        let wallet = Pubkey::new_unique().to_string();
+        let (missing, undecodable, foreign) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let owned_by = |owner| Account {
+            data: vec![1; 64],
+            owner,
+            ..Account::default()
+        };
+        let mut accounts = AccountMap::from([
+            (undecodable, owned_by(raydium_program_id())),
+            (foreign, owned_by(spl_token::ID)),
+        ]);
+        let raydium_pools = vec![missing.to_string(), undecodable.to_string()];
+        let dlmm_pools = vec![foreign.to_string()];
+        let initialize = |accounts: &AccountMap| {
+            initialize_pool_data(
+                &mint.to_string(),
+                &wallet,
+                Some(&raydium_pools),
+                None,
+                None,
+                Some(&dlmm_pools),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                accounts,
+            )
+        };
+
+        // Without its mint account there is nothing to set up
+        assert!(initialize(&accounts).is_err());
+
+        accounts.insert(mint, owned_by(spl_token::ID));
+        let pool_data = initialize(&accounts).unwrap();
+        assert_eq!(pool_data.token_program, spl_token::ID);
+        assert!(pool_data.raydium_pools.is_empty());
+        assert!(pool_data.dlmm_pairs.is_empty());
+    }
+}
@@ -0,0 +1,622 @@
+use crate::clock::websocket_url;
+use crate::config::{Config, SenderKind};
+use crate::jito::{BundleSender, JitoClient, DEFAULT_TIP_ACCOUNTS};
+use crate::tpu::{TpuSender, DEFAULT_LEADERS};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::{join_all, BoxFuture};
+use rand::Rng;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+pub const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_millis(2_000);
+
+pub const DEFAULT_MAX_RETRIES: usize = 3;
+
+/// Consecutive failures after which a sender is rested
+const UNHEALTHY_AFTER_FAILURES: u32 = 3;
+
+/// How long a rested sender is left out of selection
+const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Outcome of sending one transaction to one endpoint
+#[derive(Debug)]
+pub struct SendReport {
+    pub endpoint: String,
+    pub latency: Duration,
+    pub result: Result<Signature>,
+}
+
+/// A way of getting signed transactions to the leaders
+pub trait TransactionSender: Send + Sync {
+    /// Name the sender is logged and tracked under
+    fn name(&self) -> &str;
+
+    /// Smallest tip the sender forwards transactions for, None when it takes no tips
+    fn min_tip_lamports(&self) -> Option<u64> {
+        None
+    }
+
+    /// Transfer of `lamports` from `payer` to the sender, for senders that take tips.
+    /// Goes last in the transaction.
+    fn tip_instruction(&self, _payer: &Pubkey, _lamports: u64) -> Option<Instruction> {
+        None
+    }
+
+    /// Sets up connections ahead of the first send
+    fn warm_up(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    fn send<'a>(&'a self, tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>>;
+}
+
+/// Plain `sendTransaction` to an RPC node
+pub struct RpcSender {
+    name: String,
+    client: RpcClient,
+    timeout: Duration,
+    max_retries: usize,
+}
+
+impl RpcSender {
+    pub fn new(name: String, url: String, timeout: Duration, max_retries: usize) -> Self {
+        Self {
+            name,
+            client: RpcClient::new_with_timeout(url, timeout),
+            timeout,
+            max_retries,
+        }
+    }
+}
+
+impl TransactionSender for RpcSender {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The client looks up the node's version before its first send. Doing it up front
+    /// keeps that round trip, and the connection setup, off the first transaction.
+    fn warm_up(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            match tokio::time::timeout(self.timeout, self.client.get_version()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => warn!("Failed to reach {}: {}", self.name, e),
+                Err(_) => warn!("Timed out reaching {}", self.name),
+            }
+        })
+    }
+
+    fn send<'a>(&'a self, tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move {
+            let config = RpcSendTransactionConfig {
+                skip_preflight: true,
+                max_retries: Some(self.max_retries),
+                preflight_commitment: Some(CommitmentLevel::Confirmed),
+                encoding: Some(UiTransactionEncoding::Base64),
+                ..Default::default()
+            };
+            Ok(self.client.send_transaction_with_config(tx, config).await?)
+        })
+    }
+}
+
+/// Accounts a sender takes tips on
+pub struct TipAccounts {
+    accounts: Vec<Pubkey>,
+    next: AtomicUsize,
+}
+
+impl TipAccounts {
+    pub fn new(accounts: Vec<Pubkey>) -> Result<Self> {
+        if accounts.is_empty() {
+            bail!("At least one tip account is required");
+        }
+        Ok(Self {
+            accounts,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Tip accounts are rotated so consecutive transactions don't contend on one account
+    pub fn next(&self) -> Pubkey {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.accounts[index % self.accounts.len()]
+    }
+
+    /// Transfer of `lamports` from `payer` to the next tip account
+    pub fn transfer(&self, payer: &Pubkey, lamports: u64) -> Instruction {
+        system_instruction::transfer(payer, &self.next(), lamports)
+    }
+}
+
+/// HTTP service that forwards transactions over staked connections for a tip. It takes
+/// the same `sendTransaction` request as an RPC node.
+pub struct StakedSender {
+    name: String,
+    http: reqwest::Client,
+    url: String,
+    tip_accounts: TipAccounts,
+    tip_lamports: u64,
+}
+
+impl StakedSender {
+    pub fn new(
+        name: String,
+        url: String,
+        tip_accounts: Vec<Pubkey>,
+        tip_lamports: u64,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
+            tip_accounts: TipAccounts::new(tip_accounts)
+                .with_context(|| format!("{} has no tip accounts", name))?,
+            name,
+            http: reqwest::Client::builder().timeout(timeout).build()?,
+            url,
+            tip_lamports,
+        })
+    }
+}
+
+impl TransactionSender for StakedSender {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn min_tip_lamports(&self) -> Option<u64> {
+        Some(self.tip_lamports)
+    }
+
+    fn tip_instruction(&self, payer: &Pubkey, lamports: u64) -> Option<Instruction> {
+        Some(self.tip_accounts.transfer(payer, lamports))
+    }
+
+    fn send<'a>(&'a self, tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "sendTransaction",
+                "params": [
+                    STANDARD.encode(bincode::serialize(tx)?),
+                    { "encoding": "base64", "skipPreflight": true, "maxRetries": 0 },
+                ],
+            });
+            let response: Value = self
+                .http
+                .post(&self.url)
+                .json(&request)
+                .send()
+                .await?
+                .json()
+                .await?;
+            if let Some(error) = response.get("error") {
+                bail!("sendTransaction failed: {}", error);
+            }
+            let signature = response
+                .get("result")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("sendTransaction returned no signature"))?;
+            Ok(Signature::from_str(signature)?)
+        })
+    }
+}
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    resting_until: Option<Instant>,
+}
+
+/// Token bucket allowing `per_second` sends, in bursts of up to a second's worth
+struct RateLimiter {
+    per_second: f64,
+    /// Tokens available, and when they were counted
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(per_second: f64) -> Self {
+        Self {
+            per_second,
+            bucket: Mutex::new((per_second.max(1.0), Instant::now())),
+        }
+    }
+
+    fn try_acquire(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let (tokens, counted_at) = *bucket;
+        let elapsed = now.saturating_duration_since(counted_at).as_secs_f64();
+        let tokens = (tokens + elapsed * self.per_second).min(self.per_second.max(1.0));
+        if tokens >= 1.0 {
+            *bucket = (tokens - 1.0, now);
+            true
+        } else {
+            *bucket = (tokens, now);
+            false
+        }
+    }
+}
+
+struct Route {
+    sender: Arc<dyn TransactionSender>,
+    weight: u32,
+    rate_limiter: Option<RateLimiter>,
+    health: Mutex<Health>,
+}
+
+impl Route {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.health
+            .lock()
+            .unwrap()
+            .resting_until
+            .is_none_or(|until| now >= until)
+    }
+
+    fn record(&self, ok: bool, now: Instant) {
+        let mut health = self.health.lock().unwrap();
+        if ok {
+            *health = Health::default();
+            return;
+        }
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= UNHEALTHY_AFTER_FAILURES {
+            warn!(
+                "{} failed {} times in a row, resting it for {:?}",
+                self.sender.name(),
+                health.consecutive_failures,
+                UNHEALTHY_COOLDOWN
+            );
+            *health = Health {
+                consecutive_failures: 0,
+                resting_until: Some(now + UNHEALTHY_COOLDOWN),
+            };
+        }
+    }
+}
+
+/// Picks the senders for each transaction by weight, among the healthy ones under their
+/// rate limit, and sends to them concurrently so a slow sender never delays the others
+pub struct SenderRouter {
+    routes: Vec<Route>,
+    /// Senders per transaction, every eligible one when unset
+    fanout: Option<usize>,
+    timeout: Duration,
+}
+
+impl SenderRouter {
+    pub fn new(fanout: Option<usize>, timeout: Duration) -> Self {
+        Self {
+            routes: Vec::new(),
+            fanout,
+            timeout,
+        }
+    }
+
+    /// Senders from `[sending]`. Without that section transactions go through the legacy
+    /// `spam.sending_rpc_urls` when spam is enabled, or else through the `[rpc]` node.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let Some(sending) = &config.sending else {
+            let spam = config.spam.as_ref();
+            let timeout = spam
+                .and_then(|s| s.send_timeout_ms)
+                .map_or(DEFAULT_SEND_TIMEOUT, Duration::from_millis);
+            let max_retries = spam
+                .and_then(|s| s.max_retries)
+                .map_or(DEFAULT_MAX_RETRIES, |retries| retries as usize);
+            let urls = match spam {
+                Some(spam) if spam.enabled && !spam.sending_rpc_urls.is_empty() => {
+                    warn!("spam.sending_rpc_urls is deprecated, use [[sending.senders]]");
+                    spam.sending_rpc_urls.clone()
+                }
+                _ => vec![config.rpc.url.clone()],
+            };
+            return Ok(urls
+                .into_iter()
+                .fold(Self::new(None, timeout), |router, url| {
+                    let sender = RpcSender::new(url.clone(), url, timeout, max_retries);
+                    router.route(Arc::new(sender), 1, None)
+                }));
+        };
+
+        let timeout = sending
+            .timeout_ms
+            .map_or(DEFAULT_SEND_TIMEOUT, Duration::from_millis);
+        let mut router = Self::new(sending.fanout, timeout);
+        for sender_config in &sending.senders {
+            let sender: Arc<dyn TransactionSender> = match &sender_config.kind {
+                SenderKind::Rpc { url, max_retries } => Arc::new(RpcSender::new(
+                    sender_config.name.clone().unwrap_or_else(|| url.clone()),
+                    url.clone(),
+                    timeout,
+                    max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+                )),
+                SenderKind::Bundle {
+                    url,
+                    tip_lamports,
+                    tip_accounts,
+                } => {
+                    let tip_accounts = match tip_accounts {
+                        Some(tip_accounts) => parse_pubkeys(tip_accounts)?,
+                        None => parse_pubkeys(&DEFAULT_TIP_ACCOUNTS)?,
+                    };
+                    let client = JitoClient::new(url, tip_accounts, *tip_lamports)
+                        .with_context(|| format!("Failed to set up bundle sender {}", url))?;
+                    Arc::new(BundleSender::new(
+                        sender_config.name.clone().unwrap_or_else(|| url.clone()),
+                        Arc::new(client),
+                    ))
+                }
+                SenderKind::Staked {
+                    url,
+                    tip_lamports,
+                    tip_accounts,
+                } => Arc::new(
+                    StakedSender::new(
+                        sender_config.name.clone().unwrap_or_else(|| url.clone()),
+                        url.clone(),
+                        parse_pubkeys(tip_accounts)?,
+                        *tip_lamports,
+                        timeout,
+                    )
+                    .with_context(|| format!("Failed to set up staked sender {}", url))?,
+                ),
+                SenderKind::Tpu { leaders } => {
+                    let ws_url = config
+                        .rpc
+                        .ws_url
+                        .clone()
+                        .unwrap_or_else(|| websocket_url(&config.rpc.url));
+                    let tpu = TpuSender::new(
+                        sender_config
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| "tpu".to_string()),
+                        &config.rpc.url,
+                        &ws_url,
+                        leaders.unwrap_or(DEFAULT_LEADERS),
+                    )
+                    .await
+                    .context("Failed to set up TPU sender")?;
+                    Arc::new(tpu)
+                }
+            };
+            router = router.route(
+                sender,
+                sender_config.weight.unwrap_or(1),
+                sender_config.max_per_second,
+            );
+        }
+        if router.routes.is_empty() {
+            bail!("[sending] needs at least one sender");
+        }
+        Ok(router)
+    }
+
+    /// Adds a sender, picked in proportion to `weight` and at most `max_per_second` times
+    /// a second
+    pub fn route(
+        mut self,
+        sender: Arc<dyn TransactionSender>,
+        weight: u32,
+        max_per_second: Option<f64>,
+    ) -> Self {
+        self.routes.push(Route {
+            sender,
+            weight,
+            rate_limiter: max_per_second.map(RateLimiter::new),
+            health: Mutex::new(Health::default()),
+        });
+        self
+    }
+
+    pub fn sender(&self, route: usize) -> &dyn TransactionSender {
+        self.routes[route].sender.as_ref()
+    }
+
+    /// Smallest tip any sender asks for, None when none of them take tips
+    pub fn min_tip_lamports(&self) -> Option<u64> {
+        self.routes
+            .iter()
+            .filter_map(|route| route.sender.min_tip_lamports())
+            .min()
+    }
+
+    pub async fn warm_up(&self) {
+        join_all(self.routes.iter().map(|route| route.sender.warm_up())).await;
+    }
+
+    /// Routes for the next transaction, drawn by weight without replacement. Resting
+    /// senders are skipped unless every sender is resting.
+    pub fn select(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut rng = rand::thread_rng();
+        // Ordering by u^(1/weight) draws each sender in proportion to its weight
+        let mut order: Vec<(f64, usize)> = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter(|(_, route)| route.weight > 0)
+            .map(|(index, route)| {
+                let key = rng.gen::<f64>().powf(1.0 / f64::from(route.weight));
+                (key, index)
+            })
+            .collect();
+        order.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let any_healthy = order
+            .iter()
+            .any(|(_, index)| self.routes[*index].is_healthy(now));
+        let fanout = self.fanout.unwrap_or(self.routes.len());
+        let mut selected = Vec::with_capacity(fanout);
+        for (_, index) in order {
+            if selected.len() >= fanout {
+                break;
+            }
+            let route = &self.routes[index];
+            if any_healthy && !route.is_healthy(now) {
+                continue;
+            }
+            if route
+                .rate_limiter
+                .as_ref()
+                .is_some_and(|rate_limiter| !rate_limiter.try_acquire(now))
+            {
+                continue;
+            }
+            selected.push(index);
+        }
+        selected
+    }
+
+    /// Sends each transaction through its route, all at once. Returns one report per
+    /// transaction, in order, once every sender has answered or timed out.
+    pub async fn send(&self, transactions: &[(usize, VersionedTransaction)]) -> Vec<SendReport> {
+        let requests = transactions.iter().map(|(index, tx)| async move {
+            let route = &self.routes[*index];
+            let started = Instant::now();
+            let result = match tokio::time::timeout(self.timeout, route.sender.send(tx)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!("Timed out after {:?}", self.timeout)),
+            };
+            route.record(result.is_ok(), Instant::now());
+            SendReport {
+                endpoint: route.sender.name().to_string(),
+                latency: started.elapsed(),
+                result,
+            }
+        });
+        join_all(requests).await
+    }
+}
+
+fn parse_pubkeys<S: AsRef<str>>(pubkeys: &[S]) -> Result<Vec<Pubkey>> {
+    pubkeys
+        .iter()
+        .map(|pubkey| {
+            Pubkey::from_str(pubkey.as_ref())
+                .with_context(|| format!("Invalid tip account {}", pubkey.as_ref()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rpc_stand_in, signed_transaction};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+
+    /// Answers like a node: echoes the transaction's signature
+    fn node(request: &Value) -> Value {
+        match request["method"].as_str().unwrap() {
+            "sendTransaction" => {
+                let bytes = STANDARD
+                    .decode(request["params"][0].as_str().unwrap())
+                    .unwrap();
+                let tx: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                json!(tx.signatures[0].to_string())
+            }
+            method => panic!("Unexpected {}", method),
+        }
+    }
+
+    fn transfer() -> VersionedTransaction {
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        signed_transaction(&payer, &[transfer], Hash::default())
+    }
+
+    #[tokio::test]
+    async fn test_slow_endpoint_does_not_delay_others() {
+        let fast = rpc_stand_in(Duration::ZERO, node).await;
+        let slow = rpc_stand_in(Duration::from_millis(600), node).await;
+        let timeout = Duration::from_millis(300);
+        let staked = StakedSender::new(
+            fast.url.clone(),
+            fast.url.clone(),
+            vec![Pubkey::new_unique()],
+            1_000,
+            timeout,
+        )
+        .unwrap();
+        let router = SenderRouter::new(None, timeout)
+            .route(
+                Arc::new(RpcSender::new(
+                    slow.url.clone(),
+                    slow.url.clone(),
+                    timeout,
+                    0,
+                )),
+                1,
+                None,
+            )
+            .route(Arc::new(staked), 1, None);
+        assert_eq!(router.min_tip_lamports(), Some(1_000));
+
+        let tx = transfer();
+        let started = Instant::now();
+        let reports = router.send(&[(0, tx.clone()), (1, tx.clone())]).await;
+        assert!(started.elapsed() < Duration::from_millis(550));
+
+        assert_eq!(reports[0].endpoint, slow.url);
+        assert!(reports[0].result.is_err());
+        assert!(reports[0].latency >= Duration::from_millis(300));
+
+        assert_eq!(reports[1].endpoint, fast.url);
+        assert_eq!(*reports[1].result.as_ref().unwrap(), tx.signatures[0]);
+        assert!(reports[1].latency < Duration::from_millis(300));
+    }
+
+    struct Unavailable(&'static str);
+
+    impl TransactionSender for Unavailable {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn send<'a>(&'a self, _tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>> {
+            Box::pin(async { Err(anyhow!("Unavailable")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_selection_follows_weights_health_and_rate_limits() {
+        let router = SenderRouter::new(Some(1), DEFAULT_SEND_TIMEOUT)
+            .route(Arc::new(Unavailable("heavy")), 9, None)
+            .route(Arc::new(Unavailable("light")), 1, None)
+            .route(Arc::new(Unavailable("off")), 0, None)
+            .route(Arc::new(Unavailable("limited")), 1_000, Some(0.01));
+
+        // The rate limited sender wins nearly every draw, but only gets its one send
+        let picks: Vec<usize> = (0..1_000).map(|_| router.select()[0]).collect();
+        assert_eq!(picks.iter().filter(|index| **index == 3).count(), 1);
+        assert!(!picks.contains(&2));
+        let heavy = picks.iter().filter(|index| **index == 0).count();
+        let light = picks.iter().filter(|index| **index == 1).count();
+        assert!(heavy > 4 * light, "{} vs {}", heavy, light);
+
+        // Repeated failures rest a sender
+        let tx = transfer();
+        for _ in 0..UNHEALTHY_AFTER_FAILURES {
+            router.send(&[(0, tx.clone())]).await;
+        }
+        assert!((0..100).all(|_| router.select() == [1]));
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::UiTransactionEncoding;
+use std::time::Duration;
+
+pub const DEFAULT_COMPUTE_UNIT_MARGIN: f64 = 1.1;
+
+/// Most compute units a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Offset of the amount in an SPL token account
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+
+/// What simulating one transaction showed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// Change of the wallet's WSOL balance, in lamports
+    pub profit: i64,
+}
+
+/// Runs transactions through `simulateTransaction` before they are sent
+pub struct Simulator {
+    client: RpcClient,
+    compute_unit_margin: f64,
+}
+
+impl Simulator {
+    pub fn new(url: String, timeout: Duration, compute_unit_margin: f64) -> Self {
+        Self {
+            client: RpcClient::new_with_timeout(url, timeout),
+            compute_unit_margin,
+        }
+    }
+
+    /// Simulates `tx` against the latest processed state. The profit is the change of the
+    /// `wallet_sol_account` token balance, which is where the executor settles.
+    pub async fn simulate(
+        &self,
+        tx: &VersionedTransaction,
+        wallet_sol_account: &Pubkey,
+    ) -> Result<SimulationReport> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: false,
+            commitment: Some(CommitmentConfig::processed()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: vec![wallet_sol_account.to_string()],
+            }),
+            ..Default::default()
+        };
+        let (before, simulation) = tokio::join!(
+            self.client
+                .get_account_with_commitment(wallet_sol_account, CommitmentConfig::processed()),
+            self.client.simulate_transaction_with_config(tx, config),
+        );
+        let before = before?
+            .value
+            .map_or(Ok(0), |account| token_amount(&account.data))?;
+        let simulation = simulation?.value;
+
+        let after = match simulation
+            .accounts
+            .as_ref()
+            .and_then(|accounts| accounts.first().cloned().flatten())
+        {
+            Some(account) => token_amount(
+                &account
+                    .data
+                    .decode()
+                    .ok_or_else(|| anyhow!("Simulated WSOL account is not base64 encoded"))?,
+            )?,
+            None => 0,
+        };
+        Ok(SimulationReport {
+            err: simulation.err,
+            logs: simulation.logs.unwrap_or_default(),
+            units_consumed: simulation.units_consumed,
+            profit: (i128::from(after) - i128::from(before)) as i64,
+        })
+    }
+
+    /// Compute unit limit for a transaction that consumed `units_consumed` in simulation
+    pub fn compute_unit_limit(&self, units_consumed: u64) -> u32 {
+        ((units_consumed as f64 * self.compute_unit_margin).ceil() as u64)
+            .min(u64::from(MAX_COMPUTE_UNIT_LIMIT)) as u32
+    }
+}
+
+fn token_amount(data: &[u8]) -> Result<u64> {
+    data.get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)
+        .map(|amount| u64::from_le_bytes(amount.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("WSOL account data is too short"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rpc_stand_in, signed_transaction};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::{json, Value};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+    use solana_sdk::system_instruction;
+
+    fn token_account(amount: u64) -> Value {
+        let mut data = vec![0u8; 165];
+        data[TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+        json!({
+            "data": [STANDARD.encode(data), "base64"],
+            "executable": false,
+            "lamports": 2_039_280,
+            "owner": spl_token::ID.to_string(),
+            "rentEpoch": 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_simulation_reports_profit_and_units() {
+        let node = rpc_stand_in(Duration::ZERO, |request| {
+            match request["method"].as_str().unwrap() {
+                "getAccountInfo" => json!({
+                    "context": { "slot": 10 },
+                    "value": token_account(1_000_000),
+                }),
+                "simulateTransaction" => json!({
+                    "context": { "slot": 10 },
+                    "value": {
+                        "err": null,
+                        "logs": ["Program log: profit 25000"],
+                        "accounts": [token_account(1_025_000)],
+                        "unitsConsumed": 181_000,
+                        "returnData": null,
+                    },
+                }),
+                method => panic!("Unexpected {}", method),
+            }
+        })
+        .await;
+        let simulator = Simulator::new(node.url.clone(), Duration::from_secs(1), 1.5);
+
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let tx = signed_transaction(&payer, &[transfer], Hash::default());
+
+        let report = simulator
+            .simulate(&tx, &Pubkey::new_unique())
+            .await
+            .unwrap();
+        assert_eq!(report.err, None);
+        assert_eq!(report.profit, 25_000);
+        assert_eq!(report.units_consumed, Some(181_000));
+        assert_eq!(report.logs, ["Program log: profit 25000"]);
+        assert_eq!(simulator.compute_unit_limit(181_000), 271_500);
+        assert_eq!(
+            simulator.compute_unit_limit(2_000_000),
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+    }
+}
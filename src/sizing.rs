@@ -0,0 +1,286 @@
+use crate::clock::ChainClock;
+use crate::pools::MintPoolData;
+use crate::quote::{clock_sysvar_account, AccountMap, PoolQuoter, SwapDirection};
+use crate::state_cache::StateCache;
+use anyhow::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 1 / golden ratio in parts per billion
+const INV_PHI_PPB: u128 = 618_033_989;
+const PPB: u128 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SizingParams {
+    /// Smallest SOL input considered, in lamports
+    pub min_amount_in: u64,
+    /// Largest SOL input considered, in lamports
+    pub max_amount_in: u64,
+    /// The search stops once the bracket is this narrow, in lamports
+    pub tolerance: u64,
+    pub max_iterations: usize,
+}
+
+impl Default for SizingParams {
+    fn default() -> Self {
+        Self {
+            min_amount_in: 1_000_000,
+            max_amount_in: 100_000_000_000,
+            tolerance: 1_000,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Best round trip found between two pools: buy the token on `buy_pool`, sell it on
+/// `sell_pool`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opportunity {
+    pub buy_pool: Pubkey,
+    pub sell_pool: Pubkey,
+    pub amount_in: u64,
+    pub token_amount: u64,
+    pub amount_out: u64,
+    /// amount_out - amount_in, in lamports. Negative when no size is profitable.
+    pub profit: i64,
+}
+
+/// Quote SOL -> token on `buy` then token -> SOL on `sell`. Returns the intermediate token
+/// amount and the SOL out, or None when either leg cannot be quoted.
+pub fn quote_round_trip(
+    buy: &dyn PoolQuoter,
+    sell: &dyn PoolQuoter,
+    accounts: &AccountMap,
+    amount_in: u64,
+) -> Option<(u64, u64)> {
+    let token_amount = buy
+        .quote_exact_in(accounts, amount_in, SwapDirection::SolToToken)
+        .ok()?
+        .amount_out;
+    if token_amount == 0 {
+        return None;
+    }
+    let amount_out = sell
+        .quote_exact_in(accounts, token_amount, SwapDirection::TokenToSol)
+        .ok()?
+        .amount_out;
+    Some((token_amount, amount_out))
+}
+
+/// Golden-section search for the SOL input maximizing the profit of a round trip.
+///
+/// Round-trip profit is concave in the input size, so the search narrows a bracket over
+/// integer lamport amounts and finishes by scanning what is left. Sizes that cannot be
+/// quoted, for example because they exhaust the loaded liquidity, count as losses so the
+/// bracket moves away from them.
+pub fn optimal_trade_size(
+    buy: &dyn PoolQuoter,
+    sell: &dyn PoolQuoter,
+    accounts: &AccountMap,
+    params: &SizingParams,
+) -> Option<Opportunity> {
+    if params.min_amount_in == 0 || params.min_amount_in > params.max_amount_in {
+        return None;
+    }
+
+    let mut evaluated: HashMap<u64, Option<(u64, u64)>> = HashMap::new();
+    let mut profit_at = |amount_in: u64| -> i128 {
+        let result = *evaluated
+            .entry(amount_in)
+            .or_insert_with(|| quote_round_trip(buy, sell, accounts, amount_in));
+        match result {
+            Some((_, amount_out)) => i128::from(amount_out) - i128::from(amount_in),
+            None => i128::MIN,
+        }
+    };
+
+    let interior = |lo: u64, hi: u64| -> (u64, u64) {
+        let offset = (u128::from(hi - lo) * INV_PHI_PPB / PPB) as u64;
+        (hi - offset, lo + offset)
+    };
+
+    let tolerance = params.tolerance.max(2);
+    let (mut lo, mut hi) = (params.min_amount_in, params.max_amount_in);
+    let (mut left, mut right) = interior(lo, hi);
+    let mut left_profit = profit_at(left);
+    let mut right_profit = profit_at(right);
+
+    for _ in 0..params.max_iterations {
+        if hi - lo <= tolerance {
+            break;
+        }
+        if left_profit < right_profit {
+            lo = left;
+            left = right;
+            left_profit = right_profit;
+            right = lo + (u128::from(hi - lo) * INV_PHI_PPB / PPB) as u64;
+            right_profit = profit_at(right);
+        } else {
+            hi = right;
+            right = left;
+            right_profit = left_profit;
+            left = hi - (u128::from(hi - lo) * INV_PHI_PPB / PPB) as u64;
+            left_profit = profit_at(left);
+        }
+        // Integer rounding can make the interior points meet
+        if left >= right {
+            break;
+        }
+    }
+
+    // Pick the best of the final bracket, sampling it evenly when it is still wide
+    let step = ((hi - lo) / 16).max(1);
+    let mut candidates: Vec<u64> = (0..=16u64)
+        .map(|i| lo.saturating_add(i * step).min(hi))
+        .chain([left, right, params.min_amount_in, params.max_amount_in])
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    let best = candidates
+        .into_iter()
+        .max_by_key(|amount_in| (profit_at(*amount_in), Reverse(*amount_in)))?;
+
+    let (token_amount, amount_out) = evaluated.get(&best).copied().flatten()?;
+    Some(Opportunity {
+        buy_pool: buy.pool_address(),
+        sell_pool: sell.pool_address(),
+        amount_in: best,
+        token_amount,
+        amount_out,
+        profit: (i128::from(amount_out) - i128::from(best)) as i64,
+    })
+}
+
+impl MintPoolData {
+    /// Optimal size for every ordered pair of distinct pools, most profitable first.
+    /// Pairs that cannot be quoted at any size are left out.
+    pub fn find_opportunities(
+        &self,
+        accounts: &AccountMap,
+        params: &SizingParams,
+    ) -> Vec<Opportunity> {
+        let quoters = self.quoters();
+        let mut opportunities = Vec::new();
+        for buy in &quoters {
+            for sell in &quoters {
+                if buy.pool_address() == sell.pool_address() {
+                    continue;
+                }
+                if let Some(opportunity) = optimal_trade_size(*buy, *sell, accounts, params) {
+                    opportunities.push(opportunity);
+                }
+            }
+        }
+        opportunities.sort_by_key(|opportunity| Reverse(opportunity.profit));
+        opportunities
+    }
+
+    /// Profit of the best round trip at the cached on-chain state and the current cluster
+    /// time, or None when no pair can be quoted
+    pub async fn expected_profit(
+        &self,
+        cache: &StateCache,
+        rpc_client: &Arc<RpcClient>,
+        clock: &ChainClock,
+        params: &SizingParams,
+    ) -> Result<Option<i64>> {
+        let mut accounts = cache.accounts(rpc_client, &self.quote_accounts()).await?;
+        if let Some(sysvar) = clock.sysvar() {
+            accounts.insert(sysvar::clock::id(), clock_sysvar_account(&sysvar));
+        }
+        Ok(self
+            .find_opportunities(&accounts, params)
+            .first()
+            .map(|opportunity| opportunity.profit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pools::SolfiPool;
+    use solana_sdk::account::Account;
+
+    /// Constant product pool with a 0.3% fee over its two vault balances
+    fn pool(accounts: &mut AccountMap, sol: u64, token: u64) -> SolfiPool {
+        let pool = SolfiPool {
+            pool: Pubkey::new_unique(),
+            token_x_vault: Pubkey::new_unique(),
+            token_sol_vault: Pubkey::new_unique(),
+        };
+        for (vault, amount) in [(pool.token_sol_vault, sol), (pool.token_x_vault, token)] {
+            let mut data = vec![0; 165];
+            data[64..72].copy_from_slice(&amount.to_le_bytes());
+            accounts.insert(
+                vault,
+                Account {
+                    data,
+                    ..Account::default()
+                },
+            );
+        }
+        pool
+    }
+
+    #[test]
+    fn test_optimal_size_between_constant_product_pools() {
+        let mut accounts = AccountMap::new();
+        // The token costs 0.5 SOL on the first pool and sells for 0.55 on the second
+        let cheap = pool(&mut accounts, 1_000_000_000_000, 2_000_000_000_000);
+        let dear = pool(&mut accounts, 1_100_000_000_000, 2_000_000_000_000);
+        let params = SizingParams::default();
+
+        // The two legs compose into one constant product pool with reserves X and Y and
+        // fee factor g = 0.997, which is most profitable at (sqrt(g * X * Y) - X) / g,
+        // about 22.934 SOL for a profit of 1_047_236_224 lamports
+        let opportunity = optimal_trade_size(&cheap, &dear, &accounts, &params).unwrap();
+        assert_eq!(opportunity.buy_pool, cheap.pool);
+        assert_eq!(opportunity.sell_pool, dear.pool);
+        assert!(opportunity.amount_in.abs_diff(22_934_312_012) < 20_000_000);
+        assert!((1_047_236_200..=1_047_236_224).contains(&opportunity.profit));
+        assert_eq!(
+            opportunity.profit,
+            opportunity.amount_out as i64 - opportunity.amount_in as i64
+        );
+
+        // Below the optimum the search ends up against the largest size allowed
+        let capped = SizingParams {
+            max_amount_in: 10_000_000_000,
+            ..params
+        };
+        let opportunity = optimal_trade_size(&cheap, &dear, &accounts, &capped).unwrap();
+        assert!(opportunity.amount_in.abs_diff(10_000_000_000) <= params.tolerance);
+    }
+
+    #[test]
+    fn test_find_opportunities_most_profitable_first() {
+        let mut accounts = AccountMap::new();
+        let cheap = pool(&mut accounts, 1_000_000_000_000, 2_000_000_000_000);
+        let dear = pool(&mut accounts, 1_100_000_000_000, 2_000_000_000_000);
+        let mut mint_pool_data = MintPoolData::new(
+            &Pubkey::new_unique().to_string(),
+            &Pubkey::new_unique().to_string(),
+            spl_token::ID,
+        )
+        .unwrap();
+        mint_pool_data.solfi_pools = vec![dear.clone(), cheap.clone()];
+
+        let opportunities = mint_pool_data.find_opportunities(&accounts, &SizingParams::default());
+        assert_eq!(opportunities.len(), 2);
+        assert_eq!(
+            (opportunities[0].buy_pool, opportunities[0].sell_pool),
+            (cheap.pool, dear.pool)
+        );
+        assert!(opportunities[0].profit > 0);
+        // Going the other way loses, least at the smallest size
+        assert!(opportunities[1].profit < 0);
+        assert_eq!(
+            opportunities[1].amount_in,
+            SizingParams::default().min_amount_in
+        );
+    }
+}
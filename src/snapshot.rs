@@ -0,0 +1,75 @@
+use arc_swap::ArcSwap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// A value as it was published at `version`
+#[derive(Debug)]
+pub struct Snapshot<T> {
+    pub version: u64,
+    value: T,
+}
+
+impl<T> Deref for Snapshot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Publishes a value as immutable, versioned snapshots.
+///
+/// Readers take the current snapshot without locking and keep it for as long as they
+/// need. Writers derive the next snapshot from the current one and swap it in.
+pub struct SnapshotCell<T> {
+    current: ArcSwap<Snapshot<T>>,
+    /// Serializes writers, so no update is derived from a stale snapshot
+    writer: Mutex<()>,
+}
+
+impl<T> SnapshotCell<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(Snapshot { version: 1, value }),
+            writer: Mutex::new(()),
+        }
+    }
+
+    pub fn load(&self) -> Arc<Snapshot<T>> {
+        self.current.load_full()
+    }
+
+    /// Publishes what `update` derives from the current snapshot, unless it returns None.
+    /// Returns the new version.
+    pub fn update(&self, update: impl FnOnce(&T) -> Option<T>) -> Option<u64> {
+        let _writer = self.writer.lock().unwrap();
+        let current = self.load();
+        let value = update(&current)?;
+        let version = current.version + 1;
+        self.current.store(Arc::new(Snapshot { version, value }));
+        Some(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readers_keep_their_snapshot() {
+        let cell = SnapshotCell::new(vec![1]);
+        let first = cell.load();
+        assert_eq!(first.version, 1);
+
+        assert_eq!(
+            cell.update(|current| Some([current.as_slice(), &[2]].concat())),
+            Some(2)
+        );
+        assert_eq!(cell.update(|_| None), None);
+
+        let second = cell.load();
+        assert_eq!((second.version, second.as_slice()), (2, &[1, 2][..]));
+        // Taken before the update, so unchanged
+        assert_eq!((first.version, first.as_slice()), (1, &[1][..]));
+    }
+}
@@ -0,0 +1,398 @@
+use crate::quote::AccountMap;
+use crate::reconnect::subscribe_or_poll;
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use futures::stream::{SelectAll, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+use tracing::{debug, error, info};
+
+/// Accounts per `getMultipleAccounts` request
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Polling interval of the polling source, and while another source is down
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An account as of the slot it was observed at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedAccount {
+    pub account: Account,
+    pub slot: u64,
+}
+
+/// Latest state of every pool, vault, tick or bin array and config account the bot
+/// reads, kept current over `accountSubscribe`.
+///
+/// Updates are applied in slot order, so a poll answered by a lagging node never
+/// overwrites a newer notification. Tasks that react to state changes subscribe to the
+/// addresses of updated accounts. Updates come from an [`AccountSource`].
+pub struct StateCache {
+    accounts: RwLock<HashMap<Pubkey, CachedAccount>>,
+    watched: Mutex<BTreeSet<Pubkey>>,
+    watch_added: Notify,
+    updates: broadcast::Sender<Pubkey>,
+}
+
+impl Default for StateCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateCache {
+    pub fn new() -> Self {
+        Self {
+            accounts: RwLock::new(HashMap::new()),
+            watched: Mutex::new(BTreeSet::new()),
+            watch_added: Notify::new(),
+            updates: broadcast::channel(1024).0,
+        }
+    }
+
+    /// Keeps `keys` current from now on
+    pub fn watch(&self, keys: impl IntoIterator<Item = Pubkey>) {
+        let mut watched = self.watched.lock().unwrap();
+        let before = watched.len();
+        watched.extend(keys);
+        if watched.len() > before {
+            self.watch_added.notify_one();
+        }
+    }
+
+    pub fn watched(&self) -> Vec<Pubkey> {
+        self.watched.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Resolves once more accounts are watched, or right away when some were added since
+    /// the last call
+    pub async fn watch_added(&self) {
+        self.watch_added.notified().await;
+    }
+
+    /// Receives the address of every account whose state changed. Slow receivers skip
+    /// ahead rather than block updates.
+    pub fn subscribe(&self) -> broadcast::Receiver<Pubkey> {
+        self.updates.subscribe()
+    }
+
+    pub fn get(&self, key: &Pubkey) -> Option<CachedAccount> {
+        self.accounts.read().unwrap().get(key).cloned()
+    }
+
+    /// Records `account` as observed at `slot`, None meaning it doesn't exist. Returns
+    /// whether the state changed: updates older than the cached one are ignored.
+    pub fn update(&self, key: Pubkey, account: Option<Account>, slot: u64) -> bool {
+        let mut accounts = self.accounts.write().unwrap();
+        let cached = accounts.get(&key);
+        if cached.is_some_and(|cached| cached.slot > slot) {
+            return false;
+        }
+        let changed = cached.map(|cached| &cached.account) != account.as_ref();
+        match account {
+            Some(account) => {
+                accounts.insert(key, CachedAccount { account, slot });
+            }
+            None => {
+                accounts.remove(&key);
+            }
+        }
+        drop(accounts);
+        if changed {
+            // Nobody listening is fine
+            let _ = self.updates.send(key);
+        }
+        changed
+    }
+
+    /// Fetches `keys` in batches, at processed commitment. The blocking client runs off
+    /// the async workers.
+    pub async fn poll(&self, rpc_client: &Arc<RpcClient>, keys: &[Pubkey]) -> Result<()> {
+        for chunk in keys.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let rpc_client = rpc_client.clone();
+            let chunk = chunk.to_vec();
+            let (chunk, response) = tokio::task::spawn_blocking(move || {
+                let response = rpc_client
+                    .get_multiple_accounts_with_commitment(&chunk, CommitmentConfig::processed());
+                (chunk, response)
+            })
+            .await?;
+            let response = response?;
+            for (key, account) in chunk.iter().zip(response.value) {
+                self.update(*key, account, response.context.slot);
+            }
+        }
+        Ok(())
+    }
+
+    /// Cached state of `keys`. Accounts not watched yet are fetched and watched from now
+    /// on; accounts that don't exist are left out.
+    pub async fn accounts(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        keys: &[Pubkey],
+    ) -> Result<AccountMap> {
+        let unwatched: Vec<Pubkey> = {
+            let watched = self.watched.lock().unwrap();
+            keys.iter()
+                .filter(|key| !watched.contains(key))
+                .copied()
+                .collect()
+        };
+        if !unwatched.is_empty() {
+            self.poll(rpc_client, &unwatched).await?;
+            self.watch(unwatched);
+        }
+        let accounts = self.accounts.read().unwrap();
+        Ok(keys
+            .iter()
+            .filter_map(|key| Some((*key, accounts.get(key)?.account.clone())))
+            .collect())
+    }
+}
+
+/// A feed of account updates into the cache. The decoders in `src/dex` only read cached
+/// accounts, so sources can be swapped without touching them.
+pub trait AccountSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Keeps every watched account current, including accounts watched later on, until
+    /// the feed fails
+    fn follow<'a>(&'a self, cache: &'a StateCache) -> BoxFuture<'a, Result<()>>;
+}
+
+/// `getMultipleAccounts` for every watched account, on an interval
+pub struct PollingSource {
+    rpc_client: Arc<RpcClient>,
+    interval: Duration,
+}
+
+impl PollingSource {
+    pub fn new(rpc_client: Arc<RpcClient>, interval: Duration) -> Self {
+        Self {
+            rpc_client,
+            interval,
+        }
+    }
+}
+
+impl AccountSource for PollingSource {
+    fn name(&self) -> &str {
+        "polling"
+    }
+
+    /// Never fails, failed polls are logged and retried
+    fn follow<'a>(&'a self, cache: &'a StateCache) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            loop {
+                if let Err(e) = cache.poll(&self.rpc_client, &cache.watched()).await {
+                    error!("Failed to poll accounts: {:?}", e);
+                }
+                tokio::time::sleep(self.interval).await;
+            }
+        })
+    }
+}
+
+/// `accountSubscribe` for every watched account
+pub struct WebsocketSource {
+    ws_url: String,
+    /// Loads the current state of newly subscribed accounts
+    rpc_client: Arc<RpcClient>,
+}
+
+impl WebsocketSource {
+    pub fn new(ws_url: String, rpc_client: Arc<RpcClient>) -> Self {
+        Self { ws_url, rpc_client }
+    }
+
+    async fn follow_accounts(&self, cache: &StateCache) -> Result<()> {
+        let client = PubsubClient::new(&self.ws_url).await?;
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::processed()),
+            ..Default::default()
+        };
+        let mut subscribed = BTreeSet::new();
+        let mut notifications = SelectAll::new();
+        loop {
+            let added: Vec<Pubkey> = cache
+                .watched()
+                .into_iter()
+                .filter(|key| !subscribed.contains(key))
+                .collect();
+            if !added.is_empty() {
+                for key in &added {
+                    let (stream, _unsubscribe) =
+                        client.account_subscribe(key, Some(config.clone())).await?;
+                    let key = *key;
+                    notifications.push(stream.map(move |response| (key, response)));
+                    subscribed.insert(key);
+                }
+                // Notifications only start with the next change
+                cache.poll(&self.rpc_client, &added).await?;
+                info!(
+                    "Subscribed to {} accounts at {}",
+                    subscribed.len(),
+                    self.ws_url
+                );
+            }
+
+            tokio::select! {
+                notification = notifications.next(), if !notifications.is_empty() => {
+                    let Some((key, response)) = notification else {
+                        return Err(anyhow!("Websocket closed"));
+                    };
+                    match response.value.decode::<Account>() {
+                        Some(account) => {
+                            cache.update(key, Some(account), response.context.slot);
+                        }
+                        None => debug!("Undecodable notification for {}", key),
+                    }
+                }
+                _ = cache.watch_added() => {}
+            }
+        }
+    }
+}
+
+impl AccountSource for WebsocketSource {
+    fn name(&self) -> &str {
+        "websocket"
+    }
+
+    fn follow<'a>(&'a self, cache: &'a StateCache) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.follow_accounts(cache))
+    }
+}
+
+/// Feeds the cache from `source`, polling through `fallback` for a while whenever the
+/// source fails
+pub async fn account_subscriber(
+    source: Arc<dyn AccountSource>,
+    fallback: PollingSource,
+    cache: Arc<StateCache>,
+) {
+    let name = format!("{} account updates", source.name());
+    subscribe_or_poll(
+        &name,
+        || source.follow(&cache),
+        || async {
+            // Polling never fails
+            let _ = fallback.follow(&cache).await;
+        },
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{pubsub_stand_in, rpc_stand_in};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn ui_account(data: &[u8]) -> Value {
+        json!({
+            "data": [STANDARD.encode(data), "base64"],
+            "executable": false,
+            "lamports": 1_000_000,
+            "owner": spl_token::ID.to_string(),
+            "rentEpoch": 0,
+        })
+    }
+
+    async fn wait_for(cache: &StateCache, key: &Pubkey, slot: u64) -> CachedAccount {
+        for _ in 0..200 {
+            if let Some(cached) = cache.get(key).filter(|cached| cached.slot == slot) {
+                return cached;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} never reached slot {}", key, slot);
+    }
+
+    #[test]
+    fn test_updates_apply_in_slot_order() {
+        let cache = StateCache::new();
+        let mut updates = cache.subscribe();
+        let key = Pubkey::new_unique();
+        let account = |lamports| Account {
+            lamports,
+            ..Account::default()
+        };
+
+        assert!(cache.update(key, Some(account(1)), 10));
+        assert!(!cache.update(key, Some(account(2)), 9));
+        // The same state seen again is not a change
+        assert!(!cache.update(key, Some(account(1)), 11));
+        assert_eq!(cache.get(&key).unwrap().slot, 11);
+        assert!(cache.update(key, None, 12));
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(updates.try_recv().unwrap(), key);
+        assert_eq!(updates.try_recv().unwrap(), key);
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_follows_notifications_and_polls_on_disconnect() {
+        let key = Pubkey::new_unique();
+        // The node answers polls with the data `[slot]`
+        let slot = Arc::new(AtomicU64::new(5));
+        let node_slot = slot.clone();
+        let node = rpc_stand_in(Duration::ZERO, move |request| {
+            match request["method"].as_str().unwrap() {
+                "getMultipleAccounts" => {
+                    let slot = node_slot.load(Ordering::Relaxed);
+                    json!({
+                        "context": { "slot": slot },
+                        "value": [ui_account(&[slot as u8])],
+                    })
+                }
+                method => panic!("Unexpected {}", method),
+            }
+        })
+        .await;
+        let mut pubsub = pubsub_stand_in().await;
+
+        let cache = Arc::new(StateCache::new());
+        cache.watch([key]);
+        let rpc_client = Arc::new(RpcClient::new(node.url.clone()));
+        tokio::spawn(account_subscriber(
+            Arc::new(WebsocketSource::new(pubsub.url.clone(), rpc_client.clone())),
+            PollingSource::new(rpc_client, DEFAULT_POLL_INTERVAL),
+            cache.clone(),
+        ));
+
+        let (subscription, request) = pubsub.subscriptions.recv().await.unwrap();
+        assert_eq!(request["method"], "accountSubscribe");
+        assert_eq!(request["params"][0], key.to_string());
+        assert_eq!(request["params"][1]["encoding"], "base64");
+        assert_eq!(wait_for(&cache, &key, 5).await.account.data, [5]);
+
+        pubsub.notify(
+            subscription,
+            json!({ "context": { "slot": 10 }, "value": ui_account(&[10]) }),
+        );
+        assert_eq!(wait_for(&cache, &key, 10).await.account.data, [10]);
+
+        // Newly watched accounts are subscribed on the open connection
+        let other = Pubkey::new_unique();
+        cache.watch([other]);
+        let (_, request) = pubsub.subscriptions.recv().await.unwrap();
+        assert_eq!(request["params"][0], other.to_string());
+
+        slot.store(20, Ordering::Relaxed);
+        pubsub.disconnect();
+        assert_eq!(wait_for(&cache, &key, 20).await.account.data, [20]);
+    }
+}
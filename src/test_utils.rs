@@ -0,0 +1,212 @@
+//! Local stand-ins for the HTTP and websocket services the bot talks to
+
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+type Handler = dyn Fn(&Value) -> Value + Send + Sync;
+
+pub struct RpcStandIn {
+    pub url: String,
+    /// Bodies of the JSON-RPC requests received so far
+    pub requests: mpsc::UnboundedReceiver<Value>,
+}
+
+/// JSON-RPC server on a local port. Each request is answered after `delay` with the
+/// result `handler` returns for it, except `getVersion`, which the RPC client sends
+/// before its first request and is answered here.
+pub async fn rpc_stand_in<F>(delay: Duration, handler: F) -> RpcStandIn
+where
+    F: Fn(&Value) -> Value + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (requests, received) = mpsc::unbounded_channel();
+    let handler: Arc<Handler> = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve_connection(
+                stream,
+                delay,
+                handler.clone(),
+                requests.clone(),
+            ));
+        }
+    });
+    RpcStandIn {
+        url,
+        requests: received,
+    }
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    delay: Duration,
+    handler: Arc<Handler>,
+    requests: mpsc::UnboundedSender<Value>,
+) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        // Read one request: headers, then a Content-Length body
+        let header_end = loop {
+            if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            }
+        };
+        let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+        let content_length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|len| len.trim().parse().unwrap())
+            .unwrap_or(0);
+        while buffer.len() < header_end + content_length {
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            }
+        }
+        let body: Value =
+            serde_json::from_slice(&buffer[header_end..header_end + content_length]).unwrap();
+        buffer.drain(..header_end + content_length);
+
+        tokio::time::sleep(delay).await;
+        let result = match body["method"].as_str() {
+            Some("getVersion") => json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+            _ => handler(&body),
+        };
+        let response = json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }).to_string();
+        let _ = requests.send(body);
+        let reply = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        if stream.write_all(reply.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// `instructions` in a v0 transaction paid and signed by `payer`
+pub fn signed_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    blockhash: Hash,
+) -> VersionedTransaction {
+    let message = v0::Message::try_compile(&payer.pubkey(), instructions, &[], blockhash).unwrap();
+    VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap()
+}
+
+enum PubsubCommand {
+    Notify(u64, Value),
+    Disconnect,
+}
+
+pub struct PubsubStandIn {
+    pub url: String,
+    /// Subscription requests received so far, with the subscription id each was given
+    pub subscriptions: mpsc::UnboundedReceiver<(u64, Value)>,
+    commands: mpsc::UnboundedSender<PubsubCommand>,
+}
+
+impl PubsubStandIn {
+    /// Sends `result` to `subscription` on the open connection
+    pub fn notify(&self, subscription: u64, result: Value) {
+        let _ = self
+            .commands
+            .send(PubsubCommand::Notify(subscription, result));
+    }
+
+    /// Closes the open connection. The next one is accepted as usual.
+    pub fn disconnect(&self) {
+        let _ = self.commands.send(PubsubCommand::Disconnect);
+    }
+}
+
+/// Websocket JSON-RPC server on a local port that accepts every subscription and only
+/// sends the notifications it is told to. Serves one connection at a time.
+pub async fn pubsub_stand_in() -> PubsubStandIn {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (subscribed, subscriptions) = mpsc::unbounded_channel();
+    let (commands, mut received_commands) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut next_subscription = 0;
+        // Notification method of each subscription
+        let mut methods = HashMap::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                continue;
+            };
+            loop {
+                tokio::select! {
+                    message = ws.next() => {
+                        let text = match message {
+                            Some(Ok(Message::Text(text))) => text,
+                            Some(Ok(_)) => continue,
+                            None | Some(Err(_)) => break,
+                        };
+                        let request: Value = serde_json::from_str(&text).unwrap();
+                        let method = request["method"].as_str().unwrap().to_string();
+                        let result = match method.strip_suffix("Subscribe") {
+                            Some(kind) => {
+                                next_subscription += 1;
+                                methods.insert(next_subscription, format!("{}Notification", kind));
+                                let _ = subscribed.send((next_subscription, request.clone()));
+                                json!(next_subscription)
+                            }
+                            None => json!(true),
+                        };
+                        let response = json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "result": result,
+                        });
+                        if ws.send(Message::Text(response.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    command = received_commands.recv() => match command {
+                        Some(PubsubCommand::Notify(subscription, result)) => {
+                            let notification = json!({
+                                "jsonrpc": "2.0",
+                                "method": methods[&subscription],
+                                "params": { "result": result, "subscription": subscription },
+                            });
+                            if ws.send(Message::Text(notification.to_string())).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(PubsubCommand::Disconnect) => {
+                            let _ = ws.close(None).await;
+                            break;
+                        }
+                        None => return,
+                    },
+                }
+            }
+        }
+    });
+    PubsubStandIn {
+        url,
+        subscriptions,
+        commands,
+    }
+}
@@ -0,0 +1,111 @@
+use crate::sender::TransactionSender;
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonblocking::tpu_client::TpuClient;
+use solana_client::tpu_client::{TpuClientConfig, MAX_FANOUT_SLOTS};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::clock::NUM_CONSECUTIVE_LEADER_SLOTS;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
+
+pub const DEFAULT_LEADERS: u64 = 2;
+
+/// Sends transactions over QUIC straight to the TPU ports of the current leader and the
+/// next few, skipping the RPC node's own forwarding.
+///
+/// The leader schedule and cluster contact info come from the RPC node, and slot updates
+/// from its websocket.
+pub struct TpuSender {
+    name: String,
+    client: TpuClient<QuicPool, QuicConnectionManager, QuicConfig>,
+}
+
+impl TpuSender {
+    /// Sends to the current leader and the next `leaders` after it
+    pub async fn new(name: String, rpc_url: &str, ws_url: &str, leaders: u64) -> Result<Self> {
+        let fanout_slots = ((leaders + 1) * NUM_CONSECUTIVE_LEADER_SLOTS).min(MAX_FANOUT_SLOTS);
+        let client = TpuClient::new(
+            "tpu-sender",
+            Arc::new(RpcClient::new(rpc_url.to_string())),
+            ws_url,
+            TpuClientConfig { fanout_slots },
+        )
+        .await
+        .map_err(|e| anyhow!("Failed to start TPU client: {}", e))?;
+        Ok(Self { name, client })
+    }
+}
+
+impl TransactionSender for TpuSender {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Succeeds when at least one leader accepted the transaction
+    fn send<'a>(&'a self, tx: &'a VersionedTransaction) -> BoxFuture<'a, Result<Signature>> {
+        Box::pin(async move {
+            self.client
+                .try_send_wire_transaction(bincode::serialize(tx)?)
+                .await?;
+            Ok(tx.signatures[0])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::signed_transaction;
+    use solana_sdk::commitment_config::CommitmentConfig;
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+    use solana_sdk::system_instruction;
+    use std::time::Duration;
+
+    /// Needs `solana-test-validator` running with its default ports
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_send_to_local_validator() {
+        let rpc_client = RpcClient::new_with_commitment(
+            "http://127.0.0.1:8899".to_string(),
+            CommitmentConfig::confirmed(),
+        );
+        let payer = Keypair::new();
+        let airdrop = rpc_client
+            .request_airdrop(&payer.pubkey(), LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+        while !rpc_client.confirm_transaction(&airdrop).await.unwrap() {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+
+        let sender = TpuSender::new(
+            "tpu".to_string(),
+            "http://127.0.0.1:8899",
+            "ws://127.0.0.1:8900",
+            1,
+        )
+        .await
+        .unwrap();
+        let recipient = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &recipient, 1_000_000);
+        let tx = signed_transaction(
+            &payer,
+            &[transfer],
+            rpc_client.get_latest_blockhash().await.unwrap(),
+        );
+
+        assert_eq!(sender.send(&tx).await.unwrap(), tx.signatures[0]);
+        for _ in 0..50 {
+            if rpc_client.get_balance(&recipient).await.unwrap() == 1_000_000 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        panic!("Transaction sent over TPU never landed");
+    }
+}
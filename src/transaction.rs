@@ -1,329 +1,393 @@
-use crate::config::Config;
-use crate::dex::raydium::{raydium_authority, raydium_cp_authority};
-use crate::dex::solfi::constants::solfi_program_id;
-use crate::dex::vertigo::constants::vertigo_program_id;
+use crate::bidding::Bid;
+use crate::config::{Config, ProfitConfig};
+use crate::confirmation::ConfirmationTracker;
+use crate::nonce::NonceLease;
 use crate::pools::MintPoolData;
-use solana_client::rpc_client::RpcClient;
+use crate::sender::SenderRouter;
+use crate::simulation::{Simulator, MAX_COMPUTE_UNIT_LIMIT};
+use crate::snapshot::Snapshot;
+use executor_cpi::{ExecutorInstruction, Leg};
 use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
 use solana_sdk::address_lookup_table::AddressLookupTableAccount;
-use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::hash::Hash;
 use solana_sdk::message::v0::Message;
 use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::VersionedTransaction;
-use std::sync::Arc;
-use tracing::{debug, error, info};
-
-use crate::constants::sol_mint;
-use crate::dex::meteora::constants::{
-    damm_program_id, damm_v2_event_authority, damm_v2_pool_authority, damm_v2_program_id,
-    dlmm_event_authority, dlmm_program_id, vault_program_id,
-};
-use crate::dex::pump::constants::{pump_fee_wallet, pump_program_id};
-use crate::dex::raydium::constants::{
-    raydium_clmm_program_id, raydium_cp_program_id, raydium_program_id,
-};
-use crate::dex::whirlpool::constants::whirlpool_program_id;
-use solana_program::instruction::AccountMeta;
-use solana_program::pubkey::Pubkey;
-use solana_program::system_program;
-use spl_associated_token_account::ID as associated_token_program_id;
-use spl_token::ID as token_program_id;
-use std::str::FromStr;
+use tracing::{debug, error, info, warn};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn build_and_send_transaction(
     wallet_kp: &Keypair,
     config: &Config,
-    mint_pool_data: &MintPoolData,
-    rpc_clients: &[Arc<RpcClient>],
+    profit_config: &ProfitConfig,
+    expected_profit: Option<i64>,
+    mint_pool_data: &Snapshot<MintPoolData>,
+    bid: Bid,
+    router: &SenderRouter,
+    tracker: &ConfirmationTracker,
+    simulator: Option<&Simulator>,
+    nonce: Option<&NonceLease<'_>>,
     blockhash: Hash,
     address_lookup_table_accounts: &[AddressLookupTableAccount],
 ) -> anyhow::Result<Vec<Signature>> {
     let enable_flashloan = config.flashloan.as_ref().map_or(false, |k| k.enabled);
-    let compute_unit_limit = config.bot.compute_unit_limit;
-    let mut instructions = vec![];
-    // Add a random number here to make each transaction unique
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(
-        compute_unit_limit + rand::random::<u32>() % 1000,
-    );
-    instructions.push(compute_budget_ix);
-
-    let compute_unit_price = config.spam.as_ref().map_or(1000, |s| s.compute_unit_price);
-    let compute_budget_price_ix =
-        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price);
-    instructions.push(compute_budget_price_ix);
 
-    let swap_ix = create_swap_instruction(
-        wallet_kp,
-        mint_pool_data,
-        compute_unit_limit as u64,
-        enable_flashloan,
-    )?;
-
-    let mut all_instructions = instructions.clone();
+    let routes = router.select();
+    if routes.is_empty() {
+        warn!(
+            "No sender available for mint {}, all are rate limited",
+            mint_pool_data.mint
+        );
+        return Ok(vec![]);
+    }
 
-    debug!("Adding swap instruction");
-    all_instructions.push(swap_ix);
+    // None when the expected profit doesn't cover what landing the transaction costs
+    let build = |compute_unit_limit: u32,
+                 expected_profit: Option<i64>,
+                 tip: Option<(Instruction, u64)>|
+     -> anyhow::Result<Option<VersionedTransaction>> {
+        let mut instructions = vec![];
+        if let Some(nonce) = nonce {
+            instructions.push(nonce.advance_instruction());
+        }
+        // Add a random number here to make each transaction unique
+        let compute_unit_limit =
+            (compute_unit_limit + rand::random::<u32>() % 1000).min(MAX_COMPUTE_UNIT_LIMIT);
+        let compute_budget_ix =
+            ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit);
+        instructions.push(compute_budget_ix);
+
+        let compute_budget_price_ix =
+            ComputeBudgetInstruction::set_compute_unit_price(bid.compute_unit_price);
+        instructions.push(compute_budget_price_ix);
+
+        // The compute unit price is in micro-lamports
+        let priority_fee = (u128::from(bid.compute_unit_price) * u128::from(compute_unit_limit)
+            / 1_000_000) as u64;
+        let tip_lamports = tip.as_ref().map_or(0, |(_, lamports)| *lamports);
+        let minimum_profit = match profit_config.resolve_minimum_profit(
+            expected_profit,
+            priority_fee,
+            tip_lamports,
+        ) {
+            Some(minimum_profit) => minimum_profit,
+            None => {
+                debug!(
+                    "Skipping {}: expected profit {:?} does not cover priority fee {} and tip {}",
+                    mint_pool_data.mint, expected_profit, priority_fee, tip_lamports
+                );
+                return Ok(None);
+            }
+        };
 
-    let message = Message::try_compile(
-        &wallet_kp.pubkey(),
-        &all_instructions,
-        address_lookup_table_accounts,
-        blockhash,
-    )?;
+        let swap_ix = create_swap_instruction(
+            wallet_kp,
+            mint_pool_data,
+            minimum_profit,
+            compute_unit_limit as u64,
+            profit_config.no_failure_mode,
+            enable_flashloan,
+        )?;
 
-    let tx = VersionedTransaction::try_new(
-        solana_sdk::message::VersionedMessage::V0(message),
-        &[wallet_kp],
-    )?;
+        let mut all_instructions = instructions.clone();
 
-    let max_retries = config
-        .spam
-        .as_ref()
-        .and_then(|s| s.max_retries)
-        .unwrap_or(3);
+        debug!("Adding swap instruction");
+        all_instructions.push(swap_ix);
 
-    let mut signatures = Vec::new();
+        if let Some((tip_ix, _)) = tip {
+            all_instructions.push(tip_ix);
+        }
 
-    for (i, client) in rpc_clients.iter().enumerate() {
-        debug!("Sending transaction through RPC client {}", i);
+        let message = Message::try_compile(
+            &wallet_kp.pubkey(),
+            &all_instructions,
+            address_lookup_table_accounts,
+            nonce.map_or(blockhash, |nonce| nonce.value),
+        )?;
+
+        Ok(Some(VersionedTransaction::try_new(
+            solana_sdk::message::VersionedMessage::V0(message),
+            &[wallet_kp],
+        )?))
+    };
+
+    let mut compute_unit_limit = config.bot.compute_unit_limit;
+    let mut expected_profit = expected_profit;
+
+    if let Some(simulator) = simulator {
+        // Tips don't change what the swap does, so the untipped transaction stands in for
+        // every sender
+        let Some(tx) = build(compute_unit_limit, expected_profit, None)? else {
+            return Ok(vec![]);
+        };
+        let simulation = simulator
+            .simulate(&tx, &mint_pool_data.wallet_wsol_account)
+            .await?;
+        if let Some(err) = &simulation.err {
+            debug!(
+                "Skipping {}: simulation failed: {}",
+                mint_pool_data.mint, err
+            );
+            for log in &simulation.logs {
+                debug!("  {}", log);
+            }
+            return Ok(vec![]);
+        }
+        if simulation.profit <= 0 {
+            debug!(
+                "Skipping {}: simulation shows a profit of {}",
+                mint_pool_data.mint, simulation.profit
+            );
+            return Ok(vec![]);
+        }
+        if let Some(units_consumed) = simulation.units_consumed {
+            // Build with just the compute units it needs, priced against the simulated profit
+            compute_unit_limit = simulator.compute_unit_limit(units_consumed);
+            expected_profit = Some(simulation.profit);
+        }
+    }
 
-        let signature = match send_transaction_with_retries(client, &tx, max_retries).await {
-            Ok(sig) => sig,
-            Err(e) => {
-                error!("Failed to send transaction through RPC client {}: {}", i, e);
-                continue;
+    let tip = |route: usize| {
+        let sender = router.sender(route);
+        let lamports = bid.tip_lamports.max(sender.min_tip_lamports()?);
+        sender
+            .tip_instruction(&wallet_kp.pubkey(), lamports)
+            .map(|tip_ix| (tip_ix, lamports))
+    };
+    let takes_tips = |route: &usize| router.sender(*route).min_tip_lamports().is_some();
+
+    let mut transactions = Vec::with_capacity(routes.len());
+    if nonce.is_some() {
+        // Every variant advances the same nonce, so only one of them can execute. Senders
+        // that take no tip share one transaction, the others each get their own carrying
+        // their tip.
+        let mut untipped = None;
+        for route in routes {
+            let tx = if takes_tips(&route) {
+                build(compute_unit_limit, expected_profit, tip(route))?
+            } else {
+                if untipped.is_none() {
+                    untipped = Some(build(compute_unit_limit, expected_profit, None)?);
+                }
+                untipped.clone().flatten()
+            };
+            if let Some(tx) = tx {
+                transactions.push((route, tx));
             }
+        }
+    } else {
+        // Distinct transactions on a blockhash could each land, so a single one goes out,
+        // carrying the tip of the first tipping sender picked. The other tipping senders
+        // sit this one out.
+        let tipped_route = routes.iter().copied().find(takes_tips);
+        let Some(tx) = build(
+            compute_unit_limit,
+            expected_profit,
+            tipped_route.and_then(tip),
+        )?
+        else {
+            return Ok(vec![]);
         };
-
-        info!(
-            "Transaction sent successfully through RPC client {}: {}",
-            i, signature
-        );
-        signatures.push(signature);
+        for route in routes {
+            if takes_tips(&route) && Some(route) != tipped_route {
+                debug!(
+                    "Not sending through {}, the transaction tips another sender",
+                    router.sender(route).name()
+                );
+                continue;
+            }
+            transactions.push((route, tx.clone()));
+        }
+    }
+    if transactions.is_empty() {
+        return Ok(vec![]);
     }
 
-    Ok(signatures)
-}
+    let reports = router.send(&transactions).await;
+    for report in &reports {
+        match &report.result {
+            Ok(signature) => info!(
+                "Transaction sent through {} in {:?}: {}",
+                report.endpoint, report.latency, signature
+            ),
+            Err(e) => error!(
+                "Failed to send transaction through {} after {:?}: {}",
+                report.endpoint, report.latency, e
+            ),
+        }
+    }
 
-async fn send_transaction_with_retries(
-    client: &RpcClient,
-    tx: &VersionedTransaction,
-    max_retries: u64,
-) -> anyhow::Result<Signature> {
-    Ok(client.send_transaction_with_config(
-        tx,
-        solana_client::rpc_config::RpcSendTransactionConfig {
-            skip_preflight: true,
-            max_retries: Some(max_retries as usize),
-            preflight_commitment: Some(CommitmentLevel::Confirmed),
-            ..Default::default()
-        },
-    )?)
-}
+    // Each distinct transaction is tracked with the senders that accepted it
+    let mut signatures: Vec<Signature> = Vec::new();
+    for (_, tx) in &transactions {
+        let signature = tx.signatures[0];
+        if signatures.contains(&signature) {
+            continue;
+        }
+        let endpoints: Vec<String> = transactions
+            .iter()
+            .zip(&reports)
+            .filter(|((_, other), report)| {
+                other.signatures[0] == signature && report.result.is_ok()
+            })
+            .map(|(_, report)| report.endpoint.clone())
+            .collect();
+        if !endpoints.is_empty() {
+            signatures.push(signature);
+            tracker.track(
+                signature,
+                mint_pool_data.mint,
+                mint_pool_data.version,
+                endpoints,
+                nonce.map(|nonce| (nonce.account, nonce.value)),
+            );
+        }
+    }
+    if !signatures.is_empty() {
+        if let Some(nonce) = nonce {
+            nonce.mark_sent();
+        }
+    }
 
-/// Helper function to derive the vault token account PDA address for a given mint
-pub fn derive_vault_token_account(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[b"vault_token_account", mint.as_ref()], program_id)
+    Ok(signatures)
 }
 
 // See https://docs.solanamevbot.com/home/onchain-bot/onchain-program for more information
 fn create_swap_instruction(
     wallet_kp: &Keypair,
     mint_pool_data: &MintPoolData,
+    minimum_profit: u64,
     compute_unit_limit: u64,
+    no_failure_mode: bool,
     use_flashloan: bool,
 ) -> anyhow::Result<Instruction> {
     debug!("Creating swap instruction for all DEX types");
 
-    let executor_program_id =
-        Pubkey::from_str("MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz").unwrap();
-    let fee_collector = Pubkey::from_str("6AGB9kqgSp2mQXwYpdrV4QVV8urvCaDS35U1wsLssy6H").unwrap();
-
-    let pump_global_config =
-        Pubkey::from_str("ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw").unwrap();
-    let pump_authority = Pubkey::from_str("GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR").unwrap();
-    let sysvar_instructions =
-        Pubkey::from_str("Sysvar1nstructions1111111111111111111111111").unwrap();
-
     let wallet = wallet_kp.pubkey();
-    let sol_mint_pubkey = sol_mint();
-    let wallet_sol_account = mint_pool_data.wallet_wsol_account;
-
-    let mut accounts = vec![
-        AccountMeta::new_readonly(wallet, true), // 0. Wallet (signer)
-        AccountMeta::new_readonly(sol_mint_pubkey, false), // 1. SOL mint
-        AccountMeta::new(fee_collector, false),  // 2. Fee collector
-        AccountMeta::new(wallet_sol_account, false), // 3. Wallet SOL account
-        AccountMeta::new_readonly(token_program_id, false), // 4. Token program
-        AccountMeta::new_readonly(system_program::ID, false), // 5. System program
-        AccountMeta::new_readonly(associated_token_program_id, false), // 6. Associated Token program
-    ];
-
-    let base_mint = sol_mint_pubkey;
-
-    if use_flashloan {
-        accounts.push(AccountMeta::new_readonly(
-            Pubkey::from_str("5LFpzqgsxrSfhKwbaFiAEJ2kbc9QyimjKueswsyU4T3o").unwrap(),
-            false,
-        ));
-        let token_pda = derive_vault_token_account(
-            &Pubkey::from_str("MEViEnscUm6tsQRoGd9h6nLQaQspKj7DB2M5FwM3Xvz").unwrap(),
-            &base_mint,
-        );
-        accounts.push(AccountMeta::new(token_pda.0, false));
-    }
-
-    accounts.push(AccountMeta::new_readonly(mint_pool_data.mint, false));
-    accounts.push(AccountMeta::new_readonly(
-        mint_pool_data.token_program,
-        false,
-    )); // Token program (SPL Token or Token 2022)
     let wallet_x_account =
         spl_associated_token_account::get_associated_token_address_with_program_id(
             &wallet,
             &mint_pool_data.mint,
             &mint_pool_data.token_program,
         );
-    accounts.push(AccountMeta::new(wallet_x_account, false));
 
-    for pool in &mint_pool_data.raydium_pools {
-        accounts.push(AccountMeta::new_readonly(raydium_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(raydium_authority(), false)); // Raydium authority
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.token_vault, false));
-        accounts.push(AccountMeta::new(pool.sol_vault, false));
-    }
-
-    for pool in &mint_pool_data.raydium_cp_pools {
-        accounts.push(AccountMeta::new_readonly(raydium_cp_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(raydium_cp_authority(), false)); // Raydium CP authority
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new_readonly(pool.amm_config, false));
-        accounts.push(AccountMeta::new(pool.token_vault, false));
-        accounts.push(AccountMeta::new(pool.sol_vault, false));
-        accounts.push(AccountMeta::new(pool.observation, false));
-    }
-
-    for pool in &mint_pool_data.pump_pools {
-        accounts.push(AccountMeta::new_readonly(pump_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(pump_global_config, false));
-        accounts.push(AccountMeta::new_readonly(pump_authority, false));
-        accounts.push(AccountMeta::new_readonly(pump_fee_wallet(), false));
-        accounts.push(AccountMeta::new_readonly(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.token_vault, false));
-        accounts.push(AccountMeta::new(pool.sol_vault, false));
-        accounts.push(AccountMeta::new(pool.fee_token_wallet, false));
-        accounts.push(AccountMeta::new(pool.coin_creator_vault_ata, false));
-        accounts.push(AccountMeta::new_readonly(
-            pool.coin_creator_vault_authority,
-            false,
-        ));
-    }
-
-    for pair in &mint_pool_data.dlmm_pairs {
-        accounts.push(AccountMeta::new_readonly(dlmm_program_id(), false));
-        accounts.push(AccountMeta::new(dlmm_event_authority(), false)); // DLMM event authority
-        if let Some(memo_program) = pair.memo_program {
-            accounts.push(AccountMeta::new_readonly(memo_program, false)); // Token 2022 memo program
-        }
-        accounts.push(AccountMeta::new(pair.pair, false));
-        accounts.push(AccountMeta::new(pair.token_vault, false));
-        accounts.push(AccountMeta::new(pair.sol_vault, false));
-        accounts.push(AccountMeta::new(pair.oracle, false));
-        for bin_array in &pair.bin_arrays {
-            accounts.push(AccountMeta::new(*bin_array, false));
-        }
-    }
+    Ok(ExecutorInstruction::new(
+        wallet,
+        mint_pool_data.wallet_wsol_account,
+        mint_pool_data.mint,
+        mint_pool_data.token_program,
+        wallet_x_account,
+    )
+    .minimum_profit(minimum_profit)
+    .compute_unit_limit(compute_unit_limit)
+    .no_failure_mode(no_failure_mode)
+    .flashloan(use_flashloan)
+    .legs(mint_pool_data.legs())
+    .instruction())
+}
 
-    for pool in &mint_pool_data.whirlpool_pools {
-        accounts.push(AccountMeta::new_readonly(whirlpool_program_id(), false));
-        if let Some(memo_program) = pool.memo_program {
-            accounts.push(AccountMeta::new_readonly(memo_program, false)); // Token 2022 memo program
-        }
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.oracle, false));
-        accounts.push(AccountMeta::new(pool.x_vault, false));
-        accounts.push(AccountMeta::new(pool.y_vault, false));
-        for tick_array in &pool.tick_arrays {
-            accounts.push(AccountMeta::new(*tick_array, false));
-        }
+impl MintPoolData {
+    /// Executor legs for every pool, grouped by DEX in the order the program expects
+    pub fn legs(&self) -> Vec<Leg> {
+        let mut legs = Vec::new();
+        legs.extend(self.raydium_pools.iter().map(|pool| Leg::RaydiumAmm {
+            pool: pool.pool,
+            token_vault: pool.token_vault,
+            sol_vault: pool.sol_vault,
+        }));
+        legs.extend(self.raydium_cp_pools.iter().map(|pool| Leg::RaydiumCp {
+            pool: pool.pool,
+            amm_config: pool.amm_config,
+            token_vault: pool.token_vault,
+            sol_vault: pool.sol_vault,
+            observation: pool.observation,
+        }));
+        legs.extend(self.pump_pools.iter().map(|pool| Leg::Pump {
+            pool: pool.pool,
+            token_vault: pool.token_vault,
+            sol_vault: pool.sol_vault,
+            fee_token_wallet: pool.fee_token_wallet,
+            coin_creator_vault_ata: pool.coin_creator_vault_ata,
+            coin_creator_vault_authority: pool.coin_creator_vault_authority,
+        }));
+        legs.extend(self.dlmm_pairs.iter().map(|pair| Leg::Dlmm {
+            memo_program: pair.memo_program,
+            pair: pair.pair,
+            token_vault: pair.token_vault,
+            sol_vault: pair.sol_vault,
+            oracle: pair.oracle,
+            bin_arrays: pair.bin_arrays.clone(),
+        }));
+        legs.extend(self.whirlpool_pools.iter().map(|pool| Leg::Whirlpool {
+            memo_program: pool.memo_program,
+            pool: pool.pool,
+            oracle: pool.oracle,
+            x_vault: pool.x_vault,
+            y_vault: pool.y_vault,
+            tick_arrays: pool.tick_arrays.clone(),
+        }));
+        legs.extend(self.raydium_clmm_pools.iter().map(|pool| Leg::RaydiumClmm {
+            memo_program: pool.memo_program,
+            pool: pool.pool,
+            amm_config: pool.amm_config,
+            observation_state: pool.observation_state,
+            bitmap_extension: pool.bitmap_extension,
+            x_vault: pool.x_vault,
+            y_vault: pool.y_vault,
+            tick_arrays: pool.tick_arrays.clone(),
+        }));
+        legs.extend(self.meteora_damm_pools.iter().map(|pool| Leg::MeteoraDamm {
+            pool: pool.pool,
+            token_x_vault: pool.token_x_vault,
+            token_sol_vault: pool.token_sol_vault,
+            token_x_token_vault: pool.token_x_token_vault,
+            token_sol_token_vault: pool.token_sol_token_vault,
+            token_x_lp_mint: pool.token_x_lp_mint,
+            token_sol_lp_mint: pool.token_sol_lp_mint,
+            token_x_pool_lp: pool.token_x_pool_lp,
+            token_sol_pool_lp: pool.token_sol_pool_lp,
+            admin_token_fee_x: pool.admin_token_fee_x,
+            admin_token_fee_sol: pool.admin_token_fee_sol,
+        }));
+        legs.extend(
+            self.meteora_damm_v2_pools
+                .iter()
+                .map(|pool| Leg::MeteoraDammV2 {
+                    pool: pool.pool,
+                    token_x_vault: pool.token_x_vault,
+                    token_sol_vault: pool.token_sol_vault,
+                }),
+        );
+        legs.extend(self.solfi_pools.iter().map(|pool| Leg::Solfi {
+            pool: pool.pool,
+            token_x_vault: pool.token_x_vault,
+            token_sol_vault: pool.token_sol_vault,
+        }));
+        legs.extend(self.vertigo_pools.iter().map(|pool| Leg::Vertigo {
+            pool: pool.pool,
+            pool_owner: pool.pool_owner,
+            token_x_vault: pool.token_x_vault,
+            token_sol_vault: pool.token_sol_vault,
+        }));
+        legs
     }
 
-    for pool in &mint_pool_data.raydium_clmm_pools {
-        accounts.push(AccountMeta::new_readonly(raydium_clmm_program_id(), false));
-        if let Some(memo_program) = pool.memo_program {
-            accounts.push(AccountMeta::new_readonly(memo_program, false)); // Token 2022 memo program
+    /// Accounts the swap writes, other than the wallet's own
+    pub fn writable_pool_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = Vec::new();
+        for leg in self.legs() {
+            leg.append_account_metas(&mut accounts);
         }
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new_readonly(pool.amm_config, false));
-        accounts.push(AccountMeta::new(pool.observation_state, false));
-        accounts.push(AccountMeta::new(pool.bitmap_extension, false));
-        accounts.push(AccountMeta::new(pool.x_vault, false));
-        accounts.push(AccountMeta::new(pool.y_vault, false));
-        for tick_array in &pool.tick_arrays {
-            accounts.push(AccountMeta::new(*tick_array, false));
-        }
-    }
-
-    for pool in &mint_pool_data.meteora_damm_pools {
-        accounts.push(AccountMeta::new_readonly(damm_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(vault_program_id(), false));
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.token_x_vault, false));
-        accounts.push(AccountMeta::new(pool.token_sol_vault, false));
-        accounts.push(AccountMeta::new(pool.token_x_token_vault, false));
-        accounts.push(AccountMeta::new(pool.token_sol_token_vault, false));
-        accounts.push(AccountMeta::new(pool.token_x_lp_mint, false));
-        accounts.push(AccountMeta::new(pool.token_sol_lp_mint, false));
-        accounts.push(AccountMeta::new(pool.token_x_pool_lp, false));
-        accounts.push(AccountMeta::new(pool.token_sol_pool_lp, false));
-        accounts.push(AccountMeta::new(pool.admin_token_fee_x, false));
-        accounts.push(AccountMeta::new(pool.admin_token_fee_sol, false));
-    }
-
-    for pool in &mint_pool_data.meteora_damm_v2_pools {
-        accounts.push(AccountMeta::new_readonly(damm_v2_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(damm_v2_event_authority(), false));
-        accounts.push(AccountMeta::new_readonly(damm_v2_pool_authority(), false));
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.token_x_vault, false));
-        accounts.push(AccountMeta::new(pool.token_sol_vault, false));
-    }
-
-    for pool in &mint_pool_data.solfi_pools {
-        accounts.push(AccountMeta::new_readonly(solfi_program_id(), false));
-        accounts.push(AccountMeta::new_readonly(sysvar_instructions, false));
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new(pool.token_x_vault, false));
-        accounts.push(AccountMeta::new(pool.token_sol_vault, false));
-    }
-
-    for pool in &mint_pool_data.vertigo_pools {
-        accounts.push(AccountMeta::new_readonly(vertigo_program_id(), false));
-        accounts.push(AccountMeta::new(pool.pool, false));
-        accounts.push(AccountMeta::new_readonly(pool.pool_owner, false));
-        accounts.push(AccountMeta::new(pool.token_x_vault, false));
-        accounts.push(AccountMeta::new(pool.token_sol_vault, false));
+        let mut writable: Vec<Pubkey> = accounts
+            .into_iter()
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect();
+        writable.sort();
+        writable.dedup();
+        writable
     }
-
-    let mut data = vec![26u8];
-
-    let minimum_profit: u64 = 0;
-    // When true, the bot will not fail the transaction even when it can't find a profitable arbitrage. It will just do nothing and succeed.
-    let no_failure_mode = false;
-
-    data.extend_from_slice(&minimum_profit.to_le_bytes());
-    data.extend_from_slice(&compute_unit_limit.to_le_bytes());
-    data.extend_from_slice(if no_failure_mode { &[1] } else { &[0] });
-    data.extend_from_slice(&0u16.to_le_bytes()); // Keep this 0.
-    data.extend_from_slice(if use_flashloan { &[1] } else { &[0] });
-
-    Ok(Instruction {
-        program_id: executor_program_id,
-        accounts,
-        data,
-    })
 }
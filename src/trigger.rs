@@ -0,0 +1,122 @@
+use crate::state_cache::StateCache;
+use solana_program::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+
+/// Why a mint is being evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// An account the mint's quotes read changed
+    StateChanged,
+    /// Nothing changed for a whole heartbeat
+    Heartbeat,
+}
+
+/// Decides when a mint is evaluated: whenever one of its accounts changes in the state
+/// cache, and optionally on a heartbeat while nothing does
+pub struct MintTrigger {
+    /// Keeps the update channel open
+    _cache: Arc<StateCache>,
+    updates: broadcast::Receiver<Pubkey>,
+    accounts: HashSet<Pubkey>,
+    heartbeat: Option<Duration>,
+    min_interval: Duration,
+    last: Instant,
+}
+
+impl MintTrigger {
+    /// `min_interval` is the least time between two evaluations. Changes within it are
+    /// evaluated together once it has passed.
+    pub fn new(
+        cache: Arc<StateCache>,
+        accounts: impl IntoIterator<Item = Pubkey>,
+        heartbeat: Option<Duration>,
+        min_interval: Duration,
+    ) -> Self {
+        Self {
+            updates: cache.subscribe(),
+            _cache: cache,
+            accounts: accounts.into_iter().collect(),
+            heartbeat,
+            min_interval,
+            last: Instant::now(),
+        }
+    }
+
+    /// Replaces the accounts to react to, as tick or bin arrays move with the price
+    pub fn set_accounts(&mut self, accounts: impl IntoIterator<Item = Pubkey>) {
+        self.accounts = accounts.into_iter().collect();
+    }
+
+    /// Waits until the mint should be evaluated next
+    pub async fn next(&mut self) -> Trigger {
+        tokio::time::sleep_until(self.last + self.min_interval).await;
+        let heartbeat_at = self.heartbeat.map(|heartbeat| self.last + heartbeat);
+        let heartbeat = async move {
+            match heartbeat_at {
+                Some(heartbeat_at) => tokio::time::sleep_until(heartbeat_at).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(heartbeat);
+
+        let trigger = loop {
+            tokio::select! {
+                update = self.updates.recv() => match update {
+                    Ok(key) if self.accounts.contains(&key) => break Trigger::StateChanged,
+                    Ok(_) => {}
+                    // Some updates were skipped, any of them may have been ours
+                    Err(_) => break Trigger::StateChanged,
+                },
+                _ = &mut heartbeat => break Trigger::Heartbeat,
+            }
+        };
+        // One evaluation covers every change so far
+        while self.updates.try_recv().is_ok() {}
+        self.last = Instant::now();
+        trigger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::account::Account;
+
+    #[tokio::test]
+    async fn test_fires_on_own_accounts_and_heartbeat() {
+        let cache = Arc::new(StateCache::new());
+        let ours = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let heartbeat = Duration::from_millis(300);
+        let mut trigger = MintTrigger::new(
+            cache.clone(),
+            [ours],
+            Some(heartbeat),
+            Duration::from_millis(10),
+        );
+        let account = |lamports| Account {
+            lamports,
+            ..Account::default()
+        };
+
+        cache.update(other, Some(account(1)), 1);
+        cache.update(ours, Some(account(1)), 1);
+        let started = Instant::now();
+        assert_eq!(trigger.next().await, Trigger::StateChanged);
+        assert!(started.elapsed() < heartbeat);
+
+        // Changes to other mints' accounts don't count
+        cache.update(other, Some(account(2)), 2);
+        let started = Instant::now();
+        assert_eq!(trigger.next().await, Trigger::Heartbeat);
+        assert!(started.elapsed() >= heartbeat - Duration::from_millis(50));
+
+        trigger.set_accounts([other]);
+        cache.update(other, Some(account(3)), 3);
+        assert_eq!(trigger.next().await, Trigger::StateChanged);
+    }
+}